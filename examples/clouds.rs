@@ -0,0 +1,42 @@
+use bevy::prelude::*;
+use bevy_atmosphere::prelude::*;
+use bevy_spectator::{Spectator, SpectatorPlugin};
+
+fn main() {
+    println!("Demonstrates procedural clouds over the sky\n\t- Up/Down: Change coverage\n\t- C: Toggle between cumulus and stratus\n\t- Space: Move the sun");
+    App::new()
+        .insert_resource(AtmosphereClouds::default()) // Clouds drifting with the wind, over the default Nishita model
+        .add_plugins((DefaultPlugins, AtmospherePlugin, SpectatorPlugin))
+        .add_systems(Startup, setup)
+        .add_systems(Update, (change_clouds, move_sun))
+        .run();
+}
+
+fn setup(mut commands: Commands) {
+    commands.spawn((
+        Camera3d::default(),
+        Transform::from_xyz(0.0, 0.0, 0.0).looking_at(Vec3::new(0.0, 0.3, -1.0), Vec3::Y),
+        AtmosphereCamera::default(),
+        Spectator,
+    ));
+}
+
+fn change_clouds(mut clouds: ResMut<AtmosphereClouds>, keys: Res<ButtonInput<KeyCode>>) {
+    if keys.just_pressed(KeyCode::ArrowUp) {
+        clouds.coverage = (clouds.coverage + 0.1).min(1.0);
+    }
+    if keys.just_pressed(KeyCode::ArrowDown) {
+        clouds.coverage = (clouds.coverage - 0.1).max(0.0);
+    }
+    if keys.just_pressed(KeyCode::KeyC) {
+        clouds.cumulus = if clouds.cumulus > 0.5 { 0.0 } else { 1.0 };
+    }
+}
+
+fn move_sun(mut atmosphere: AtmosphereMut<Nishita>, keys: Res<ButtonInput<KeyCode>>) {
+    if keys.just_pressed(KeyCode::Space) {
+        // The clouds are lit from the model's `sun_position`
+        let angle = atmosphere.sun_position.y.atan2(atmosphere.sun_position.z) + 0.3;
+        atmosphere.sun_position = Vec3::new(0.0, angle.sin(), angle.cos());
+    }
+}
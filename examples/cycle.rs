@@ -1,14 +1,17 @@
-use bevy::{pbr::light_consts::lux::AMBIENT_DAYLIGHT, prelude::*};
+use bevy::prelude::*;
 use bevy_atmosphere::prelude::*;
 use bevy_spectator::{Spectator, SpectatorPlugin};
 
 fn main() {
     App::new()
         .insert_resource(AtmosphereModel::default()) // Default Atmosphere material, we can edit it to simulate another planet
-        .insert_resource(CycleTimer(Timer::new(
-            std::time::Duration::from_millis(50), // Update our atmosphere every 50ms (in a real game, this would be much slower, but for the sake of an example we use a faster update)
-            TimerMode::Repeating,
-        )))
+        .insert_resource(AtmosphereSettings {
+            // Rendering the whole sky every frame is slow, so spread each update over a few frames
+            update_mode: AtmosphereUpdateMode::TimeSliced {
+                slices_per_frame: 1,
+            },
+            ..default()
+        })
         .add_plugins((
             DefaultPlugins,
             SpectatorPlugin,  // Simple movement for this example
@@ -19,32 +22,13 @@ fn main() {
         .run();
 }
 
-// Marker for updating the position of the light, not needed unless we have multiple lights
-#[derive(Component)]
-struct Sun;
-
-// Timer for updating the daylight cycle (updating the atmosphere every frame is slow, so it's better to do incremental changes)
-#[derive(Resource)]
-struct CycleTimer(Timer);
-
 // We can edit the Atmosphere resource and it will be updated automatically
-fn daylight_cycle(
-    mut atmosphere: AtmosphereMut<Nishita>,
-    mut query: Query<(&mut Transform, &mut DirectionalLight), With<Sun>>,
-    mut timer: ResMut<CycleTimer>,
-    time: Res<Time>,
-) {
-    timer.0.tick(time.delta());
-
-    if timer.0.finished() {
-        let t = time.elapsed_secs_wrapped() / 2.0;
-        atmosphere.sun_position = Vec3::new(0., t.sin(), t.cos());
-
-        if let Some(Ok((mut light_trans, mut directional))) = query.single_mut().into() {
-            light_trans.rotation = Quat::from_rotation_x(-t);
-            directional.illuminance = t.sin().max(0.0).powf(2.0) * AMBIENT_DAYLIGHT;
-        }
-    }
+// (`AtmosphereSun` takes care of moving and coloring the light)
+fn daylight_cycle(mut atmosphere: AtmosphereMut<Nishita>, time: Res<Time>) {
+    let t = time.elapsed_secs_wrapped() / 2.0;
+    atmosphere.sun_position = Vec3::new(0., t.sin(), t.cos());
+    // Keep a full moon opposite the sun, so it rises as the sun sets and the stars come out
+    atmosphere.moon_position = -atmosphere.sun_position;
 }
 
 // Simple environment
@@ -56,7 +40,7 @@ fn setup_environment(
     // Our Sun
     commands.spawn((
         DirectionalLight::default(),
-        Sun, // Marks the light as Sun
+        AtmosphereSun::default(), // Keeps the light's rotation, color and illuminance in sync with the atmosphere
     ));
 
     // Simple transform shape just for reference
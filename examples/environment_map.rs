@@ -0,0 +1,64 @@
+use bevy::prelude::*;
+use bevy_atmosphere::prelude::*;
+use bevy_spectator::{Spectator, SpectatorPlugin};
+
+fn main() {
+    println!("Demonstrates lighting PBR materials with the sky\n\t- Space: Move the sun");
+    App::new()
+        .add_plugins((
+            DefaultPlugins,
+            AtmospherePlugin,
+            AtmosphereEnvironmentMapPlugin::default(), // Adds an `EnvironmentMapLight` to every `AtmosphereCamera`
+            SpectatorPlugin,
+        ))
+        .add_systems(Startup, setup)
+        .add_systems(Update, move_sun)
+        .run();
+}
+
+fn setup(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    // A row of spheres, from smooth to rough, to show the specular mips
+    let sphere = meshes.add(Sphere::new(0.45));
+    for i in 0..=4 {
+        commands.spawn((
+            Mesh3d(sphere.clone()),
+            MeshMaterial3d(materials.add(StandardMaterial {
+                base_color: Color::WHITE,
+                metallic: 1.0,
+                perceptual_roughness: i as f32 / 4.0,
+                ..default()
+            })),
+            Transform::from_xyz(i as f32 - 2.0, 0.0, 0.0),
+        ));
+    }
+
+    // A rough dielectric sphere, to show the diffuse map
+    commands.spawn((
+        Mesh3d(sphere),
+        MeshMaterial3d(materials.add(StandardMaterial {
+            base_color: Color::WHITE,
+            perceptual_roughness: 1.0,
+            ..default()
+        })),
+        Transform::from_xyz(0.0, 1.0, 0.0),
+    ));
+
+    commands.spawn((
+        Camera3d::default(),
+        Transform::from_xyz(0.0, 0.5, 5.0).looking_at(Vec3::ZERO, Vec3::Y),
+        AtmosphereCamera::default(),
+        Spectator,
+    ));
+}
+
+fn move_sun(mut atmosphere: AtmosphereMut<Nishita>, keys: Res<ButtonInput<KeyCode>>) {
+    if keys.just_pressed(KeyCode::Space) {
+        // The environment map is regenerated whenever the sky is
+        let angle = atmosphere.sun_position.y.atan2(atmosphere.sun_position.z) + 0.3;
+        atmosphere.sun_position = Vec3::new(0.0, angle.sin(), angle.cos());
+    }
+}
@@ -1,9 +1,11 @@
+use std::time::Duration;
+
 use bevy::prelude::*;
 use bevy_atmosphere::prelude::*;
 use bevy_spectator::{Spectator, SpectatorPlugin};
 
 fn main() {
-    println!("Demonstrates changing the atmosphere model\n\t- G: Gradient\n\t- N: Nishita");
+    println!("Demonstrates cross-fading between atmosphere models\n\t- G: Gradient\n\t- H: Hillaire\n\t- N: Nishita");
 
     App::new()
         .add_plugins((DefaultPlugins, AtmospherePlugin, SpectatorPlugin))
@@ -12,6 +14,9 @@ fn main() {
         .run();
 }
 
+/// How long the sky takes to fade into another model.
+const FADE: Duration = Duration::from_secs(1);
+
 fn setup(mut commands: Commands) {
     commands.spawn((Camera3d::default(), AtmosphereCamera::default(), Spectator));
 }
@@ -19,10 +24,13 @@ fn setup(mut commands: Commands) {
 fn change_model(mut commands: Commands, keys: Res<ButtonInput<KeyCode>>) {
     if keys.just_pressed(KeyCode::KeyG) {
         info!("Changed to Gradient atmosphere model");
-        commands.insert_resource(AtmosphereModel::new(Gradient::default()));
+        commands.insert_resource(AtmosphereTransition::new(Gradient::default(), FADE));
+    } else if keys.just_pressed(KeyCode::KeyH) {
+        info!("Changed to Hillaire atmosphere model");
+        commands.insert_resource(AtmosphereTransition::new(Hillaire::default(), FADE));
     } else if keys.just_pressed(KeyCode::KeyN) {
         info!("Changed to Nishita atmosphere model");
-        commands.insert_resource(AtmosphereModel::new(Nishita::default()));
+        commands.insert_resource(AtmosphereTransition::new(Nishita::default(), FADE));
     } else if keys.just_pressed(KeyCode::Digit0) {
         info!("Reset atmosphere model to default");
         commands.remove_resource::<AtmosphereModel>();
@@ -0,0 +1,45 @@
+use bevy::prelude::*;
+use bevy_atmosphere::prelude::*;
+use bevy_spectator::{Spectator, SpectatorPlugin};
+
+fn main() {
+    println!("Demonstrates loading models from `.atmosphere.ron` files\n\t- 1: Nishita preset\n\t- 2: Gradient preset");
+    App::new()
+        .add_plugins((
+            // Run with `--features bevy/file_watcher` to see edits to the presets while the example is running
+            DefaultPlugins,
+            AtmospherePlugin,
+            SpectatorPlugin,
+        ))
+        .add_systems(Startup, setup)
+        .add_systems(Update, change_preset)
+        .run();
+}
+
+#[derive(Resource)]
+struct Presets([Handle<AtmospherePreset>; 2]);
+
+fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let presets = [
+        asset_server.load("presets/nishita.atmosphere.ron"),
+        asset_server.load("presets/gradient.atmosphere.ron"),
+    ];
+
+    // The global `AtmosphereModel` follows this preset, and is replaced whenever the file changes
+    commands.insert_resource(AtmospherePresetHandle(presets[0].clone()));
+    commands.insert_resource(Presets(presets));
+
+    commands.spawn((Camera3d::default(), AtmosphereCamera::default(), Spectator));
+}
+
+fn change_preset(
+    keys: Res<ButtonInput<KeyCode>>,
+    presets: Res<Presets>,
+    mut handle: ResMut<AtmospherePresetHandle>,
+) {
+    if keys.just_pressed(KeyCode::Digit1) {
+        handle.0 = presets.0[0].clone();
+    } else if keys.just_pressed(KeyCode::Digit2) {
+        handle.0 = presets.0[1].clone();
+    }
+}
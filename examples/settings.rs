@@ -3,7 +3,7 @@ use bevy_atmosphere::prelude::*;
 use bevy_spectator::{Spectator, SpectatorPlugin};
 
 fn main() {
-    println!("Demonstrates using the `AtmosphereSettings` resource\n\t- Spacebar: Toggle dithering\n\t- 1-9 number keys: Change resolution\n\t- 0 number key: Remove `AtmosphereSettings` resource");
+    println!("Demonstrates using the `AtmosphereSettings` resource\n\t- Spacebar: Toggle dithering\n\t- P: Cycle projection\n\t- M: Toggle mipmaps\n\t- 1-9 number keys: Change resolution\n\t- 0 number key: Remove `AtmosphereSettings` resource");
     App::new()
         .insert_resource(AtmosphereSettings {
             resolution: 16,
@@ -37,6 +37,31 @@ fn change_resolution(
             });
         }
         info!("Toggled dithering");
+    } else if keys.just_pressed(KeyCode::KeyP) {
+        let projection = match settings.as_ref().map(|settings| settings.projection) {
+            None | Some(AtmosphereProjection::Cubemap) => AtmosphereProjection::Equirectangular,
+            Some(AtmosphereProjection::Equirectangular) => AtmosphereProjection::Octahedral,
+            Some(AtmosphereProjection::Octahedral) => AtmosphereProjection::Cubemap,
+        };
+        if let Some(mut settings) = settings {
+            settings.projection = projection;
+        } else {
+            commands.insert_resource(AtmosphereSettings {
+                projection,
+                ..default()
+            });
+        }
+        info!("Changed projection to {projection:?}");
+    } else if keys.just_pressed(KeyCode::KeyM) {
+        if let Some(mut settings) = settings {
+            settings.mipmaps ^= true;
+        } else {
+            commands.insert_resource(AtmosphereSettings {
+                mipmaps: true,
+                ..default()
+            });
+        }
+        info!("Toggled mipmaps");
     } else if keys.just_pressed(KeyCode::Digit1) {
         change(commands, settings, 8); // 8x8
     } else if keys.just_pressed(KeyCode::Digit2) {
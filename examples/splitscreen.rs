@@ -10,7 +10,7 @@ use bevy_atmosphere::prelude::*;
 use bevy_spectator::{Spectator, SpectatorPlugin, SpectatorSettings};
 
 fn main() {
-    println!("Demonstrates using `AtmosphereCamera.render_layers` to have multiple skyboxes in the scene at once\n\t- E: Switch camera");
+    println!("Demonstrates using `AtmosphereCamera.render_layers` to have multiple skyboxes in the scene at once\n\t- E: Switch camera\n\t- S: Toggle the sunset on the right camera");
     App::new()
         .insert_resource(AtmosphereModel::new(Nishita {
             rayleigh_coefficient: Vec3::new(22.4e-6, 5.5e-6, 13.0e-6), // Change rayleigh coefficient to change color
@@ -25,6 +25,7 @@ fn main() {
         .add_systems(Startup, setup)
         .add_systems(Update, set_camera_viewports)
         .add_systems(Update, switch_camera)
+        .add_systems(Update, toggle_sunset)
         .run();
 }
 
@@ -87,11 +88,20 @@ fn setup(
         AtmosphereCamera {
             render_layers: Some(RenderLayers::layer(2)),
         },
+        AtmosphereModel::new(sunset()), // Gives the right camera a sky of its own, in this case, a sunset
         RightCamera,
         Spectator,
     ));
 }
 
+// The sky used by the right camera
+fn sunset() -> Nishita {
+    Nishita {
+        sun_position: Vec3::new(0.0, 0.05, -1.0),
+        ..default()
+    }
+}
+
 #[derive(Component)]
 struct LeftCamera;
 
@@ -153,3 +163,25 @@ fn switch_camera(
         println!("Switched camera");
     }
 }
+
+fn toggle_sunset(
+    mut commands: Commands,
+    keys: Res<ButtonInput<KeyCode>>,
+    right_camera: Query<(Entity, Has<AtmosphereModel>), With<RightCamera>>,
+) {
+    let Ok((right_camera, has_model)) = right_camera.single() else {
+        return;
+    };
+
+    if keys.just_pressed(KeyCode::KeyS) {
+        if has_model {
+            // Without its own model, the camera goes back to using the global sky
+            commands.entity(right_camera).remove::<AtmosphereModel>();
+        } else {
+            commands
+                .entity(right_camera)
+                .insert(AtmosphereModel::new(sunset()));
+        }
+        println!("Toggled sunset");
+    }
+}
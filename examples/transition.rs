@@ -0,0 +1,52 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy_atmosphere::prelude::*;
+use bevy_spectator::{Spectator, SpectatorPlugin};
+
+fn main() {
+    println!("Demonstrates smoothly changing the sky with `AtmosphereTransition`\n\t- Space: Toggle between a clear and a stormy sky");
+    App::new()
+        .add_plugins((DefaultPlugins, AtmospherePlugin, SpectatorPlugin))
+        .add_systems(Startup, setup)
+        .add_systems(Update, toggle_weather)
+        .run();
+}
+
+fn setup(mut commands: Commands) {
+    commands.spawn((Camera3d::default(), AtmosphereCamera::default(), Spectator));
+}
+
+fn clear() -> Nishita {
+    Nishita {
+        sun_position: Vec3::new(0.0, 0.4, -1.0),
+        ..default()
+    }
+}
+
+fn stormy() -> Nishita {
+    Nishita {
+        sun_position: Vec3::new(0.0, 0.4, -1.0),
+        sun_intensity: 8.0,
+        mie_coefficient: 1e-4,
+        mie_direction: 0.3,
+        rayleigh_coefficient: Vec3::new(1e-5, 1.1e-5, 1.3e-5),
+        ..default()
+    }
+}
+
+fn toggle_weather(
+    mut commands: Commands,
+    keys: Res<ButtonInput<KeyCode>>,
+    mut is_stormy: Local<bool>,
+) {
+    if keys.just_pressed(KeyCode::Space) {
+        *is_stormy = !*is_stormy;
+        let target = if *is_stormy { stormy() } else { clear() };
+        // Every `f32`, `Vec3` and `LinearRgba` field of the model is interpolated over 3 seconds
+        commands.insert_resource(
+            AtmosphereTransition::new(target, Duration::from_secs(3))
+                .with_easing(EaseFunction::CubicInOut),
+        );
+    }
+}
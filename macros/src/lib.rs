@@ -15,7 +15,7 @@ pub(crate) fn bevy_atmosphere_path() -> syn::Path {
     }
 }
 
-#[proc_macro_derive(Atmospheric, attributes(external, internal, uniform, texture, sampler))]
+#[proc_macro_derive(Atmospheric, attributes(external, internal, pass, uniform, texture, sampler))]
 pub fn derive_atmospheric(input: TokenStream) -> TokenStream {
     let input = parse_macro_input!(input as DeriveInput);
 
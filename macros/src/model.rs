@@ -14,6 +14,7 @@ const SAMPLER_ATTRIBUTE_NAME: Symbol = Symbol("sampler");
 
 const EXTERNAL_ATTRIBUTE_NAME: Symbol = Symbol("external");
 const INTERNAL_ATTRIBUTE_NAME: Symbol = Symbol("internal");
+const PASS_ATTRIBUTE_NAME: Symbol = Symbol("pass");
 
 #[derive(Copy, Clone, Debug)]
 enum BindingType {
@@ -48,6 +49,8 @@ pub fn derive_atmospheric(ast: syn::DeriveInput) -> Result<TokenStream> {
     let render_path = manifest.get_path("bevy_render");
     let asset_path = manifest.get_path("bevy_asset");
     let ecs_path = manifest.get_path("bevy_ecs");
+    let math_path = manifest.get_path("bevy_math");
+    let image_path = manifest.get_path("bevy_image");
 
     let id = {
         use std::collections::hash_map::DefaultHasher;
@@ -60,8 +63,11 @@ pub fn derive_atmospheric(ast: syn::DeriveInput) -> Result<TokenStream> {
     let mut shader_path = ShaderPathType::None;
     let mut binding_states: Vec<BindingState> = Vec::new();
     let mut binding_impls = Vec::new();
+    let mut image_view_impls = Vec::new();
+    let mut uniform_impls = Vec::new();
     let mut bind_group_entries = Vec::new();
     let mut binding_layouts = Vec::new();
+    let mut passes = Vec::new();
 
     // Read struct-level attributes
     for attr in &ast.attrs {
@@ -69,18 +75,13 @@ pub fn derive_atmospheric(ast: syn::DeriveInput) -> Result<TokenStream> {
             if attr_ident == UNIFORM_ATTRIBUTE_NAME {
                 let (binding_index, converted_shader_type) = get_uniform_binding_attr(attr)?;
 
-                binding_impls.push(quote! {{
+                let uniform_index = uniform_impls.len();
+                uniform_impls.push(quote! {{
                     use #render_path::render_resource::AsBindGroupShaderType;
                     let mut buffer = #render_path::render_resource::encase::UniformBuffer::new(Vec::new());
                     let converted: #converted_shader_type = self.as_bind_group_shader_type(images);
                     buffer.write(&converted).unwrap();
-                    #render_path::render_resource::OwnedBindingResource::Buffer(render_device.create_buffer_with_data(
-                        &#render_path::render_resource::BufferInitDescriptor {
-                            label: None,
-                            usage: #render_path::render_resource::BufferUsages::COPY_DST | #render_path::render_resource::BufferUsages::UNIFORM,
-                            contents: buffer.as_ref(),
-                        },
-                    ))
+                    buffer.into_inner()
                 }});
 
                 binding_layouts.push(quote!{
@@ -96,11 +97,10 @@ pub fn derive_atmospheric(ast: syn::DeriveInput) -> Result<TokenStream> {
                     }
                 });
 
-                let binding_vec_index = bind_group_entries.len();
                 bind_group_entries.push(quote! {
                     #render_path::render_resource::BindGroupEntry {
                         binding: #binding_index,
-                        resource: bindings[#binding_vec_index].get_binding(),
+                        resource: uniform_buffers[#uniform_index].as_entire_binding(),
                     }
                 });
 
@@ -126,6 +126,8 @@ pub fn derive_atmospheric(ast: syn::DeriveInput) -> Result<TokenStream> {
                     let lit_str = get_shader_path_attr(attr)?;
 
                     shader_path = ShaderPathType::Internal(lit_str);
+                } else if attr_ident == PASS_ATTRIBUTE_NAME {
+                    passes.push(get_pass_attr(attr)?);
                 }
             }
         }
@@ -204,7 +206,7 @@ pub fn derive_atmospheric(ast: syn::DeriveInput) -> Result<TokenStream> {
                         _ => {
                             // only populate bind group entries for non-uniforms
                             // uniform entries are deferred until the end
-                            let binding_vec_index = bind_group_entries.len();
+                            let binding_vec_index = binding_impls.len();
                             bind_group_entries.push(quote! {
                                 #render_path::render_resource::BindGroupEntry {
                                     binding: #binding_index,
@@ -260,17 +262,28 @@ pub fn derive_atmospheric(ast: syn::DeriveInput) -> Result<TokenStream> {
                     let visibility =
                         visibility.hygienic_quote(&quote! { #render_path::render_resource });
 
+                    let fallback = dimension.fallback_image_field();
                     binding_impls.push(quote! {
-                        #render_path::render_resource::OwnedBindingResource::TextureView({
-                            let handle: Option<&#asset_path::Handle<#render_path::texture::GpuImage>> = (&self.#field_name).into();
-                            if let Some(handle) = handle {
-                                images.get(handle).ok_or_else(|| #render_path::render_resource::AsBindGroupError::RetryNextUpdate)?.texture_view.clone()
-                            } else {
-                                fallback_image.texture_view.clone()
-                            }
-                        })
+                        #render_path::render_resource::OwnedBindingResource::TextureView(
+                            #render_path::render_resource::#dimension,
+                            {
+                                let handle: Option<&#asset_path::Handle<#image_path::Image>> = (&self.#field_name).into();
+                                // images that aren't loaded yet are bound to the fallback, until they are
+                                match handle.and_then(|handle| images.get(handle)) {
+                                    Some(image) => image.texture_view.clone(),
+                                    None => fallback_image.#fallback.texture_view.clone(),
+                                }
+                            },
+                        )
                     });
 
+                    image_view_impls.push(quote! {{
+                        let handle: Option<&#asset_path::Handle<#image_path::Image>> = (&self.#field_name).into();
+                        handle
+                            .and_then(|handle| images.get(handle))
+                            .map(|image| image.texture_view.id())
+                    }});
+
                     binding_layouts.push(quote! {
                         #render_path::render_resource::BindGroupLayoutEntry {
                             binding: #binding_index,
@@ -294,14 +307,16 @@ pub fn derive_atmospheric(ast: syn::DeriveInput) -> Result<TokenStream> {
                         visibility.hygienic_quote(&quote! { #render_path::render_resource });
 
                     binding_impls.push(quote! {
-                        #render_path::render_resource::OwnedBindingResource::Sampler({
-                            let handle: Option<&#asset_path::Handle<#render_path::texture::GpuImage>> = (&self.#field_name).into();
-                            if let Some(handle) = handle {
-                                images.get(handle).ok_or_else(|| #render_path::render_resource::AsBindGroupError::RetryNextUpdate)?.sampler.clone()
-                            } else {
-                                fallback_image.sampler.clone()
-                            }
-                        })
+                        #render_path::render_resource::OwnedBindingResource::Sampler(
+                            #render_path::render_resource::#sampler_binding_type,
+                            {
+                                let handle: Option<&#asset_path::Handle<#image_path::Image>> = (&self.#field_name).into();
+                                match handle.and_then(|handle| images.get(handle)) {
+                                    Some(image) => image.sampler.clone(),
+                                    None => fallback_image.d2.sampler.clone(),
+                                }
+                            },
+                        )
                     });
 
                     binding_layouts.push(quote!{
@@ -323,11 +338,11 @@ pub fn derive_atmospheric(ast: syn::DeriveInput) -> Result<TokenStream> {
     for (binding_index, binding_state) in binding_states.iter().enumerate() {
         let binding_index = binding_index as u32;
         if let BindingState::OccupiedMergeableUniform { uniform_fields } = binding_state {
-            let binding_vec_index = bind_group_entries.len();
+            let uniform_index = uniform_impls.len();
             bind_group_entries.push(quote! {
                 #render_path::render_resource::BindGroupEntry {
                     binding: #binding_index,
-                    resource: bindings[#binding_vec_index].get_binding(),
+                    resource: uniform_buffers[#uniform_index].as_entire_binding(),
                 }
             });
             // single field uniform bindings for a given index can use a straightforward binding
@@ -335,16 +350,10 @@ pub fn derive_atmospheric(ast: syn::DeriveInput) -> Result<TokenStream> {
                 let field = &uniform_fields[0];
                 let field_name = field.ident.as_ref().unwrap();
                 let field_ty = &field.ty;
-                binding_impls.push(quote! {{
+                uniform_impls.push(quote! {{
                     let mut buffer = #render_path::render_resource::encase::UniformBuffer::new(Vec::new());
                     buffer.write(&self.#field_name).unwrap();
-                    #render_path::render_resource::OwnedBindingResource::Buffer(render_device.create_buffer_with_data(
-                        &#render_path::render_resource::BufferInitDescriptor {
-                            label: None,
-                            usage: #render_path::render_resource::BufferUsages::COPY_DST | #render_path::render_resource::BufferUsages::UNIFORM,
-                            contents: buffer.as_ref(),
-                        },
-                    ))
+                    buffer.into_inner()
                 }});
 
                 binding_layouts.push(quote!{
@@ -376,18 +385,12 @@ pub fn derive_atmospheric(ast: syn::DeriveInput) -> Result<TokenStream> {
                 });
 
                 let field_name = uniform_fields.iter().map(|f| f.ident.as_ref().unwrap());
-                binding_impls.push(quote! {{
+                uniform_impls.push(quote! {{
                     let mut buffer = #render_path::render_resource::encase::UniformBuffer::new(Vec::new());
                     buffer.write(&#uniform_struct_name {
                         #(#field_name: &self.#field_name,)*
                     }).unwrap();
-                    #render_path::render_resource::OwnedBindingResource::Buffer(render_device.create_buffer_with_data(
-                        &#render_path::render_resource::BufferInitDescriptor {
-                            label: None,
-                            usage: #render_path::render_resource::BufferUsages::COPY_DST | #render_path::render_resource::BufferUsages::UNIFORM,
-                            contents: buffer.as_ref(),
-                        },
-                    ))
+                    buffer.into_inner()
                 }});
 
                 binding_layouts.push(quote!{
@@ -406,6 +409,11 @@ pub fn derive_atmospheric(ast: syn::DeriveInput) -> Result<TokenStream> {
         }
     }
 
+    let pass_count = passes.len() as u32;
+    let pass_entry_points = passes.iter().map(|pass| &pass.entry_point);
+    let pass_widths = passes.iter().map(|pass| pass.width);
+    let pass_heights = passes.iter().map(|pass| pass.height);
+
     let generics = ast.generics;
     let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
 
@@ -413,14 +421,22 @@ pub fn derive_atmospheric(ast: syn::DeriveInput) -> Result<TokenStream> {
         #(#field_struct_impls)*
 
         impl #impl_generics #atmosphere_path::model::Atmospheric for #struct_name #ty_generics #where_clause {
+            fn uniforms(
+                &self,
+                images: &#render_path::render_asset::RenderAssets<#render_path::texture::GpuImage>,
+            ) -> Vec<Vec<u8>> {
+                vec![#(#uniform_impls,)*]
+            }
+
             fn as_bind_group(
                 &self,
                 layout: &#render_path::render_resource::BindGroupLayout,
                 render_device: &#render_path::renderer::RenderDevice,
+                uniform_buffers: &[#render_path::render_resource::Buffer],
                 images: &#render_path::render_asset::RenderAssets<#render_path::texture::GpuImage>,
                 fallback_image: &#render_path::texture::FallbackImage,
             ) -> #render_path::render_resource::BindGroup {
-                let bindings = vec![#(#binding_impls,)*];
+                let bindings: Vec<#render_path::render_resource::OwnedBindingResource> = vec![#(#binding_impls,)*];
 
                 let bind_group =
                     render_device.create_bind_group(
@@ -428,6 +444,13 @@ pub fn derive_atmospheric(ast: syn::DeriveInput) -> Result<TokenStream> {
                 bind_group
             }
 
+            fn image_views(
+                &self,
+                images: &#render_path::render_asset::RenderAssets<#render_path::texture::GpuImage>,
+            ) -> Vec<Option<#render_path::render_resource::TextureViewId>> {
+                vec![#(#image_view_impls,)*]
+            }
+
             fn clone_dynamic(&self) -> Box<dyn #atmosphere_path::model::Atmospheric> {
                 Box::new((*self).clone())
             }
@@ -446,6 +469,12 @@ pub fn derive_atmospheric(ast: syn::DeriveInput) -> Result<TokenStream> {
                 use std::borrow::Cow;
                 use std::any::TypeId;
                 app.register_type::<Self>();
+                app.register_type_data::<Self, #atmosphere_path::model::ReflectAtmosphereModel>();
+
+                // headless apps only need the model to be reflected
+                if app.get_sub_app(#render_path::RenderApp).is_none() {
+                    return;
+                }
 
                 let handle = #shader_path_impl;
 
@@ -455,20 +484,40 @@ pub fn derive_atmospheric(ast: syn::DeriveInput) -> Result<TokenStream> {
 
                 let bind_group_layout = Self::bind_group_layout(render_device);
 
+                let pass_bind_group_layout = (#pass_count > 0).then(|| #atmosphere_path::pipeline::pass_textures_bind_group_layout(render_device, #pass_count));
+                let #atmosphere_path::pipeline::AtmospherePassBindGroupLayout(pass_output_bind_group_layout) = render_app.world().resource::<#atmosphere_path::pipeline::AtmospherePassBindGroupLayout>().clone();
+                let format = render_app.world().get_resource::<#atmosphere_path::pipeline::AtmosphereImageFormat>().copied().unwrap_or_default().0;
+                let shader_defs = format.shader_defs();
+
                 let mut pipeline_cache = render_app.world_mut().resource_mut::<#render_path::render_resource::PipelineCache>();
 
-                let pipeline = pipeline_cache.queue_compute_pipeline(#render_path::render_resource::ComputePipelineDescriptor {
-                    label: Some(Cow::from("bevy_atmosphere_compute_pipeline")),
-                    layout: vec![
-                        bind_group_layout.clone(),
-                        image_bind_group_layout,
-                    ],
-                    push_constant_ranges: vec![],
-                    shader: handle,
-                    shader_defs: vec![],
-                    entry_point: Cow::from("main"),
-                    zero_initialize_workgroup_memory: true,
-                });
+                let passes = vec![#(
+                    #atmosphere_path::model::AtmospherePass {
+                        size: #math_path::UVec2::new(#pass_widths, #pass_heights),
+                        pipeline: pipeline_cache.queue_compute_pipeline(#render_path::render_resource::ComputePipelineDescriptor {
+                            label: Some(Cow::from(concat!("bevy_atmosphere_", #pass_entry_points, "_pipeline"))),
+                            layout: vec![
+                                bind_group_layout.clone(),
+                                pass_output_bind_group_layout.clone(),
+                                pass_bind_group_layout.clone().unwrap(),
+                            ],
+                            push_constant_ranges: vec![],
+                            shader: handle.clone(),
+                            shader_defs: shader_defs.clone(),
+                            entry_point: Cow::from(#pass_entry_points),
+                            zero_initialize_workgroup_memory: true,
+                        }),
+                    },
+                )*];
+
+                let pipeline = #atmosphere_path::pipeline::queue_atmosphere_model_pipeline(
+                    &pipeline_cache,
+                    handle.clone(),
+                    &bind_group_layout,
+                    &image_bind_group_layout,
+                    pass_bind_group_layout.as_ref(),
+                    format,
+                );
 
                 let id = TypeId::of::<Self>();
 
@@ -476,6 +525,9 @@ pub fn derive_atmospheric(ast: syn::DeriveInput) -> Result<TokenStream> {
                     id,
                     bind_group_layout,
                     pipeline,
+                    shader: handle,
+                    passes,
+                    pass_bind_group_layout,
                 };
 
                 let type_registry = app.world_mut().resource_mut::<#ecs_path::reflect::AppTypeRegistry>();
@@ -530,6 +582,25 @@ struct ShaderPathMeta {
     lit_str: syn::LitStr,
 }
 
+/// Represents the arguments for the `pass` attribute.
+///
+/// If parsed, represents an attribute
+/// like `#[pass(LitStr, LitInt, LitInt)]`
+struct PassMeta {
+    entry_point: LitStr,
+    _comma: Token![,],
+    width: LitInt,
+    _comma2: Token![,],
+    height: LitInt,
+}
+
+/// An intermediate pass, rendering to a texture of its own before the `main` entry point.
+struct Pass {
+    entry_point: String,
+    width: u32,
+    height: u32,
+}
+
 impl Parse for BindingMeta {
     fn parse(input: ParseStream) -> Result<Self> {
         if input.peek2(Token![,]) {
@@ -560,6 +631,18 @@ impl Parse for UniformBindingMeta {
     }
 }
 
+impl Parse for PassMeta {
+    fn parse(input: ParseStream) -> Result<Self> {
+        Ok(Self {
+            entry_point: input.parse()?,
+            _comma: input.parse()?,
+            width: input.parse()?,
+            _comma2: input.parse()?,
+            height: input.parse()?,
+        })
+    }
+}
+
 impl Parse for ShaderPathMeta {
     fn parse(input: ParseStream) -> Result<Self> {
         Ok(Self {
@@ -598,6 +681,16 @@ fn get_shader_path_attr(attr: &syn::Attribute) -> Result<String> {
     Ok(lit_str)
 }
 
+fn get_pass_attr(attr: &syn::Attribute) -> Result<Pass> {
+    let pass_meta = attr.parse_args_with(PassMeta::parse)?;
+
+    Ok(Pass {
+        entry_point: pass_meta.entry_point.value(),
+        width: pass_meta.width.base10_parse()?,
+        height: pass_meta.height.base10_parse()?,
+    })
+}
+
 #[derive(Default)]
 enum ShaderStageVisibility {
     #[default]
@@ -718,6 +811,21 @@ enum BindingTextureSampleType {
     Uint,
 }
 
+impl BindingTextureDimension {
+    /// The field of `FallbackImage` with a texture of this dimension.
+    fn fallback_image_field(&self) -> Ident {
+        let field = match self {
+            BindingTextureDimension::D1 => "d1",
+            BindingTextureDimension::D2 => "d2",
+            BindingTextureDimension::D2Array => "d2_array",
+            BindingTextureDimension::Cube => "cube",
+            BindingTextureDimension::CubeArray => "cube_array",
+            BindingTextureDimension::D3 => "d3",
+        };
+        Ident::new(field, Span::call_site())
+    }
+}
+
 impl ToTokens for BindingTextureDimension {
     fn to_tokens(&self, tokens: &mut proc_macro2::TokenStream) {
         tokens.extend(match self {
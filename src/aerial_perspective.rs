@@ -0,0 +1,669 @@
+//! Provides [`AtmosphereAerialPerspective`], a `Component` for scattering the light of the [`Nishita`] atmosphere into scene geometry.
+//!
+//! Every frame, a compute pass marches the view rays of the camera through the atmosphere of the model,
+//! into a froxel volume: a 3D texture fit to the view frustum, whose slices hold the light scattered toward the camera
+//! and the transmittance up to their distance.
+//! After the opaque geometry is rendered, a fullscreen pass looks the volume up at the depth of every pixel,
+//! attenuating the geometry by the transmittance and adding the scattered light:
+//! distant geometry turns hazy and blue, like mountains on the horizon.
+//!
+//! ```no_run
+//! # use bevy::prelude::*;
+//! # use bevy_atmosphere::{aerial_perspective::AtmosphereAerialPerspective, prelude::*};
+//! fn setup(mut commands: Commands) {
+//!     commands.spawn((
+//!         Camera3d::default(),
+//!         Camera {
+//!             hdr: true,
+//!             ..default()
+//!         },
+//!         AtmosphereAerialPerspective::default(),
+//!     ));
+//! }
+//! ```
+
+use bevy::{
+    asset::{load_internal_asset, weak_handle},
+    core_pipeline::{
+        core_3d::graph::{Core3d, Node3d},
+        fullscreen_vertex_shader::fullscreen_shader_vertex_state,
+    },
+    ecs::{query::QueryItem, system::lifetimeless::Read},
+    image::BevyDefault,
+    platform::collections::HashMap,
+    prelude::*,
+    render::{
+        render_graph::{
+            NodeRunError, RenderGraphApp, RenderGraphContext, RenderLabel, ViewNode, ViewNodeRunner,
+        },
+        render_resource::{
+            binding_types::{
+                sampler, texture_3d, texture_depth_2d, texture_depth_2d_multisampled,
+                texture_storage_3d, uniform_buffer,
+            },
+            BindGroup, BindGroupEntries, BindGroupLayout, BindGroupLayoutEntries, BlendComponent,
+            BlendFactor, BlendOperation, BlendState, BufferId, CachedComputePipelineId,
+            CachedRenderPipelineId, ColorTargetState, ColorWrites, ComputePassDescriptor,
+            ComputePipelineDescriptor, Extent3d, FilterMode, FragmentState, MultisampleState,
+            PipelineCache, RenderPassDescriptor, RenderPipelineDescriptor, Sampler,
+            SamplerBindingType, SamplerDescriptor, ShaderStages, ShaderType,
+            SpecializedRenderPipeline, SpecializedRenderPipelines, StorageTextureAccess,
+            TextureDescriptor, TextureDimension, TextureFormat, TextureSampleType, TextureUsages,
+            TextureView, TextureViewDescriptor, TextureViewId, UniformBuffer,
+        },
+        renderer::{RenderContext, RenderDevice, RenderQueue},
+        sync_world::RenderEntity,
+        view::{
+            ExtractedView, ViewDepthTexture, ViewTarget, ViewUniform, ViewUniformOffset,
+            ViewUniforms,
+        },
+        Extract, ExtractSchedule, Render, RenderApp, RenderSet,
+    },
+};
+
+use crate::{
+    collection::nishita::Nishita,
+    model::AtmosphereModel,
+    pipeline::{AtmosphereCached, AtmosphereCreated, AtmosphereRenderDevice},
+};
+
+/// The `Handle` for the shader that computes and applies the aerial perspective.
+pub const ATMOSPHERE_AERIAL_PERSPECTIVE_SHADER_HANDLE: Handle<Shader> =
+    weak_handle!("019a1f2c-3e4d-7a5b-8c6d-9e0f1a2b3c4d");
+
+/// Number of texels of the volume along each axis: across the width and height of the view, and along the distance.
+const VOLUME_SIZE: u32 = 32;
+
+/// Size of the workgroups of the compute pass, along the width and height of the volume.
+const VOLUME_WORKGROUP_SIZE: u32 = 8;
+
+/// A `Component` for cameras that scatters the light of the atmosphere into the opaque geometry they render.
+///
+/// The atmosphere is the [`Nishita`] model of the camera, or the global one,
+/// and nothing is applied while the model is of another type.
+/// Rayleigh and Mie scattering light in from the sun and ozone absorbing it are computed the same way as for the sky,
+/// from the camera, which sits above the `ray_origin` of the model by its height times `scale`.
+///
+/// The geometry has to be rendered by a `Camera3d`.
+/// Its depth texture is read, so it gains `TextureUsages::TEXTURE_BINDING`.
+/// The sky behind the geometry is left as it is, so draw it with `SkyboxCreationMode::Background`:
+/// a skybox mesh would be fogged like geometry.
+///
+/// Unlike [`AtmosphereFog`](crate::fog::AtmosphereFog), the haze depends on the direction and the height of the view,
+/// and the extinction of every color channel is applied where the device supports dual-source blending.
+#[derive(Component, Debug, Clone, Copy, PartialEq)]
+pub struct AtmosphereAerialPerspective {
+    /// Max Distance (Default: `32e3`).
+    ///
+    /// Controls the distance the volume reaches, in meters.
+    /// Geometry beyond it is scattered into as if it were at this distance.
+    pub max_distance: f32,
+    /// Scale (Default: `1.0`).
+    ///
+    /// Controls the number of meters in a unit of the world.
+    pub scale: f32,
+}
+
+impl Default for AtmosphereAerialPerspective {
+    fn default() -> Self {
+        Self {
+            max_distance: 32e3,
+            scale: 1.0,
+        }
+    }
+}
+
+/// The `AerialPerspective` struct in `aerial_perspective.wgsl`.
+#[derive(ShaderType, Debug, Clone, Copy, Default, PartialEq)]
+struct AerialPerspectiveUniform {
+    ray_origin: Vec3,
+    sun_position: Vec3,
+    sun_intensity: f32,
+    planet_radius: f32,
+    atmosphere_radius: f32,
+    rayleigh_coefficient: Vec3,
+    rayleigh_scale_height: f32,
+    mie_coefficient: f32,
+    mie_scale_height: f32,
+    mie_direction: f32,
+    ozone_coefficient: Vec3,
+    ozone_center_height: f32,
+    ozone_width: f32,
+    max_distance: f32,
+    scale: f32,
+}
+
+impl AerialPerspectiveUniform {
+    fn new(aerial_perspective: &AtmosphereAerialPerspective, nishita: &Nishita) -> Self {
+        Self {
+            ray_origin: nishita.ray_origin,
+            sun_position: nishita.sun_position,
+            sun_intensity: nishita.sun_intensity,
+            planet_radius: nishita.planet_radius,
+            atmosphere_radius: nishita.atmosphere_radius,
+            rayleigh_coefficient: nishita.rayleigh_coefficient,
+            rayleigh_scale_height: nishita.rayleigh_scale_height,
+            mie_coefficient: nishita.mie_coefficient,
+            mie_scale_height: nishita.mie_scale_height,
+            mie_direction: nishita.mie_direction,
+            ozone_coefficient: nishita.ozone_coefficient,
+            ozone_center_height: nishita.ozone_center_height,
+            ozone_width: nishita.ozone_width,
+            max_distance: aerial_perspective.max_distance.max(1.0),
+            scale: aerial_perspective.scale,
+        }
+    }
+}
+
+/// Label of the aerial perspective `render_graph::Node`.
+#[derive(Debug, Hash, PartialEq, Eq, Clone, RenderLabel)]
+pub struct AtmosphereAerialPerspectiveLabel;
+
+/// A `Plugin` that renders the [`AtmosphereAerialPerspective`] of cameras, added by [`AtmospherePlugin`](crate::plugin::AtmospherePlugin).
+pub(crate) struct AtmosphereAerialPerspectivePlugin;
+
+impl Plugin for AtmosphereAerialPerspectivePlugin {
+    fn build(&self, app: &mut App) {
+        load_internal_asset!(
+            app,
+            ATMOSPHERE_AERIAL_PERSPECTIVE_SHADER_HANDLE,
+            "shaders/aerial_perspective.wgsl",
+            Shader::from_wgsl
+        );
+
+        let render_app = app.sub_app_mut(RenderApp);
+        render_app
+            .init_resource::<AtmosphereCreated>()
+            .init_resource::<AerialPerspectiveViews>()
+            .init_resource::<SpecializedRenderPipelines<AerialPerspectivePipeline>>()
+            .add_systems(ExtractSchedule, extract_aerial_perspectives)
+            .add_systems(
+                Render,
+                (
+                    configure_aerial_perspective_depth.in_set(RenderSet::ManageViews),
+                    prepare_aerial_perspectives.in_set(RenderSet::PrepareResources),
+                    queue_aerial_perspectives.in_set(RenderSet::Queue),
+                    prepare_aerial_perspective_bind_groups.in_set(RenderSet::PrepareBindGroups),
+                ),
+            )
+            .add_render_graph_node::<ViewNodeRunner<AtmosphereAerialPerspectiveNode>>(
+                Core3d,
+                AtmosphereAerialPerspectiveLabel,
+            )
+            .add_render_graph_edges(
+                Core3d,
+                (
+                    Node3d::MainOpaquePass,
+                    AtmosphereAerialPerspectiveLabel,
+                    Node3d::MainTransmissivePass,
+                ),
+            );
+    }
+
+    fn finish(&self, app: &mut App) {
+        if let Some(render_app) = app.get_sub_app_mut(RenderApp) {
+            render_app.init_resource::<AerialPerspectivePipeline>();
+        }
+    }
+}
+
+/// The render world state of the aerial perspective of a view.
+#[derive(Default)]
+struct AerialPerspectiveView {
+    uniform: UniformBuffer<AerialPerspectiveUniform>,
+    /// Whether the uniform was set since it was last written.
+    uniform_changed: bool,
+    /// The views of the textures the light scattered toward the camera and the transmittance are written to.
+    volume: Option<(TextureView, TextureView)>,
+    /// The view uniforms and the uniform.
+    bind_group: AtmosphereCached<(BufferId, BufferId), BindGroup>,
+    /// The volume, as written by the compute pass.
+    volume_bind_group: AtmosphereCached<(TextureViewId, TextureViewId), BindGroup>,
+    /// The volume and the depth texture, as read by the fullscreen pass.
+    apply_bind_group: AtmosphereCached<(TextureViewId, TextureViewId, TextureViewId), BindGroup>,
+    /// The fullscreen pass, specialized for the view.
+    pipeline: Option<CachedRenderPipelineId>,
+}
+
+/// The aerial perspectives of the views with one, by render world entity.
+#[derive(Resource, Default)]
+struct AerialPerspectiveViews(HashMap<Entity, AerialPerspectiveView>);
+
+/// Extracts the aerial perspectives of cameras with a [`Nishita`] model, and drops those of other cameras.
+fn extract_aerial_perspectives(
+    mut views: ResMut<AerialPerspectiveViews>,
+    global_model: Extract<Option<Res<AtmosphereModel>>>,
+    cameras: Extract<
+        Query<(
+            RenderEntity,
+            &AtmosphereAerialPerspective,
+            Option<&AtmosphereModel>,
+        )>,
+    >,
+) {
+    let extracted: HashMap<_, _> = cameras
+        .iter()
+        .filter_map(|(entity, aerial_perspective, model)| {
+            let nishita = model.or(global_model.as_deref())?.to_ref::<Nishita>()?;
+            Some((
+                entity,
+                AerialPerspectiveUniform::new(aerial_perspective, nishita),
+            ))
+        })
+        .collect();
+
+    views.0.retain(|entity, _| extracted.contains_key(entity));
+    for (entity, uniform) in extracted {
+        let view = views.0.entry(entity).or_default();
+        if view.uniform.buffer().is_none() || *view.uniform.get() != uniform {
+            view.uniform.set(uniform);
+            view.uniform_changed = true;
+        }
+    }
+}
+
+/// Lets the volume be applied with the depth texture of views.
+fn configure_aerial_perspective_depth(
+    views: Res<AerialPerspectiveViews>,
+    mut cameras: Query<&mut Camera3d>,
+) {
+    for entity in views.0.keys() {
+        if let Ok(mut camera) = cameras.get_mut(*entity) {
+            camera.depth_texture_usages.0 |= TextureUsages::TEXTURE_BINDING.bits();
+        }
+    }
+}
+
+/// Creates the volume of views, and writes their uniforms.
+fn prepare_aerial_perspectives(
+    mut views: ResMut<AerialPerspectiveViews>,
+    render_device: AtmosphereRenderDevice,
+    render_queue: Res<RenderQueue>,
+) {
+    for view in views.0.values_mut() {
+        if view.volume.is_none() {
+            let texture = |label| {
+                render_device
+                    .create_texture(&TextureDescriptor {
+                        label: Some(label),
+                        size: Extent3d {
+                            width: VOLUME_SIZE,
+                            height: VOLUME_SIZE,
+                            depth_or_array_layers: VOLUME_SIZE,
+                        },
+                        mip_level_count: 1,
+                        sample_count: 1,
+                        dimension: TextureDimension::D3,
+                        format: TextureFormat::Rgba16Float,
+                        usage: TextureUsages::STORAGE_BINDING | TextureUsages::TEXTURE_BINDING,
+                        view_formats: &[],
+                    })
+                    .create_view(&TextureViewDescriptor::default())
+            };
+            view.volume = Some((
+                texture("atmosphere_aerial_perspective_inscatter_texture"),
+                texture("atmosphere_aerial_perspective_transmittance_texture"),
+            ));
+        }
+
+        if view.uniform_changed {
+            render_device.write_uniform_buffer(&mut view.uniform, &render_queue);
+            view.uniform_changed = false;
+        }
+    }
+}
+
+/// Specializes the fullscreen pass for the target and the samples of views.
+fn queue_aerial_perspectives(
+    mut views: ResMut<AerialPerspectiveViews>,
+    extracted_views: Query<(&ExtractedView, &Msaa)>,
+    pipeline: Res<AerialPerspectivePipeline>,
+    pipeline_cache: Res<PipelineCache>,
+    mut pipelines: ResMut<SpecializedRenderPipelines<AerialPerspectivePipeline>>,
+    render_device: Res<RenderDevice>,
+) {
+    let dual_source_blending = render_device
+        .features()
+        .contains(bevy::render::settings::WgpuFeatures::DUAL_SOURCE_BLENDING);
+
+    for (entity, view) in views.0.iter_mut() {
+        let Ok((extracted_view, msaa)) = extracted_views.get(*entity) else {
+            view.pipeline = None;
+            continue;
+        };
+        view.pipeline = Some(pipelines.specialize(
+            &pipeline_cache,
+            &pipeline,
+            AerialPerspectivePipelineKey {
+                hdr: extracted_view.hdr,
+                msaa_samples: msaa.samples(),
+                dual_source_blending,
+            },
+        ));
+    }
+}
+
+/// Creates the bind groups of views, whenever the buffers or textures they bind change.
+fn prepare_aerial_perspective_bind_groups(
+    mut views: ResMut<AerialPerspectiveViews>,
+    depth_textures: Query<(&ViewDepthTexture, &Msaa)>,
+    view_uniforms: Res<ViewUniforms>,
+    pipeline: Res<AerialPerspectivePipeline>,
+    render_device: AtmosphereRenderDevice,
+) {
+    let (Some(view_binding), Some(view_buffer)) = (
+        view_uniforms.uniforms.binding(),
+        view_uniforms.uniforms.buffer(),
+    ) else {
+        return;
+    };
+
+    for (entity, view) in views.0.iter_mut() {
+        let (Ok((depth, msaa)), Some(uniform), Some((inscatter, transmittance))) = (
+            depth_textures.get(*entity),
+            view.uniform.buffer(),
+            &view.volume,
+        ) else {
+            continue;
+        };
+
+        view.bind_group
+            .get_or_create((view_buffer.id(), uniform.id()), || {
+                render_device.create_bind_group(
+                    "bevy_atmosphere_aerial_perspective_bind_group",
+                    &pipeline.layout,
+                    &BindGroupEntries::sequential((
+                        view_binding.clone(),
+                        uniform.as_entire_binding(),
+                    )),
+                )
+            });
+        view.volume_bind_group
+            .get_or_create((inscatter.id(), transmittance.id()), || {
+                render_device.create_bind_group(
+                    "bevy_atmosphere_aerial_perspective_volume_bind_group",
+                    &pipeline.volume_layout,
+                    &BindGroupEntries::sequential((inscatter, transmittance)),
+                )
+            });
+        // the depth texture is created again when the samples change
+        view.apply_bind_group.get_or_create(
+            (inscatter.id(), transmittance.id(), depth.view().id()),
+            || {
+                render_device.create_bind_group(
+                    "bevy_atmosphere_aerial_perspective_apply_bind_group",
+                    pipeline.apply_layout(msaa.samples()),
+                    &BindGroupEntries::with_indices((
+                        (2, inscatter),
+                        (3, transmittance),
+                        (4, &pipeline.sampler),
+                        (5, depth.view()),
+                    )),
+                )
+            },
+        );
+    }
+}
+
+/// The compute pipeline that writes the volume, and the layouts of the fullscreen passes applying it.
+#[derive(Resource)]
+struct AerialPerspectivePipeline {
+    /// The view uniforms and the uniform.
+    layout: BindGroupLayout,
+    /// The textures of the volume, written to.
+    volume_layout: BindGroupLayout,
+    /// The textures of the volume and the depth texture, read from.
+    apply_layout: BindGroupLayout,
+    /// Same as `apply_layout`, with a multisampled depth texture.
+    apply_layout_multisampled: BindGroupLayout,
+    sampler: Sampler,
+    volume: CachedComputePipelineId,
+}
+
+impl AerialPerspectivePipeline {
+    fn apply_layout(&self, msaa_samples: u32) -> &BindGroupLayout {
+        if msaa_samples > 1 {
+            &self.apply_layout_multisampled
+        } else {
+            &self.apply_layout
+        }
+    }
+}
+
+impl FromWorld for AerialPerspectivePipeline {
+    fn from_world(world: &mut World) -> Self {
+        let render_device = world.resource::<RenderDevice>();
+
+        let layout = render_device.create_bind_group_layout(
+            "bevy_atmosphere_aerial_perspective_bind_group_layout",
+            &BindGroupLayoutEntries::sequential(
+                ShaderStages::COMPUTE | ShaderStages::FRAGMENT,
+                (
+                    uniform_buffer::<ViewUniform>(true),
+                    uniform_buffer::<AerialPerspectiveUniform>(false),
+                ),
+            ),
+        );
+        let volume_layout = render_device.create_bind_group_layout(
+            "bevy_atmosphere_aerial_perspective_volume_bind_group_layout",
+            &BindGroupLayoutEntries::sequential(
+                ShaderStages::COMPUTE,
+                (
+                    texture_storage_3d(TextureFormat::Rgba16Float, StorageTextureAccess::WriteOnly),
+                    texture_storage_3d(TextureFormat::Rgba16Float, StorageTextureAccess::WriteOnly),
+                ),
+            ),
+        );
+        let apply_layout = |label, depth| {
+            render_device.create_bind_group_layout(
+                label,
+                &BindGroupLayoutEntries::with_indices(
+                    ShaderStages::FRAGMENT,
+                    (
+                        (2, texture_3d(TextureSampleType::Float { filterable: true })),
+                        (3, texture_3d(TextureSampleType::Float { filterable: true })),
+                        (4, sampler(SamplerBindingType::Filtering)),
+                        (5, depth),
+                    ),
+                ),
+            )
+        };
+        let apply_layout_multisampled = apply_layout(
+            "bevy_atmosphere_aerial_perspective_apply_multisampled_bind_group_layout",
+            texture_depth_2d_multisampled(),
+        );
+        let apply_layout = apply_layout(
+            "bevy_atmosphere_aerial_perspective_apply_bind_group_layout",
+            texture_depth_2d(),
+        );
+
+        let sampler = render_device.create_sampler(&SamplerDescriptor {
+            label: Some("bevy_atmosphere_aerial_perspective_sampler"),
+            mag_filter: FilterMode::Linear,
+            min_filter: FilterMode::Linear,
+            ..default()
+        });
+
+        let volume =
+            world
+                .resource::<PipelineCache>()
+                .queue_compute_pipeline(ComputePipelineDescriptor {
+                    label: Some("bevy_atmosphere_aerial_perspective_volume_pipeline".into()),
+                    layout: vec![layout.clone(), volume_layout.clone()],
+                    push_constant_ranges: vec![],
+                    shader: ATMOSPHERE_AERIAL_PERSPECTIVE_SHADER_HANDLE,
+                    shader_defs: vec![],
+                    entry_point: "volume".into(),
+                    zero_initialize_workgroup_memory: false,
+                });
+
+        Self {
+            layout,
+            volume_layout,
+            apply_layout,
+            apply_layout_multisampled,
+            sampler,
+            volume,
+        }
+    }
+}
+
+/// The target and samples of the view an [`AerialPerspectivePipeline`] is applied to,
+/// and whether it attenuates every color channel with its own transmittance.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct AerialPerspectivePipelineKey {
+    hdr: bool,
+    msaa_samples: u32,
+    dual_source_blending: bool,
+}
+
+impl SpecializedRenderPipeline for AerialPerspectivePipeline {
+    type Key = AerialPerspectivePipelineKey;
+
+    fn specialize(&self, key: Self::Key) -> RenderPipelineDescriptor {
+        let mut shader_defs = Vec::new();
+        if key.msaa_samples > 1 {
+            shader_defs.push("MULTISAMPLED".into());
+        }
+        if key.dual_source_blending {
+            shader_defs.push("DUAL_SOURCE_BLENDING".into());
+        }
+
+        RenderPipelineDescriptor {
+            label: Some("bevy_atmosphere_aerial_perspective_apply_pipeline".into()),
+            layout: vec![
+                self.layout.clone(),
+                self.apply_layout(key.msaa_samples).clone(),
+            ],
+            push_constant_ranges: vec![],
+            vertex: fullscreen_shader_vertex_state(),
+            fragment: Some(FragmentState {
+                shader: ATMOSPHERE_AERIAL_PERSPECTIVE_SHADER_HANDLE,
+                shader_defs,
+                entry_point: "apply".into(),
+                targets: vec![Some(ColorTargetState {
+                    format: if key.hdr {
+                        ViewTarget::TEXTURE_FORMAT_HDR
+                    } else {
+                        TextureFormat::bevy_default()
+                    },
+                    // the geometry times the transmittance, plus the light scattered in
+                    blend: Some(BlendState {
+                        color: BlendComponent {
+                            src_factor: BlendFactor::One,
+                            dst_factor: if key.dual_source_blending {
+                                BlendFactor::Src1
+                            } else {
+                                BlendFactor::SrcAlpha
+                            },
+                            operation: BlendOperation::Add,
+                        },
+                        alpha: BlendComponent {
+                            src_factor: BlendFactor::Zero,
+                            dst_factor: BlendFactor::One,
+                            operation: BlendOperation::Add,
+                        },
+                    }),
+                    write_mask: ColorWrites::ALL,
+                })],
+            }),
+            primitive: default(),
+            depth_stencil: None,
+            multisample: MultisampleState {
+                count: key.msaa_samples,
+                ..default()
+            },
+            zero_initialize_workgroup_memory: false,
+        }
+    }
+}
+
+/// Writes the volume of a view, then applies it to the geometry rendered by the main opaque pass.
+#[derive(Default)]
+struct AtmosphereAerialPerspectiveNode;
+
+impl ViewNode for AtmosphereAerialPerspectiveNode {
+    type ViewQuery = (Read<ViewTarget>, Read<ViewUniformOffset>);
+
+    fn run<'w>(
+        &self,
+        graph: &mut RenderGraphContext,
+        render_context: &mut RenderContext<'w>,
+        (view_target, view_uniform_offset): QueryItem<'w, Self::ViewQuery>,
+        world: &'w World,
+    ) -> Result<(), NodeRunError> {
+        let views = world.resource::<AerialPerspectiveViews>();
+        let Some(view) = views.0.get(&graph.view_entity()) else {
+            return Ok(());
+        };
+        let (Some(bind_group), Some(volume_bind_group), Some(apply_bind_group), Some(pipeline)) = (
+            view.bind_group.get(),
+            view.volume_bind_group.get(),
+            view.apply_bind_group.get(),
+            view.pipeline,
+        ) else {
+            return Ok(());
+        };
+
+        let pipeline_cache = world.resource::<PipelineCache>();
+        let aerial_perspective_pipeline = world.resource::<AerialPerspectivePipeline>();
+        let (Some(volume_pipeline), Some(apply_pipeline)) = (
+            pipeline_cache.get_compute_pipeline(aerial_perspective_pipeline.volume),
+            pipeline_cache.get_render_pipeline(pipeline),
+        ) else {
+            return Ok(());
+        };
+
+        let command_encoder = render_context.command_encoder();
+        {
+            let mut pass = command_encoder.begin_compute_pass(&ComputePassDescriptor {
+                label: Some("atmosphere_aerial_perspective_volume_pass"),
+                timestamp_writes: None,
+            });
+            pass.set_pipeline(volume_pipeline);
+            pass.set_bind_group(0, bind_group, &[view_uniform_offset.offset]);
+            pass.set_bind_group(1, volume_bind_group, &[]);
+            let workgroups = VOLUME_SIZE.div_ceil(VOLUME_WORKGROUP_SIZE);
+            pass.dispatch_workgroups(workgroups, workgroups, 1);
+        }
+
+        let mut pass = command_encoder.begin_render_pass(&RenderPassDescriptor {
+            label: Some("atmosphere_aerial_perspective_apply_pass"),
+            color_attachments: &[Some(view_target.get_color_attachment())],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+        pass.set_pipeline(apply_pipeline);
+        pass.set_bind_group(0, bind_group, &[view_uniform_offset.offset]);
+        pass.set_bind_group(1, apply_bind_group, &[]);
+        pass.draw(0..3, 0..1);
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn uniform_follows_the_model() {
+        let nishita = Nishita {
+            sun_position: Vec3::X,
+            mie_coefficient: 42e-6,
+            ..default()
+        };
+        let uniform = AerialPerspectiveUniform::new(
+            &AtmosphereAerialPerspective {
+                scale: 10.0,
+                ..default()
+            },
+            &nishita,
+        );
+        assert_eq!(uniform.sun_position, Vec3::X);
+        assert_eq!(uniform.mie_coefficient, 42e-6);
+        assert_eq!(uniform.rayleigh_coefficient, nishita.rayleigh_coefficient);
+        assert_eq!((uniform.max_distance, uniform.scale), (32e3, 10.0));
+    }
+}
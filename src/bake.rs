@@ -0,0 +1,551 @@
+//! Bakes the sky of an [`AtmosphereModel`] on the CPU and writes it to image files, without a GPU.
+//!
+//! This is what the `bevy_atmosphere-bake` binary uses (`cargo run --features bake --bin bevy_atmosphere-bake -- --help`),
+//! for shipping pre-baked skies to platforms that can't afford the compute pipeline.
+//!
+//! A cubemap is evaluated with the same face and ray mapping as the compute shaders (see [`face_direction`]),
+//! so it matches the [`AtmosphereImage`](crate::pipeline::AtmosphereImage) the pipeline renders.
+//! It can be written as six PNG or EXR faces or as a KTX2 cubemap, and the sky can be written as an equirectangular HDR image as well.
+//!
+//! Only models with a CPU port can be baked (see [`sky_sampler`]).
+//! ```no_run
+//! # use bevy::prelude::*;
+//! # use bevy_atmosphere::{bake::bake_cubemap, prelude::*};
+//! let sky = bake_cubemap(&AtmosphereModel::new(Nishita::default()), 256).unwrap();
+//! let mut file = std::fs::File::create("sky.ktx2").unwrap();
+//! sky.write_ktx2(&mut file).unwrap();
+//! ```
+
+use std::{
+    f32::consts::PI,
+    fmt,
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use bevy::{
+    prelude::*,
+    reflect::{GetTypeRegistration, TypeRegistry},
+};
+use half::f16;
+use image::{
+    codecs::{hdr::HdrEncoder, png::PngEncoder},
+    ExtendedColorType, ImageEncoder, ImageError, Rgb,
+};
+
+pub use crate::model::{sky_sampler, SkySampler};
+
+use crate::model::{AtmosphereModel, Atmospheric, ReflectAtmosphereModel};
+
+/// Names of the faces of a cubemap, in the order of its layers.
+pub const FACE_NAMES: [&str; 6] = ["px", "nx", "py", "ny", "pz", "nz"];
+
+/// The direction of the texel at `x`, `y` on `face` of a cubemap with `resolution` texels per side.
+///
+/// This is the `switch invocation_id.z` block of the compute shaders, so it isn't normalized.
+pub fn face_direction(face: u32, x: u32, y: u32, resolution: u32) -> Vec3 {
+    let scale = resolution as f32 / 2.0;
+    let dir = Vec2::new(x as f32 / scale - 1.0, y as f32 / scale - 1.0);
+
+    match face {
+        0 => Vec3::new(1.0, -dir.y, -dir.x),  // +X
+        1 => Vec3::new(-1.0, -dir.y, dir.x),  // -X
+        2 => Vec3::new(dir.x, 1.0, dir.y),    // +Y
+        3 => Vec3::new(dir.x, -1.0, -dir.y),  // -Y
+        4 => Vec3::new(dir.x, -dir.y, 1.0),   // +Z
+        _ => Vec3::new(-dir.x, -dir.y, -1.0), // -Z
+    }
+}
+
+/// The direction of the pixel at `x`, `y` of an equirectangular image of `width` by `height` pixels.
+///
+/// The center of the image looks along -Z, the left and right edges along +Z, and the top row straight up.
+pub fn equirectangular_direction(x: u32, y: u32, width: u32, height: u32) -> Vec3 {
+    let longitude = ((x as f32 + 0.5) / width as f32 * 2.0 - 1.0) * PI;
+    let latitude = (0.5 - (y as f32 + 0.5) / height as f32) * PI;
+
+    Vec3::new(
+        latitude.cos() * longitude.sin(),
+        latitude.sin(),
+        -latitude.cos() * longitude.cos(),
+    )
+}
+
+/// Returns a `TypeRegistry` with every model of the [`collection`](crate::collection),
+/// for reading presets without an `App` (see [`deserialize_model`](crate::preset::deserialize_model)).
+pub fn collection_type_registry() -> TypeRegistry {
+    fn register<T: Atmospheric + GetTypeRegistration + FromReflect + TypePath>(
+        registry: &mut TypeRegistry,
+    ) {
+        registry.register::<T>();
+        registry.register_type_data::<T, ReflectAtmosphereModel>();
+    }
+
+    let mut type_registry = TypeRegistry::default();
+    #[cfg(feature = "gradient")]
+    register::<crate::collection::gradient::Gradient>(&mut type_registry);
+    #[cfg(feature = "hillaire")]
+    register::<crate::collection::hillaire::Hillaire>(&mut type_registry);
+    #[cfg(feature = "nishita")]
+    register::<crate::collection::nishita::Nishita>(&mut type_registry);
+    type_registry
+}
+
+/// An error that occurs while baking a sky.
+#[derive(Debug)]
+pub enum BakeError {
+    /// The model has no CPU port (see [`sky_sampler`]).
+    Unsupported(String),
+    /// A file couldn't be written.
+    Io(io::Error),
+    /// An image couldn't be encoded.
+    Image(ImageError),
+}
+
+impl fmt::Display for BakeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Unsupported(type_path) => write!(f, "`{type_path}` can't be baked on the CPU"),
+            Self::Io(error) => write!(f, "could not write sky: {error}"),
+            Self::Image(error) => write!(f, "could not encode sky: {error}"),
+        }
+    }
+}
+
+impl std::error::Error for BakeError {}
+
+impl From<io::Error> for BakeError {
+    fn from(error: io::Error) -> Self {
+        Self::Io(error)
+    }
+}
+
+impl From<ImageError> for BakeError {
+    fn from(error: ImageError) -> Self {
+        Self::Image(error)
+    }
+}
+
+/// The file format to bake a sky to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BakeFormat {
+    /// Six 8-bit sRGB PNG faces, clipping the sky to `1.0`.
+    Png,
+    /// Six half float EXR faces.
+    Exr,
+    /// A Radiance HDR equirectangular image.
+    Hdr,
+    /// A half float KTX2 cubemap.
+    Ktx2,
+}
+
+impl FromStr for BakeFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "png" => Ok(Self::Png),
+            "exr" => Ok(Self::Exr),
+            "hdr" => Ok(Self::Hdr),
+            "ktx2" => Ok(Self::Ktx2),
+            _ => Err(format!(
+                "unknown format `{s}`, expected `png`, `exr`, `hdr` or `ktx2`"
+            )),
+        }
+    }
+}
+
+/// A sky cubemap baked on the CPU.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BakedCubemap {
+    /// The number of texels along each side of a face.
+    pub resolution: u32,
+    /// The texels of every face, row by row, in the order of [`FACE_NAMES`].
+    pub faces: [Vec<LinearRgba>; 6],
+}
+
+/// A sky baked on the CPU to an equirectangular image (see [`equirectangular_direction`]).
+#[derive(Debug, Clone, PartialEq)]
+pub struct BakedEquirectangular {
+    /// The width of the image, in pixels.
+    pub width: u32,
+    /// The height of the image, in pixels.
+    pub height: u32,
+    /// The pixels of the image, row by row.
+    pub pixels: Vec<LinearRgba>,
+}
+
+/// Evaluates the sky of `model` for every texel of a cubemap with `resolution` texels per side.
+///
+/// The faces are evaluated in parallel.
+pub fn bake_cubemap(model: &AtmosphereModel, resolution: u32) -> Result<BakedCubemap, BakeError> {
+    let sampler = sampler_or_error(model)?;
+
+    let faces = std::thread::scope(|scope| {
+        let handles = [0, 1, 2, 3, 4, 5].map(|face| {
+            let sampler = &sampler;
+            scope.spawn(move || {
+                (0..resolution * resolution)
+                    .map(|i| {
+                        sampler(face_direction(
+                            face,
+                            i % resolution,
+                            i / resolution,
+                            resolution,
+                        ))
+                    })
+                    .collect::<Vec<_>>()
+            })
+        });
+        handles.map(|handle| handle.join().expect("Failed to bake face"))
+    });
+
+    Ok(BakedCubemap { resolution, faces })
+}
+
+/// Evaluates the sky of `model` for every pixel of an equirectangular image of `width` by `width / 2` pixels.
+pub fn bake_equirectangular(
+    model: &AtmosphereModel,
+    width: u32,
+) -> Result<BakedEquirectangular, BakeError> {
+    let sampler = sampler_or_error(model)?;
+    let height = (width / 2).max(1);
+
+    let pixels = (0..width * height)
+        .map(|i| {
+            sampler(equirectangular_direction(
+                i % width,
+                i / width,
+                width,
+                height,
+            ))
+        })
+        .collect();
+
+    Ok(BakedEquirectangular {
+        width,
+        height,
+        pixels,
+    })
+}
+
+fn sampler_or_error(model: &AtmosphereModel) -> Result<SkySampler<'_>, BakeError> {
+    sky_sampler(model).ok_or_else(|| {
+        let type_path = Atmospheric::as_reflect(model.model())
+            .reflect_type_path()
+            .to_string();
+        BakeError::Unsupported(type_path)
+    })
+}
+
+impl BakedCubemap {
+    /// Encodes `face` as an 8-bit sRGB PNG.
+    pub fn write_png_face(&self, face: usize, writer: impl Write) -> Result<(), BakeError> {
+        let bytes = self.faces[face]
+            .iter()
+            .flat_map(|texel| Srgba::from(*texel).to_u8_array())
+            .collect::<Vec<_>>();
+        PngEncoder::new(writer).write_image(
+            &bytes,
+            self.resolution,
+            self.resolution,
+            ExtendedColorType::Rgba8,
+        )?;
+        Ok(())
+    }
+
+    /// Encodes `face` as an uncompressed half float EXR.
+    pub fn write_exr_face(&self, face: usize, writer: impl Write) -> Result<(), BakeError> {
+        write_exr(&self.faces[face], self.resolution, self.resolution, writer)?;
+        Ok(())
+    }
+
+    /// Encodes the cubemap as an uncompressed `R16G16B16A16_SFLOAT` KTX2 texture, which Bevy loads as a cube texture.
+    pub fn write_ktx2(&self, mut writer: impl Write) -> Result<(), BakeError> {
+        /// `VK_FORMAT_R16G16B16A16_SFLOAT`
+        const VK_FORMAT: u32 = 97;
+        const IDENTIFIER: [u8; 12] = [
+            0xAB, 0x4B, 0x54, 0x58, 0x20, 0x32, 0x30, 0xBB, 0x0D, 0x0A, 0x1A, 0x0A,
+        ];
+        // identifier, header, index and a single level
+        const DFD_OFFSET: u32 = 12 + 9 * 4 + 4 * 4 + 2 * 8 + 3 * 8;
+
+        // A basic data format descriptor with a signed float sample for every channel.
+        let mut dfd = Vec::new();
+        let block_size: u16 = 24 + 16 * 4;
+        dfd.extend_from_slice(&(4 + u32::from(block_size)).to_le_bytes());
+        dfd.extend_from_slice(&0u32.to_le_bytes()); // vendor and descriptor type
+        dfd.extend_from_slice(&2u16.to_le_bytes()); // version
+        dfd.extend_from_slice(&block_size.to_le_bytes());
+        dfd.extend_from_slice(&[1, 1, 1, 0]); // RGBSDA, BT.709, linear, straight alpha
+        dfd.extend_from_slice(&[0; 4]); // texel block dimensions
+        dfd.extend_from_slice(&[8, 0, 0, 0, 0, 0, 0, 0]); // bytes per plane
+        for (i, channel) in [0u8, 1, 2, 15].into_iter().enumerate() {
+            dfd.extend_from_slice(&(i as u16 * 16).to_le_bytes()); // bit offset
+            dfd.push(15); // bit length - 1
+            dfd.push(channel | 0xC0); // float and signed
+            dfd.extend_from_slice(&[0; 4]); // sample position
+            dfd.extend_from_slice(&(-1.0f32).to_bits().to_le_bytes());
+            dfd.extend_from_slice(&1.0f32.to_bits().to_le_bytes());
+        }
+
+        let level_offset = (DFD_OFFSET + dfd.len() as u32).next_multiple_of(8);
+        let level_length = 6 * u64::from(self.resolution * self.resolution) * 8;
+
+        writer.write_all(&IDENTIFIER)?;
+        for value in [
+            VK_FORMAT,
+            2, // type size
+            self.resolution,
+            self.resolution,
+            0, // depth
+            0, // layers
+            6, // faces
+            1, // levels
+            0, // supercompression
+            DFD_OFFSET,
+            dfd.len() as u32,
+            0, // key/value data
+            0,
+        ] {
+            writer.write_all(&value.to_le_bytes())?;
+        }
+        for value in [0u64, 0, u64::from(level_offset), level_length, level_length] {
+            writer.write_all(&value.to_le_bytes())?;
+        }
+        writer.write_all(&dfd)?;
+        writer.write_all(&vec![0; (level_offset - DFD_OFFSET) as usize - dfd.len()])?;
+
+        for texel in self.faces.iter().flatten() {
+            for channel in texel.to_f32_array() {
+                writer.write_all(&f16::from_f32(channel).to_le_bytes())?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl BakedEquirectangular {
+    /// Encodes the image as a Radiance HDR image.
+    pub fn write_hdr(&self, writer: impl Write) -> Result<(), BakeError> {
+        let pixels = self
+            .pixels
+            .iter()
+            .map(|pixel| Rgb(pixel.to_f32_array_no_alpha()))
+            .collect::<Vec<_>>();
+        HdrEncoder::new(writer).encode(&pixels, self.width as usize, self.height as usize)?;
+        Ok(())
+    }
+}
+
+/// Writes `pixels` as an uncompressed scanline EXR with half float RGBA channels.
+fn write_exr(
+    pixels: &[LinearRgba],
+    width: u32,
+    height: u32,
+    mut writer: impl Write,
+) -> io::Result<()> {
+    fn attribute(header: &mut Vec<u8>, name: &str, kind: &str, value: &[u8]) {
+        header.extend_from_slice(name.as_bytes());
+        header.push(0);
+        header.extend_from_slice(kind.as_bytes());
+        header.push(0);
+        header.extend_from_slice(&(value.len() as u32).to_le_bytes());
+        header.extend_from_slice(value);
+    }
+
+    // channels are stored in alphabetical order
+    const CHANNELS: [(&str, usize); 4] = [("A", 3), ("B", 2), ("G", 1), ("R", 0)];
+
+    let mut channels = Vec::new();
+    for (name, _) in CHANNELS {
+        channels.extend_from_slice(name.as_bytes());
+        channels.push(0);
+        channels.extend_from_slice(&1i32.to_le_bytes()); // half
+        channels.extend_from_slice(&[0; 4]); // linear and reserved
+        channels.extend_from_slice(&1i32.to_le_bytes()); // x sampling
+        channels.extend_from_slice(&1i32.to_le_bytes()); // y sampling
+    }
+    channels.push(0);
+
+    let window = [0, 0, width as i32 - 1, height as i32 - 1]
+        .iter()
+        .flat_map(|value| value.to_le_bytes())
+        .collect::<Vec<_>>();
+
+    let mut header = vec![0x76, 0x2F, 0x31, 0x01, 2, 0, 0, 0];
+    attribute(&mut header, "channels", "chlist", &channels);
+    attribute(&mut header, "compression", "compression", &[0]);
+    attribute(&mut header, "dataWindow", "box2i", &window);
+    attribute(&mut header, "displayWindow", "box2i", &window);
+    attribute(&mut header, "lineOrder", "lineOrder", &[0]);
+    attribute(
+        &mut header,
+        "pixelAspectRatio",
+        "float",
+        &1.0f32.to_le_bytes(),
+    );
+    attribute(&mut header, "screenWindowCenter", "v2f", &[0; 8]);
+    attribute(
+        &mut header,
+        "screenWindowWidth",
+        "float",
+        &1.0f32.to_le_bytes(),
+    );
+    header.push(0);
+
+    let line_length = width as usize * CHANNELS.len() * 2;
+    let first_line = (header.len() + height as usize * 8) as u64;
+    writer.write_all(&header)?;
+    for y in 0..u64::from(height) {
+        writer.write_all(&(first_line + y * (8 + line_length as u64)).to_le_bytes())?;
+    }
+
+    for (y, row) in pixels.chunks(width as usize).enumerate() {
+        writer.write_all(&(y as i32).to_le_bytes())?;
+        writer.write_all(&(line_length as i32).to_le_bytes())?;
+        for (_, channel) in CHANNELS {
+            for pixel in row {
+                let value = pixel.to_f32_array()[channel];
+                writer.write_all(&f16::from_f32(value).to_le_bytes())?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Bakes the sky of `model` in `format` and writes it next to `output`, returning the paths of the files written.
+///
+/// `resolution` is the size of the faces of a cubemap, or the height of an equirectangular image (which is twice as wide).
+/// The extension of the format is appended to `output`, after a suffix with the name of the face for six-face formats.
+pub fn bake_to_files(
+    model: &AtmosphereModel,
+    format: BakeFormat,
+    resolution: u32,
+    output: &Path,
+) -> Result<Vec<PathBuf>, BakeError> {
+    let path = |suffix: Option<&str>, extension: &str| {
+        let mut name = output.as_os_str().to_owned();
+        if let Some(suffix) = suffix {
+            name.push(format!("_{suffix}"));
+        }
+        name.push(format!(".{extension}"));
+        PathBuf::from(name)
+    };
+    let create = |path: &Path| File::create(path).map(BufWriter::new);
+
+    match format {
+        BakeFormat::Png | BakeFormat::Exr => {
+            let sky = bake_cubemap(model, resolution)?;
+            let extension = if format == BakeFormat::Png {
+                "png"
+            } else {
+                "exr"
+            };
+            FACE_NAMES
+                .iter()
+                .enumerate()
+                .map(|(face, name)| {
+                    let path = path(Some(name), extension);
+                    let mut file = create(&path)?;
+                    match format {
+                        BakeFormat::Png => sky.write_png_face(face, &mut file)?,
+                        _ => sky.write_exr_face(face, &mut file)?,
+                    }
+                    file.flush()?;
+                    Ok(path)
+                })
+                .collect()
+        }
+        BakeFormat::Hdr => {
+            let sky = bake_equirectangular(model, resolution * 2)?;
+            let path = path(None, "hdr");
+            sky.write_hdr(create(&path)?)?;
+            Ok(vec![path])
+        }
+        BakeFormat::Ktx2 => {
+            let sky = bake_cubemap(model, resolution)?;
+            let path = path(None, "ktx2");
+            let mut file = create(&path)?;
+            sky.write_ktx2(&mut file)?;
+            file.flush()?;
+            Ok(vec![path])
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn faces_look_along_their_axis() {
+        let axes = [
+            Vec3::X,
+            Vec3::NEG_X,
+            Vec3::Y,
+            Vec3::NEG_Y,
+            Vec3::Z,
+            Vec3::NEG_Z,
+        ];
+        for (face, axis) in axes.into_iter().enumerate() {
+            let center = face_direction(face as u32, 8, 8, 16);
+            assert_eq!(center, axis);
+        }
+
+        // the top of the side faces looks up
+        assert!(face_direction(0, 8, 0, 16).y > 0.0);
+        assert!(face_direction(5, 8, 0, 16).y > 0.0);
+    }
+
+    #[test]
+    fn equirectangular_center_looks_forward() {
+        let center = equirectangular_direction(31, 15, 64, 32);
+        assert!(center.angle_between(Vec3::NEG_Z) < 0.1);
+        assert!(equirectangular_direction(0, 0, 64, 32).y > 0.99);
+        assert!(equirectangular_direction(16, 16, 64, 32).angle_between(Vec3::NEG_X) < 0.1);
+    }
+
+    #[test]
+    #[cfg(feature = "gradient")]
+    fn ktx2_has_the_expected_layout() {
+        use crate::collection::gradient::Gradient;
+
+        let sky = bake_cubemap(&AtmosphereModel::new(Gradient::default()), 4).unwrap();
+        let mut bytes = Vec::new();
+        sky.write_ktx2(&mut bytes).unwrap();
+
+        let u32_at =
+            |offset: usize| u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap());
+        let u64_at =
+            |offset: usize| u64::from_le_bytes(bytes[offset..offset + 8].try_into().unwrap());
+        assert_eq!(&bytes[1..4], b"KTX");
+        assert_eq!(u32_at(12), 97);
+        assert_eq!((u32_at(20), u32_at(24)), (4, 4));
+        assert_eq!(u32_at(36), 6);
+
+        let (level_offset, level_length) = (u64_at(80) as usize, u64_at(88) as usize);
+        assert_eq!(level_offset % 8, 0);
+        assert_eq!(level_length, 6 * 4 * 4 * 8);
+        assert_eq!(bytes.len(), level_offset + level_length);
+    }
+
+    #[test]
+    #[cfg(feature = "hillaire")]
+    fn hillaire_can_not_be_baked() {
+        let model = AtmosphereModel::new(crate::collection::hillaire::Hillaire::default());
+        assert!(matches!(
+            bake_cubemap(&model, 8),
+            Err(BakeError::Unsupported(path)) if path.ends_with("Hillaire")
+        ));
+    }
+
+    #[test]
+    fn formats_are_parsed() {
+        assert_eq!("KTX2".parse(), Ok(BakeFormat::Ktx2));
+        assert_eq!("exr".parse(), Ok(BakeFormat::Exr));
+        assert!("jpg".parse::<BakeFormat>().is_err());
+    }
+}
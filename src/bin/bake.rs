@@ -0,0 +1,115 @@
+//! Bakes the sky of an `.atmosphere.ron` preset to image files on the CPU.
+//!
+//! ```text
+//! cargo run --release --features bake --bin bevy_atmosphere-bake -- assets/presets/nishita.atmosphere.ron --format hdr
+//! ```
+
+use std::{path::PathBuf, process::ExitCode};
+
+use bevy_atmosphere::{
+    bake::{bake_to_files, collection_type_registry, BakeFormat},
+    preset::deserialize_model,
+};
+
+const USAGE: &str = "\
+Usage: bevy_atmosphere-bake <PRESET> [OPTIONS]
+
+Bakes the sky of an `.atmosphere.ron` preset on the CPU.
+
+Options:
+  -r, --resolution <N>   Size of each cubemap face, or height of the equirectangular image [default: 512]
+  -f, --format <FORMAT>  png (six faces), exr (six faces), hdr (equirectangular) or ktx2 (cubemap) [default: ktx2]
+  -o, --output <PATH>    Path of the output, without extension [default: the preset without its extension]
+  -h, --help             Print this help";
+
+struct Args {
+    preset: PathBuf,
+    resolution: u32,
+    format: BakeFormat,
+    output: PathBuf,
+}
+
+fn parse_args() -> Result<Args, String> {
+    let mut preset = None;
+    let mut resolution = 512;
+    let mut format = BakeFormat::Ktx2;
+    let mut output = None;
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or_else(|| format!("`{arg}` needs a value"));
+        match arg.as_str() {
+            "-r" | "--resolution" => {
+                resolution = value()?
+                    .parse()
+                    .map_err(|error| format!("invalid resolution: {error}"))?;
+                if resolution == 0 {
+                    return Err("resolution must be greater than 0".to_string());
+                }
+            }
+            "-f" | "--format" => format = value()?.parse()?,
+            "-o" | "--output" => output = Some(PathBuf::from(value()?)),
+            "-h" | "--help" => return Err(String::new()),
+            _ if arg.starts_with('-') => return Err(format!("unknown option `{arg}`")),
+            _ if preset.is_none() => preset = Some(PathBuf::from(arg)),
+            _ => return Err(format!("unexpected argument `{arg}`")),
+        }
+    }
+
+    let preset = preset.ok_or_else(|| "missing preset".to_string())?;
+    let output = output.unwrap_or_else(|| {
+        let name = preset.file_name().unwrap_or_default().to_string_lossy();
+        let stem = name
+            .trim_end_matches(".ron")
+            .trim_end_matches(".atmosphere");
+        preset.with_file_name(stem)
+    });
+
+    Ok(Args {
+        preset,
+        resolution,
+        format,
+        output,
+    })
+}
+
+fn main() -> ExitCode {
+    let args = match parse_args() {
+        Ok(args) => args,
+        Err(error) => {
+            if !error.is_empty() {
+                eprintln!("error: {error}\n");
+            }
+            eprintln!("{USAGE}");
+            return if error.is_empty() {
+                ExitCode::SUCCESS
+            } else {
+                ExitCode::FAILURE
+            };
+        }
+    };
+
+    let result = std::fs::read(&args.preset)
+        .map_err(|error| error.to_string())
+        .and_then(|bytes| {
+            deserialize_model(&bytes, &collection_type_registry())
+                .map_err(|error| error.to_string())
+        })
+        .and_then(|model| {
+            bake_to_files(&model, args.format, args.resolution, &args.output)
+                .map_err(|error| error.to_string())
+        });
+
+    match result {
+        Ok(paths) => {
+            for path in paths {
+                println!("{}", path.display());
+            }
+            ExitCode::SUCCESS
+        }
+        Err(error) => {
+            eprintln!("error: {}: {error}", args.preset.display());
+            ExitCode::FAILURE
+        }
+    }
+}
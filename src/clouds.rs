@@ -0,0 +1,497 @@
+//! Provides [`AtmosphereClouds`], a procedural layer of clouds composited over the sky of any model.
+//!
+//! Insert it as a `Resource` to cover the global sky, or add it as a `Component` next to an
+//! [`AtmosphereModel`] component to cover that entity's sky.
+//!
+//! ```no_run
+//! # use bevy::prelude::*;
+//! # use bevy_atmosphere::prelude::*;
+//! App::new()
+//!     .insert_resource(AtmosphereClouds {
+//!         coverage: 0.7,
+//!         wind: Vec2::new(20.0, 5.0),
+//!         ..default()
+//!     })
+//!     .add_plugins((DefaultPlugins, AtmospherePlugin))
+//!     .run();
+//! ```
+//!
+//! A sky with clouds renders its model to a texture of its own first.
+//! The clouds are then ray marched through a spherical shell above the observer, lit by the sun and the sky behind them,
+//! and composited over that texture into the [`AtmosphereImage`](crate::pipeline::AtmosphereImage).
+//! The sun direction is read from the model's `sun_position` field if it has one (see [`sun_direction`]),
+//! otherwise the sun is straight above.
+//!
+//! While the `wind` is blowing, the clouds drift with the elapsed `Time`: only a uniform is written
+//! and the cloud pass is run again every frame, without rebuilding pipelines or rendering the model again.
+
+use bevy::{
+    asset::weak_handle,
+    platform::collections::HashMap,
+    prelude::*,
+    reflect::ReflectRef,
+    render::{
+        render_resource::{
+            binding_types::{texture_2d_array, uniform_buffer},
+            BindGroup, BindGroupEntries, BindGroupLayout, BindGroupLayoutEntries, BufferId,
+            CachedComputePipelineId, ComputePass, ComputePipelineDescriptor, Extent3d,
+            PipelineCache, ShaderStages, ShaderType, TextureSampleType, TextureView, TextureViewId,
+            UniformBuffer,
+        },
+        renderer::{RenderDevice, RenderQueue},
+        Extract,
+    },
+};
+
+use crate::{
+    model::{AtmosphereModel, Atmospheric},
+    pipeline::{
+        atmosphere_stage_texture_descriptor, AtmosphereCached, AtmosphereDispatch, AtmosphereImage,
+        AtmosphereImageBindGroupLayout, AtmosphereImageBinding, AtmosphereImageFormat,
+        AtmosphereRenderDevice, AtmosphereSkies, AtmosphereSkyId, AtmosphereSlices,
+        AtmosphereUpdateEvent, ATMOSPHERE_ARRAY_TEXTURE_VIEW_DESCRIPTOR,
+    },
+    settings::AtmosphereSettings,
+};
+
+/// The `Handle` for the shader that composites clouds over the sky.
+pub const ATMOSPHERE_CLOUDS_SHADER_HANDLE: Handle<Shader> =
+    weak_handle!("019a0c3e-5b1d-7e42-8c6f-2d9a4e1b7f35");
+
+/// A procedural layer of clouds, composited over the sky of any model.
+///
+/// As a `Resource`, it covers the global sky.
+/// As a `Component`, it covers the sky of the [`AtmosphereModel`] on the same entity.
+#[derive(Resource, Component, Reflect, Debug, Clone, Copy, PartialEq)]
+pub struct AtmosphereClouds {
+    /// Coverage (Default: `0.5`).
+    ///
+    /// Controls the fraction of the sky covered by clouds, from `0.0` (clear) to `1.0` (overcast).
+    pub coverage: f32,
+    /// Cumulus (Default: `0.7`).
+    ///
+    /// Controls the shape of the clouds, from flat stratus sheets at `0.0` to billowing cumulus towers at `1.0`.
+    pub cumulus: f32,
+    /// Bottom Height (Default: `1500.0`).
+    ///
+    /// Controls the height of the bottom of the cloud layer, in meters.
+    pub bottom_height: f32,
+    /// Top Height (Default: `4000.0`).
+    ///
+    /// Controls the height of the top of the cloud layer, in meters.
+    pub top_height: f32,
+    /// Density (Default: `0.01`).
+    ///
+    /// Controls the extinction coefficient of the densest clouds, per meter.
+    pub density: f32,
+    /// Size (Default: `6000.0`).
+    ///
+    /// Controls the size of the largest cloud formations, in meters.
+    pub size: f32,
+    /// Wind (Default: `(10.0, 0.0)`).
+    ///
+    /// Controls the velocity the clouds drift with along the X and Z axes, in meters per second.
+    /// While it isn't zero, the clouds are rendered again every frame.
+    pub wind: Vec2,
+    /// Offset (Default: `(0.0, 0.0)`).
+    ///
+    /// Controls the offset of the clouds along the X and Z axes, in meters, for moving them by hand.
+    pub offset: Vec2,
+    /// Sun Intensity (Default: `22.0`).
+    ///
+    /// Controls how brightly the sun lights the clouds.
+    pub sun_intensity: f32,
+    /// Planet Radius (Default: `6371e3`).
+    ///
+    /// Controls how much the cloud layer curves down towards the horizon.
+    pub planet_radius: f32,
+}
+
+impl Default for AtmosphereClouds {
+    fn default() -> Self {
+        Self {
+            coverage: 0.5,
+            cumulus: 0.7,
+            bottom_height: 1500.0,
+            top_height: 4000.0,
+            density: 0.01,
+            size: 6000.0,
+            wind: Vec2::new(10.0, 0.0),
+            offset: Vec2::ZERO,
+            sun_intensity: 22.0,
+            planet_radius: 6371e3,
+        }
+    }
+}
+
+impl AtmosphereClouds {
+    /// The offset of the clouds after drifting with the wind for `elapsed` seconds.
+    pub fn offset_at(&self, elapsed: f32) -> Vec2 {
+        self.offset + self.wind * elapsed
+    }
+}
+
+/// The direction of the sun in `model`, read through reflection from its `sun_position` field.
+///
+/// Returns `None` if the model has no `Vec3` field named `sun_position`, or if it is zero.
+pub fn sun_direction(model: &AtmosphereModel) -> Option<Vec3> {
+    let ReflectRef::Struct(model) = Atmospheric::as_reflect(model.model()).reflect_ref() else {
+        return None;
+    };
+    model
+        .field("sun_position")?
+        .try_downcast_ref::<Vec3>()
+        .and_then(|sun_position| sun_position.try_normalize())
+}
+
+/// The `Clouds` struct in `clouds.wgsl`.
+#[derive(ShaderType, Debug, Clone, Copy, Default, PartialEq)]
+struct CloudsUniform {
+    sun_direction: Vec3,
+    sun_intensity: f32,
+    offset: Vec2,
+    coverage: f32,
+    cumulus: f32,
+    bottom_height: f32,
+    top_height: f32,
+    density: f32,
+    size: f32,
+    planet_radius: f32,
+}
+
+impl CloudsUniform {
+    fn new(clouds: &AtmosphereClouds, offset: Vec2, model: &AtmosphereModel) -> Self {
+        Self {
+            sun_direction: sun_direction(model).unwrap_or(Vec3::Y),
+            sun_intensity: clouds.sun_intensity,
+            offset,
+            coverage: clouds.coverage.clamp(0.0, 1.0),
+            cumulus: clouds.cumulus.clamp(0.0, 1.0),
+            bottom_height: clouds.bottom_height,
+            top_height: clouds.top_height.max(clouds.bottom_height + 1.0),
+            density: clouds.density.max(0.0),
+            size: clouds.size.max(1.0),
+            planet_radius: clouds.planet_radius,
+        }
+    }
+}
+
+/// The render world state of the clouds of a single sky.
+pub(crate) struct AtmosphereSkyClouds {
+    clouds: AtmosphereClouds,
+    /// The offset of the clouds at the time of extraction.
+    offset: Vec2,
+    uniform: UniformBuffer<CloudsUniform>,
+    /// The size and `TextureViewDimension::D2Array` view of the texture the model renders to.
+    base: Option<(Extent3d, TextureView)>,
+    /// The uniform and base texture.
+    bind_group: AtmosphereCached<(BufferId, TextureViewId), BindGroup>,
+    /// The [`AtmosphereImage`] to composite into.
+    image_bind_group: AtmosphereCached<AtmosphereImageBinding, BindGroup>,
+    /// Set while the clouds are waiting to be rendered.
+    dirty: bool,
+    /// Set when the model has been rendered to the base texture.
+    base_rendered: bool,
+    /// Set when the clouds are rendered during the current frame.
+    pub(crate) render: bool,
+}
+
+impl AtmosphereSkyClouds {
+    fn new(clouds: AtmosphereClouds) -> Self {
+        Self {
+            clouds,
+            offset: Vec2::ZERO,
+            uniform: UniformBuffer::default(),
+            base: None,
+            bind_group: AtmosphereCached::default(),
+            image_bind_group: AtmosphereCached::default(),
+            dirty: true,
+            base_rendered: false,
+            render: false,
+        }
+    }
+
+    /// The view of the texture the model renders to, once it is created.
+    pub(crate) fn base(&self) -> Option<&TextureView> {
+        self.base.as_ref().map(|(_, base)| base)
+    }
+
+    /// Drops the base texture and the bind groups, which are created again when the clouds are prepared next.
+    pub(crate) fn drop_resources(&mut self) {
+        self.base = None;
+        self.bind_group.clear();
+        self.image_bind_group.clear();
+    }
+
+    /// Decides whether the clouds are rendered this frame, given whether the base texture was `rendered` to.
+    ///
+    /// Returns `true` if they are, as the image is rendered to.
+    pub(crate) fn queue(&mut self, rendered: bool, pipeline_ready: bool) -> bool {
+        self.base_rendered |= rendered;
+        self.render = pipeline_ready
+            && self.bind_group.get().is_some()
+            && self.image_bind_group.get().is_some()
+            && self.base_rendered
+            && (self.dirty || rendered);
+        self.dirty &= !self.render;
+        self.render
+    }
+
+    /// Dispatches the cloud pass, compositing the clouds over the base texture.
+    pub(crate) fn render(
+        &self,
+        pass: &mut ComputePass,
+        pipeline_cache: &PipelineCache,
+        pipeline: &AtmosphereCloudsPipeline,
+        dispatch: &AtmosphereDispatch,
+    ) {
+        let (Some(clouds), Some(image), Some(pipeline)) = (
+            self.bind_group.get(),
+            self.image_bind_group.get(),
+            pipeline_cache.get_compute_pipeline(pipeline.pipeline),
+        ) else {
+            return;
+        };
+        pass.set_bind_group(0, clouds, &[]);
+        dispatch.set_image_bind_group(pass, image);
+        pass.set_pipeline(pipeline);
+        dispatch.dispatch(pass);
+    }
+}
+
+/// Extracts [`AtmosphereClouds`] from the main world into the skies they cover.
+#[allow(clippy::type_complexity)]
+pub(crate) fn extract_atmosphere_clouds(
+    mut skies: ResMut<AtmosphereSkies>,
+    main_clouds: Extract<Option<Res<AtmosphereClouds>>>,
+    main_entities: Extract<Query<(Entity, Ref<AtmosphereClouds>), With<AtmosphereImage>>>,
+    time: Extract<Option<Res<Time>>>,
+) {
+    let elapsed = time.as_ref().map_or(0.0, |time| time.elapsed_secs());
+
+    let mut extracted = HashMap::new();
+    if let Some(clouds) = &*main_clouds {
+        extracted.insert(AtmosphereSkyId::Global, (**clouds, clouds.is_changed()));
+    }
+    for (entity, clouds) in &main_entities {
+        extracted.insert(
+            AtmosphereSkyId::Entity(entity),
+            (*clouds, clouds.is_changed()),
+        );
+    }
+
+    for (id, sky) in skies.0.iter_mut() {
+        match (extracted.get(id), &mut sky.clouds) {
+            (Some(&(clouds, changed)), Some(sky_clouds)) => {
+                // the clouds only render again if their uniform ends up changing
+                if changed || clouds.wind != Vec2::ZERO {
+                    sky_clouds.clouds = clouds;
+                    sky_clouds.offset = clouds.offset_at(elapsed);
+                }
+            }
+            (Some(&(clouds, _)), None) => {
+                // the model has to render to the base texture from now on
+                let mut sky_clouds = AtmosphereSkyClouds::new(clouds);
+                sky_clouds.offset = clouds.offset_at(elapsed);
+                sky.clouds = Some(sky_clouds);
+                sky.cancel_update();
+                sky.changed = true;
+            }
+            (None, Some(_)) => {
+                // the model has to render straight to the image again
+                sky.clouds = None;
+                sky.cancel_update();
+                sky.changed = true;
+            }
+            (None, None) => {}
+        }
+    }
+}
+
+/// Creates the base textures of skies with clouds, and writes the uniforms of the clouds that changed.
+pub(crate) fn prepare_atmosphere_clouds(
+    mut skies: ResMut<AtmosphereSkies>,
+    mut update_events: ResMut<Events<AtmosphereUpdateEvent>>,
+    settings: Res<AtmosphereSettings>,
+    format: Res<AtmosphereImageFormat>,
+    render_device: AtmosphereRenderDevice,
+    render_queue: Res<RenderQueue>,
+) {
+    for (id, sky) in skies.0.iter_mut() {
+        let updating = sky.updating();
+        let Some(clouds) = &mut sky.clouds else {
+            continue;
+        };
+
+        let size = settings.projection.size(settings.resolution);
+        if clouds
+            .base
+            .as_ref()
+            .is_none_or(|(base_size, _)| *base_size != size)
+        {
+            let view = render_device
+                .create_texture(&atmosphere_stage_texture_descriptor(
+                    "atmosphere_clouds_base_texture",
+                    size,
+                    **format,
+                ))
+                .create_view(&ATMOSPHERE_ARRAY_TEXTURE_VIEW_DESCRIPTOR);
+            clouds.base = Some((size, view));
+            clouds.base_rendered = false;
+            update_events.send(AtmosphereUpdateEvent(*id));
+        }
+
+        // every slice of an update composites the same clouds
+        if updating {
+            continue;
+        }
+
+        let uniform = CloudsUniform::new(&clouds.clouds, clouds.offset, sky.model.model());
+        if clouds.uniform.buffer().is_none() || *clouds.uniform.get() != uniform {
+            clouds.uniform.set(uniform);
+            render_device.write_uniform_buffer(&mut clouds.uniform, &render_queue);
+            clouds.dirty = true;
+        }
+    }
+}
+
+/// Creates the bind groups of the clouds of skies, whenever the buffers or textures they bind change.
+pub(crate) fn prepare_atmosphere_clouds_bind_group(
+    mut skies: ResMut<AtmosphereSkies>,
+    render_device: AtmosphereRenderDevice,
+    pipeline: Res<AtmosphereCloudsPipeline>,
+    image_bind_group_layout: Res<AtmosphereImageBindGroupLayout>,
+    slices: Res<AtmosphereSlices>,
+) {
+    for sky in skies.0.values_mut() {
+        if sky.updating() {
+            continue;
+        }
+        let Some(view) = sky.output().cloned() else {
+            continue;
+        };
+        let Some(clouds) = &mut sky.clouds else {
+            continue;
+        };
+        let (Some((_, base)), Some(uniform), Some(slice), Some(slice_buffer)) = (
+            &clouds.base,
+            clouds.uniform.buffer(),
+            slices.binding(),
+            slices.buffer_id(),
+        ) else {
+            continue;
+        };
+
+        clouds
+            .bind_group
+            .get_or_create((uniform.id(), base.id()), || {
+                render_device.create_bind_group(
+                    "bevy_atmosphere_clouds_bind_group",
+                    &pipeline.layout,
+                    &BindGroupEntries::sequential((uniform.as_entire_binding(), base)),
+                )
+            });
+        clouds
+            .image_bind_group
+            .get_or_create((view.id(), slice_buffer), || {
+                render_device.create_bind_group(
+                    "bevy_atmosphere_clouds_image_bind_group",
+                    &image_bind_group_layout.0,
+                    &BindGroupEntries::sequential((&view, slice)),
+                )
+            });
+    }
+}
+
+/// The compute pipeline that composites clouds over the sky.
+#[derive(Resource)]
+pub(crate) struct AtmosphereCloudsPipeline {
+    layout: BindGroupLayout,
+    pub(crate) pipeline: CachedComputePipelineId,
+}
+
+impl FromWorld for AtmosphereCloudsPipeline {
+    fn from_world(world: &mut World) -> Self {
+        let render_device = world.resource::<RenderDevice>();
+        let AtmosphereImageBindGroupLayout(image_bind_group_layout) =
+            world.resource::<AtmosphereImageBindGroupLayout>().clone();
+        let format = world.resource::<AtmosphereImageFormat>();
+
+        let layout = render_device.create_bind_group_layout(
+            "bevy_atmosphere_clouds_bind_group_layout",
+            &BindGroupLayoutEntries::sequential(
+                ShaderStages::COMPUTE,
+                (
+                    uniform_buffer::<CloudsUniform>(false),
+                    texture_2d_array(TextureSampleType::Float { filterable: false }),
+                ),
+            ),
+        );
+
+        let pipeline =
+            world
+                .resource::<PipelineCache>()
+                .queue_compute_pipeline(ComputePipelineDescriptor {
+                    label: Some("bevy_atmosphere_clouds_pipeline".into()),
+                    layout: vec![layout.clone(), image_bind_group_layout],
+                    push_constant_ranges: vec![],
+                    shader: ATMOSPHERE_CLOUDS_SHADER_HANDLE,
+                    shader_defs: format.shader_defs(),
+                    entry_point: "main".into(),
+                    zero_initialize_workgroup_memory: true,
+                });
+
+        Self { layout, pipeline }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sun_direction_is_read_from_the_model() {
+        #[cfg(feature = "nishita")]
+        {
+            let model = AtmosphereModel::new(crate::collection::nishita::Nishita {
+                sun_position: Vec3::new(0.0, 2.0, 0.0),
+                ..default()
+            });
+            assert_eq!(sun_direction(&model), Some(Vec3::Y));
+        }
+
+        #[cfg(feature = "gradient")]
+        {
+            let model = AtmosphereModel::new(crate::collection::gradient::Gradient::default());
+            assert_eq!(sun_direction(&model), None);
+        }
+    }
+
+    #[test]
+    fn clouds_drift_with_the_wind() {
+        let clouds = AtmosphereClouds {
+            wind: Vec2::new(10.0, -2.0),
+            offset: Vec2::new(100.0, 0.0),
+            ..default()
+        };
+
+        assert_eq!(clouds.offset_at(0.0), Vec2::new(100.0, 0.0));
+        assert_eq!(clouds.offset_at(5.0), Vec2::new(150.0, -10.0));
+    }
+
+    #[test]
+    fn uniform_keeps_the_layer_valid() {
+        let clouds = AtmosphereClouds {
+            coverage: 2.0,
+            bottom_height: 3000.0,
+            top_height: 1000.0,
+            density: -1.0,
+            ..default()
+        };
+
+        let uniform = CloudsUniform::new(&clouds, Vec2::ZERO, &AtmosphereModel::default());
+        assert_eq!(uniform.coverage, 1.0);
+        assert!(uniform.top_height > uniform.bottom_height);
+        assert_eq!(uniform.density, 0.0);
+    }
+}
@@ -5,6 +5,7 @@ use bevy::{prelude::*, render::render_resource::ShaderType};
 ///
 /// A simple gradient for creating a stylized environment.
 #[derive(Atmospheric, ShaderType, Reflect, Debug, Clone)]
+#[reflect(Default)]
 #[uniform(0, Gradient)]
 #[internal("shaders/gradient.wgsl")]
 pub struct Gradient {
@@ -38,8 +39,54 @@ impl Default for Gradient {
     }
 }
 
+impl Gradient {
+    /// Samples the color of the sky in a direction.
+    ///
+    /// This is a CPU port of `render_gradient` in `gradient.wgsl`, so it returns the same color the sky texture has in that direction.
+    /// The direction doesn't need to be normalized.
+    pub fn sample(&self, direction: Vec3) -> LinearRgba {
+        let y = direction.normalize().y;
+
+        let p_sky = y.max(0.0);
+        let p_horizon = 1.0 - y.abs();
+        let p_ground = (-y).max(0.0);
+
+        let color = (self.sky * p_sky) + (self.horizon * p_horizon) + (self.ground * p_ground);
+
+        color.with_alpha(1.0)
+    }
+}
+
 impl From<&Gradient> for Gradient {
     fn from(gradient: &Gradient) -> Self {
         gradient.clone()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sample_poles_and_horizon() {
+        let gradient = Gradient::default();
+        assert_eq!(gradient.sample(Vec3::Y), gradient.sky);
+        assert_eq!(gradient.sample(Vec3::X * 3.0), gradient.horizon);
+        assert_eq!(gradient.sample(Vec3::NEG_Y), gradient.ground);
+    }
+
+    #[test]
+    fn sample_blends_linearly() {
+        let gradient = Gradient {
+            sky: LinearRgba::rgb(1.0, 0.0, 0.0),
+            horizon: LinearRgba::rgb(0.0, 1.0, 0.0),
+            ground: LinearRgba::rgb(0.0, 0.0, 1.0),
+        };
+        // 30 degrees above the horizon
+        let color = gradient.sample(Vec3::new(3f32.sqrt(), 1.0, 0.0));
+        assert!((color.red - 0.5).abs() < 1e-6);
+        assert!((color.green - 0.5).abs() < 1e-6);
+        assert_eq!(color.blue, 0.0);
+        assert_eq!(color.alpha, 1.0);
+    }
+}
@@ -0,0 +1,112 @@
+use crate::model::Atmospheric;
+use bevy::{prelude::*, render::render_resource::ShaderType};
+
+/// The Hillaire sky model.
+///
+/// A physically based atmospheric model from ["A Scalable and Production Ready Sky and Atmosphere Rendering Technique"](https://sebh.github.io/publications/egsr2020.pdf) by Sébastien Hillaire (2020).
+///
+/// Unlike [`Nishita`](crate::collection::nishita::Nishita), it accounts for light scattered more than once and for absorption by ozone,
+/// which keeps twilight bright and blue.
+/// The sky is rendered from lookup tables that are precomputed in intermediate passes every time the model changes:
+/// the transmittance to the edge of the atmosphere, the light scattered multiple times, and the sky as seen from `ray_origin`.
+#[derive(Atmospheric, ShaderType, Reflect, Debug, Clone)]
+#[reflect(Default)]
+#[uniform(0, Hillaire)]
+#[internal("shaders/hillaire.wgsl")]
+#[pass("transmittance", 256, 64)]
+#[pass("multi_scattering", 32, 32)]
+#[pass("sky_view", 192, 108)]
+pub struct Hillaire {
+    /// Ray Origin (Default: `(0.0, 6360.2e3, 0.0)`).
+    ///
+    /// Controls orientation of the sky and the height it is seen from.
+    /// It can be thought of as the up-axis and values should be between planet radius and atmosphere radius.
+    pub ray_origin: Vec3,
+    /// Sun Position (Default: `(1.0, 1.0, 1.0)`).
+    ///
+    /// Controls position of the sun in the sky.
+    /// Scale doesn't matter, as it will be normalized.
+    pub sun_position: Vec3,
+    /// Sun Intensity (Default: `22.0`).
+    ///
+    /// Controls how intense the sun's brightness is.
+    pub sun_intensity: f32,
+    /// Planet Radius (Default: `6360e3`).
+    ///
+    /// Controls the radius of the planet.
+    /// Heavily interdependent with `atmosphere_radius`.
+    pub planet_radius: f32,
+    /// Atmosphere Radius (Default: `6460e3`).
+    ///
+    /// Controls the radius of the atmosphere.
+    /// Heavily interdependent with `planet_radius`.
+    pub atmosphere_radius: f32,
+    /// Rayleigh Scattering Coefficient (Default: `(5.802e-6, 13.558e-6, 33.1e-6)`).
+    ///
+    /// Strongly influences the color of the sky.
+    pub rayleigh_scattering: Vec3,
+    /// Rayleigh Scattering Scale Height (Default: `8e3`).
+    ///
+    /// Controls the amount of Rayleigh scattering.
+    pub rayleigh_scale_height: f32,
+    /// Mie Scattering Coefficient (Default: `3.996e-6`).
+    ///
+    /// Strongly influences the color of the horizon.
+    pub mie_scattering: f32,
+    /// Mie Absorption Coefficient (Default: `4.4e-6`).
+    ///
+    /// Controls how much light aerosols absorb, making the horizon hazier.
+    pub mie_absorption: f32,
+    /// Mie Scattering Scale Height (Default: `1.2e3`).
+    ///
+    /// Controls the amount of Mie scattering.
+    pub mie_scale_height: f32,
+    /// Mie Scattering Preferred Direction (Default: `0.8`).
+    ///
+    /// Controls the general direction of Mie scattering.
+    pub mie_direction: f32,
+    /// Ozone Absorption Coefficient (Default: `(0.65e-6, 1.881e-6, 0.085e-6)`).
+    ///
+    /// Controls how much light ozone absorbs, which keeps the sky blue when the sun is low.
+    pub ozone_absorption: Vec3,
+    /// Ozone Center Height (Default: `25e3`).
+    ///
+    /// Controls the height where ozone is densest.
+    pub ozone_center_height: f32,
+    /// Ozone Width (Default: `30e3`).
+    ///
+    /// Controls the thickness of the ozone layer, whose density falls off linearly away from `ozone_center_height`.
+    pub ozone_width: f32,
+    /// Ground Albedo (Default: `(0.3, 0.3, 0.3)`).
+    ///
+    /// Controls how much light the planet reflects back into the atmosphere.
+    pub ground_albedo: Vec3,
+}
+
+impl Default for Hillaire {
+    fn default() -> Self {
+        Self {
+            ray_origin: Vec3::new(0.0, 6360.2e3, 0.0),
+            sun_position: Vec3::new(1.0, 1.0, 1.0),
+            sun_intensity: 22.0,
+            planet_radius: 6360e3,
+            atmosphere_radius: 6460e3,
+            rayleigh_scattering: Vec3::new(5.802e-6, 13.558e-6, 33.1e-6),
+            rayleigh_scale_height: 8e3,
+            mie_scattering: 3.996e-6,
+            mie_absorption: 4.4e-6,
+            mie_scale_height: 1.2e3,
+            mie_direction: 0.8,
+            ozone_absorption: Vec3::new(0.65e-6, 1.881e-6, 0.085e-6),
+            ozone_center_height: 25e3,
+            ozone_width: 30e3,
+            ground_albedo: Vec3::splat(0.3),
+        }
+    }
+}
+
+impl From<&Hillaire> for Hillaire {
+    fn from(hillaire: &Hillaire) -> Self {
+        hillaire.clone()
+    }
+}
@@ -6,6 +6,10 @@
 #[cfg(any(doc, feature = "nishita"))]
 pub mod nishita;
 
+/// [`Hillaire`](crate::collection::hillaire::Hillaire) sky model.
+#[cfg(any(doc, feature = "hillaire"))]
+pub mod hillaire;
+
 /// [`Gradient`](crate::collection::gradient::Gradient) sky model.
 #[cfg(any(doc, feature = "gradient"))]
 pub mod gradient;
@@ -1,10 +1,12 @@
 use crate::model::Atmospheric;
 use bevy::{prelude::*, render::render_resource::ShaderType};
+use std::f32::consts::PI;
 
 /// The Nishita sky model.
 ///
 /// An atmospheric model that uses Rayleigh and Mie scattering to simulate a realistic sky.
 #[derive(Atmospheric, ShaderType, Reflect, Debug, Clone)]
+#[reflect(Default)]
 #[uniform(0, Nishita)]
 #[internal("shaders/nishita.wgsl")]
 pub struct Nishita {
@@ -53,6 +55,69 @@ pub struct Nishita {
     ///
     /// Controls the general direction of Mie scattering.
     pub mie_direction: f32,
+    /// Ozone Absorption Coefficient (Default: `(0.0, 0.0, 0.0)`).
+    ///
+    /// Controls how much light ozone absorbs, which keeps the zenith a deep blue at twilight.
+    /// Realistic values are around `(0.65e-6, 1.881e-6, 0.085e-6)`.
+    pub ozone_coefficient: Vec3,
+    /// Ozone Center Height (Default: `25e3`).
+    ///
+    /// Controls the height where ozone is densest.
+    pub ozone_center_height: f32,
+    /// Ozone Width (Default: `30e3`).
+    ///
+    /// Controls the thickness of the ozone layer, whose density falls off linearly away from `ozone_center_height`.
+    pub ozone_width: f32,
+    /// Sun Angular Diameter (Default: `0.0093`).
+    ///
+    /// Controls the size of the sun's disc in radians.
+    /// Like the moon, the disc covers only a few texels at the default resolution.
+    pub sun_angular_diameter: f32,
+    /// Sun Disc Intensity (Default: `100.0`).
+    ///
+    /// Controls how bright the center of the sun's disc is before it is attenuated by the atmosphere, with `0.0` hiding the disc.
+    pub sun_disc_intensity: f32,
+    /// Sun Limb Darkening (Default: `(0.397, 0.503, 0.652)`).
+    ///
+    /// Controls how much the sun's disc darkens towards its edge, per color channel.
+    /// The brightness falls off with the cosine between the sun's surface and the view direction raised to these powers.
+    pub sun_limb_darkening: Vec3,
+    /// Moon Position (Default: `(-1.0, -1.0, -1.0)`).
+    ///
+    /// Controls position of the moon in the sky.
+    /// Scale doesn't matter, as it will be normalized.
+    pub moon_position: Vec3,
+    /// Moon Phase (Default: `0.5`).
+    ///
+    /// Controls how much of the moon is lit, from `0.0` at new moon to `0.5` at full moon and back to `1.0`.
+    pub moon_phase: f32,
+    /// Moon Intensity (Default: `0.3`).
+    ///
+    /// Controls how intense moonlight scattered by the atmosphere is at full moon.
+    pub moon_intensity: f32,
+    /// Moon Disc Intensity (Default: `2.0`).
+    ///
+    /// Controls how bright the lit part of the moon's disc is.
+    pub moon_disc_intensity: f32,
+    /// Moon Angular Diameter (Default: `0.0093`).
+    ///
+    /// Controls the size of the moon's disc in radians.
+    /// The disc covers only a few texels at the default resolution, so larger values may be needed for it to be clearly visible.
+    pub moon_angular_diameter: f32,
+    /// Star Intensity (Default: `1.0`).
+    ///
+    /// Controls how bright the brightest stars are.
+    /// Stars fade in as the sun sets.
+    pub star_intensity: f32,
+    /// Star Density (Default: `0.05`).
+    ///
+    /// Controls how many stars there are, from `0.0` to `1.0`.
+    pub star_density: f32,
+    /// Milky Way Intensity (Default: `0.05`).
+    ///
+    /// Controls how bright the band of the Milky Way is, with `0.0` disabling it.
+    /// Like the stars, it fades in as the sun sets.
+    pub milky_way_intensity: f32,
 }
 
 impl Default for Nishita {
@@ -68,12 +133,657 @@ impl Default for Nishita {
             mie_coefficient: 21e-6,
             mie_scale_height: 1.2e3,
             mie_direction: 0.758,
+            ozone_coefficient: Vec3::ZERO,
+            ozone_center_height: 25e3,
+            ozone_width: 30e3,
+            sun_angular_diameter: 0.0093,
+            sun_disc_intensity: 100.0,
+            sun_limb_darkening: Vec3::new(0.397, 0.503, 0.652),
+            moon_position: Vec3::new(-1.0, -1.0, -1.0),
+            moon_phase: 0.5,
+            moon_intensity: 0.3,
+            moon_disc_intensity: 2.0,
+            moon_angular_diameter: 0.0093,
+            star_intensity: 1.0,
+            star_density: 0.05,
+            milky_way_intensity: 0.05,
         }
     }
 }
 
+const ISTEPS: u32 = 16;
+const JSTEPS: u32 = 8;
+
+/// Number of star cells along each axis of the unit cube.
+const STAR_CELLS: f32 = 100.0;
+/// Angular radius of a star, in cells.
+const STAR_RADIUS: f32 = 0.25;
+/// Normal of the plane of the Milky Way.
+const GALACTIC_POLE: Vec3 = Vec3::new(-0.48, 0.6, 0.64);
+const MILKY_WAY_WIDTH: f32 = 0.12;
+
+impl Nishita {
+    /// Samples the color of the sky in a direction.
+    ///
+    /// This is a CPU port of `render_nishita` and `render_night` in `nishita.wgsl`, so it returns the same color the sky texture has in that direction.
+    /// The direction doesn't need to be normalized.
+    pub fn sample(&self, direction: Vec3) -> LinearRgba {
+        let r = direction.normalize();
+
+        // Light from outside the atmosphere is attenuated by the same optical depth as the sky.
+        let attn = self.transmittance(r);
+
+        let color = self.scatter(r, self.sun_position, self.sun_intensity)
+            + self.sun_disc(r) * attn
+            + self.night(r, attn);
+        LinearRgba::rgb(color.x, color.y, color.z)
+    }
+
+    /// Port of `render_sun_disc`, the disc of the sun darkened towards its limb.
+    fn sun_disc(&self, r: Vec3) -> Vec3 {
+        let radius = self.sun_angular_diameter * 0.5;
+        let angle = r.dot(self.sun_position.normalize()).clamp(-1.0, 1.0).acos();
+        if angle >= radius {
+            return Vec3::ZERO;
+        }
+
+        // Cosine between the surface of the sun and the view direction.
+        let x = angle / radius;
+        let mu = (1.0 - x * x).sqrt();
+
+        let limb = self.sun_limb_darkening;
+        self.sun_disc_intensity * Vec3::new(mu.powf(limb.x), mu.powf(limb.y), mu.powf(limb.z))
+    }
+
+    /// Port of `render_nishita`, the light of a sun scattered towards `ray_origin`.
+    fn scatter(&self, direction: Vec3, sun_position: Vec3, sun_intensity: f32) -> Vec3 {
+        // Normalize the ray direction and sun position.
+        let r = direction.normalize();
+        let p_sun = sun_position.normalize();
+        let r0 = self.ray_origin;
+        let (k_rlh, k_mie) = (self.rayleigh_coefficient, self.mie_coefficient);
+        let (sh_rlh, sh_mie) = (self.rayleigh_scale_height, self.mie_scale_height);
+        let k_ozone = self.ozone_coefficient;
+
+        // Calculate the step size of the primary ray.
+        let mut p = rsi(r, r0, self.atmosphere_radius);
+        if p.x > p.y {
+            return Vec3::ZERO;
+        }
+        p.y = p.y.min(rsi(r, r0, self.planet_radius).x);
+        let i_step_size = (p.y - p.x) / ISTEPS as f32;
+
+        // Initialize the primary ray depth.
+        let mut i_depth = 0.0;
+
+        // Initialize accumulators for Rayleigh and Mie scattering.
+        let mut total_rlh = Vec3::ZERO;
+        let mut total_mie = Vec3::ZERO;
+
+        // Initialize optical depth accumulators for the primary ray.
+        let mut i_od_rlh = 0.0;
+        let mut i_od_mie = 0.0;
+        let mut i_od_ozone = 0.0;
+
+        // Calculate the Rayleigh and Mie phases.
+        let mu = r.dot(p_sun);
+        let mumu = mu * mu;
+        let g = self.mie_direction;
+        let gg = g * g;
+        let p_rlh = 3.0 / (16.0 * PI) * (1.0 + mumu);
+        let p_mie = 3.0 / (8.0 * PI) * ((1.0 - gg) * (mumu + 1.0))
+            / ((1.0 + gg - 2.0 * mu * g).powf(1.5) * (2.0 + gg));
+
+        // Sample the primary ray.
+        for _ in 0..ISTEPS {
+            // Calculate the primary ray sample position.
+            let i_pos = r0 + r * (i_depth + i_step_size * 0.5);
+
+            // Calculate the height of the sample.
+            let i_height = i_pos.length() - self.planet_radius;
+
+            // Calculate the optical depth of the Rayleigh and Mie scattering for this step.
+            let od_step_rlh = (-i_height / sh_rlh).exp() * i_step_size;
+            let od_step_mie = (-i_height / sh_mie).exp() * i_step_size;
+            let od_step_ozone = self.ozone_density(i_height) * i_step_size;
+
+            // Accumulate optical depth.
+            i_od_rlh += od_step_rlh;
+            i_od_mie += od_step_mie;
+            i_od_ozone += od_step_ozone;
+
+            // Calculate the step size of the secondary ray.
+            let j_step_size = rsi(p_sun, i_pos, self.atmosphere_radius).y / JSTEPS as f32;
+
+            // Initialize the secondary ray depth.
+            let mut j_depth = 0.0;
+
+            // Initialize optical depth accumulators for the secondary ray.
+            let mut j_od_rlh = 0.0;
+            let mut j_od_mie = 0.0;
+            let mut j_od_ozone = 0.0;
+
+            // Sample the secondary ray.
+            for _ in 0..JSTEPS {
+                // Calculate the secondary ray sample position.
+                let j_pos = i_pos + p_sun * (j_depth + j_step_size * 0.5);
+
+                // Calculate the height of the sample.
+                let j_height = j_pos.length() - self.planet_radius;
+
+                // Accumulate the optical depth.
+                j_od_rlh += (-j_height / sh_rlh).exp() * j_step_size;
+                j_od_mie += (-j_height / sh_mie).exp() * j_step_size;
+                j_od_ozone += self.ozone_density(j_height) * j_step_size;
+
+                // Increment the secondary ray depth.
+                j_depth += j_step_size;
+            }
+
+            // Calculate attenuation.
+            let attn = (-(k_mie * (i_od_mie + j_od_mie)
+                + k_rlh * (i_od_rlh + j_od_rlh)
+                + k_ozone * (i_od_ozone + j_od_ozone)))
+                .exp();
+
+            // Accumulate scattering.
+            total_rlh += od_step_rlh * attn;
+            total_mie += od_step_mie * attn;
+
+            // Increment the primary ray depth.
+            i_depth += i_step_size;
+        }
+
+        // Calculate and return the final color.
+        sun_intensity * (p_rlh * k_rlh * total_rlh + p_mie * k_mie * total_mie)
+    }
+
+    /// Port of `render_night`, the moon, stars and Milky Way faded in as the sun sets.
+    fn night(&self, r: Vec3, attn: Vec3) -> Vec3 {
+        let r0 = self.ray_origin;
+        let night = 1.0
+            - smoothstep(
+                -0.15,
+                0.05,
+                self.sun_position.normalize().dot(r0.normalize()),
+            );
+
+        let p_moon = self.moon_position.normalize();
+        let moon = self.moon_disc_intensity
+            * moon_shading(r, p_moon, self.moon_phase, self.moon_angular_diameter);
+
+        let mut color = moon * attn;
+
+        if night > 0.0 {
+            let milky_way = milky_way(r);
+            let stars = stars(r, self.star_density * (1.0 + 2.0 * milky_way));
+            color += night
+                * attn
+                * (self.star_intensity * stars
+                    + self.milky_way_intensity * milky_way * Vec3::new(0.85, 0.88, 1.0));
+
+            // Moonlight scatters like a much weaker sun, depending on how much of the moon is lit.
+            let lit = 0.5 - 0.5 * (2.0 * PI * self.moon_phase).cos();
+            color += night * self.scatter(r, p_moon, self.moon_intensity * lit);
+        }
+
+        color
+    }
+
+    /// Calculates the fraction of light that reaches `ray_origin` from a direction, per color channel.
+    ///
+    /// This is the attenuation `nishita.wgsl` applies along the secondary ray,
+    /// evaluated from `ray_origin` to the edge of the atmosphere.
+    /// Directions blocked by the planet have no transmittance.
+    pub fn transmittance(&self, direction: Vec3) -> Vec3 {
+        let r = direction.normalize();
+        let r0 = self.ray_origin;
+
+        let planet = rsi(r, r0, self.planet_radius);
+        if planet.x <= planet.y && planet.x > 0.0 {
+            return Vec3::ZERO;
+        }
+
+        let step_size = rsi(r, r0, self.atmosphere_radius).y.max(0.0) / ISTEPS as f32;
+
+        let mut od_rlh = 0.0;
+        let mut od_mie = 0.0;
+        let mut od_ozone = 0.0;
+
+        for i in 0..ISTEPS {
+            let pos = r0 + r * (step_size * (i as f32 + 0.5));
+            let height = pos.length() - self.planet_radius;
+
+            od_rlh += (-height / self.rayleigh_scale_height).exp() * step_size;
+            od_mie += (-height / self.mie_scale_height).exp() * step_size;
+            od_ozone += self.ozone_density(height) * step_size;
+        }
+
+        (-(self.mie_coefficient * od_mie
+            + self.rayleigh_coefficient * od_rlh
+            + self.ozone_coefficient * od_ozone))
+            .exp()
+    }
+
+    /// Density of ozone at a height, a tent profile around `ozone_center_height`.
+    fn ozone_density(&self, height: f32) -> f32 {
+        // same as the shader, which would divide by zero without a width
+        let half_width = (0.5 * self.ozone_width).max(1e-3);
+        (1.0 - (height - self.ozone_center_height).abs() / half_width).max(0.0)
+    }
+}
+
+/// Ray-sphere intersection that assumes the sphere is centered at the origin.
+///
+/// No intersection when `result.x > result.y`.
+fn rsi(rd: Vec3, r0: Vec3, sr: f32) -> Vec2 {
+    let a = rd.dot(rd);
+    let b = 2.0 * rd.dot(r0);
+    let c = r0.dot(r0) - (sr * sr);
+    let d = (b * b) - (4.0 * a * c);
+
+    if d < 0.0 {
+        Vec2::new(1e5, -1e5)
+    } else {
+        Vec2::new((-b - d.sqrt()) / (2.0 * a), (-b + d.sqrt()) / (2.0 * a))
+    }
+}
+
+fn smoothstep(low: f32, high: f32, x: f32) -> f32 {
+    let t = ((x - low) / (high - low)).clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
+
+/// PCG hash.
+fn hash(v: u32) -> u32 {
+    let state = v.wrapping_mul(747796405).wrapping_add(2891336453);
+    let word = ((state >> ((state >> 28) + 4)) ^ state).wrapping_mul(277803737);
+    (word >> 22) ^ word
+}
+
+fn hash_cell(cell: IVec3) -> u32 {
+    hash(cell.x as u32 ^ hash(cell.y as u32 ^ hash(cell.z as u32)))
+}
+
+/// Maps a hash to `[0, 1)`.
+fn unit(h: u32) -> f32 {
+    (h >> 8) as f32 / 16777216.0
+}
+
+fn value_noise(p: Vec3) -> f32 {
+    let cell = p.floor();
+    let f = p - cell;
+    let t = f * f * (3.0 - 2.0 * f);
+    let c = cell.as_ivec3();
+    let corner = |x, y, z| unit(hash_cell(c + IVec3::new(x, y, z)));
+
+    let x00 = corner(0, 0, 0).lerp(corner(1, 0, 0), t.x);
+    let x10 = corner(0, 1, 0).lerp(corner(1, 1, 0), t.x);
+    let x01 = corner(0, 0, 1).lerp(corner(1, 0, 1), t.x);
+    let x11 = corner(0, 1, 1).lerp(corner(1, 1, 1), t.x);
+
+    x00.lerp(x10, t.y).lerp(x01.lerp(x11, t.y), t.z)
+}
+
+fn fbm(p: Vec3) -> f32 {
+    0.5 * value_noise(p) + 0.3 * value_noise(p * 2.03) + 0.2 * value_noise(p * 4.01)
+}
+
+/// Brightness of the Milky Way, a noisy band split by a dust lane.
+fn milky_way(r: Vec3) -> f32 {
+    let latitude = r.dot(GALACTIC_POLE);
+    let band = (-latitude * latitude / (2.0 * MILKY_WAY_WIDTH * MILKY_WAY_WIDTH)).exp();
+    let dust = (-latitude * latitude / (2.0 * 0.02 * 0.02)).exp() * fbm(r * 12.0);
+    band * (0.4 + 0.6 * fbm(r * 6.0)) * (1.0 - 0.7 * dust)
+}
+
+/// Procedural stars, with one star at most in each cell of a grid around the unit sphere.
+fn stars(r: Vec3, density: f32) -> Vec3 {
+    let p = r * STAR_CELLS;
+    let base = p.floor().as_ivec3();
+
+    let mut color = Vec3::ZERO;
+    for z in -1..=1 {
+        for y in -1..=1 {
+            for x in -1..=1 {
+                let cell = base + IVec3::new(x, y, z);
+                let h0 = hash_cell(cell);
+                if unit(h0) >= density {
+                    continue;
+                }
+
+                let h1 = hash(h0);
+                let h2 = hash(h1);
+                let h3 = hash(h2);
+                let h4 = hash(h3);
+                let h5 = hash(h4);
+
+                // Place the star on the sphere, keeping it only if it stays in its cell.
+                let center = (cell.as_vec3() + Vec3::new(unit(h1), unit(h2), unit(h3))).normalize()
+                    * STAR_CELLS;
+                if center.floor().as_ivec3() != cell {
+                    continue;
+                }
+
+                let d = (p - center).length() / STAR_RADIUS;
+                let brightness = unit(h4).powf(12.0) * (-0.5 * d * d).exp();
+                let tint = Vec3::new(1.0, 0.75, 0.55).lerp(Vec3::new(0.7, 0.8, 1.0), unit(h5));
+                color += tint * brightness;
+            }
+        }
+    }
+
+    color
+}
+
+/// Shading of the moon disc, from `0.0` outside the disc to `1.0` where it is fully lit.
+fn moon_shading(r: Vec3, moon: Vec3, phase: f32, angular_diameter: f32) -> f32 {
+    let radius = angular_diameter * 0.5;
+    let cos_angle = r.dot(moon);
+    if cos_angle < radius.cos() {
+        return 0.0;
+    }
+
+    // Position on the disc, with z facing the viewer.
+    let up = if moon.y.abs() > 0.999 {
+        Vec3::X
+    } else {
+        Vec3::Y
+    };
+    let tangent = up.cross(moon).normalize();
+    let bitangent = moon.cross(tangent);
+    let offset = (r - moon * cos_angle) / radius.sin();
+    let x = offset.dot(tangent);
+    let y = offset.dot(bitangent);
+    let normal = Vec3::new(x, y, (1.0 - x * x - y * y).max(0.0).sqrt());
+
+    // The sun is behind the moon when new and behind the viewer when full.
+    let phase_angle = 2.0 * PI * phase;
+    let light = Vec3::new(phase_angle.sin(), 0.0, -phase_angle.cos());
+
+    // Earthshine keeps the dark side faintly visible.
+    normal.dot(light).max(0.0) + 0.02
+}
+
 impl From<&Nishita> for Nishita {
     fn from(nishita: &Nishita) -> Self {
         nishita.clone()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Values from a double precision evaluation of `render_nishita` with the default parameters.
+    const REFERENCE: [(Vec3, [f32; 3]); 5] = [
+        (Vec3::Y, [6.642587e-2, 1.407704e-1, 2.186453e-1]),
+        (Vec3::ONE, [5.815012e-1, 7.199064e-1, 8.493405e-1]),
+        (
+            Vec3::new(1.0, 0.1, 0.0),
+            [5.289905e-1, 8.277152e-1, 9.131354e-1],
+        ),
+        (
+            Vec3::new(0.0, 0.2, -1.0),
+            [2.522377e-1, 4.786948e-1, 6.316279e-1],
+        ),
+        (Vec3::NEG_Y, [7.766725e-2, 1.001723e-1, 1.149822e-1]),
+    ];
+
+    fn assert_close(actual: LinearRgba, expected: [f32; 3]) {
+        for (a, e) in actual.to_f32_array_no_alpha().iter().zip(expected) {
+            assert!(
+                (a - e).abs() <= e * 1e-3,
+                "expected {expected:?}, got {actual:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn sample_matches_shader() {
+        // the reference doesn't include the sun disc
+        let nishita = Nishita {
+            sun_disc_intensity: 0.0,
+            ..default()
+        };
+        for (direction, expected) in REFERENCE {
+            assert_close(nishita.sample(direction), expected);
+        }
+    }
+
+    #[test]
+    fn transmittance_matches_reference() {
+        let nishita = Nishita::default();
+        let reference = [
+            (Vec3::Y, Vec3::new(9.586873e-1, 9.104258e-1, 8.533542e-1)),
+            (
+                Vec3::new(1.0, 0.05, 0.0),
+                Vec3::new(4.720030e-1, 2.124264e-1, 7.809666e-2),
+            ),
+            (Vec3::X, Vec3::new(9.306985e-2, 1.428998e-2, 1.364888e-3)),
+        ];
+        for (direction, expected) in reference {
+            let actual = nishita.transmittance(direction);
+            assert!(
+                (actual - expected).abs().cmple(expected * 1e-3).all(),
+                "expected {expected}, got {actual}"
+            );
+        }
+    }
+
+    #[test]
+    fn transmittance_below_horizon_is_zero() {
+        let nishita = Nishita::default();
+        assert_eq!(nishita.transmittance(Vec3::new(1.0, -0.2, 0.0)), Vec3::ZERO);
+    }
+
+    #[test]
+    fn sample_ignores_direction_length() {
+        let nishita = Nishita::default();
+        let direction = Vec3::new(0.3, 0.5, -0.2);
+        let expected = nishita.sample(direction).to_f32_array_no_alpha();
+        assert_close(nishita.sample(direction * 40.0), expected);
+    }
+
+    #[test]
+    fn sample_is_opaque() {
+        assert_eq!(Nishita::default().sample(Vec3::X).alpha, 1.0);
+    }
+
+    #[test]
+    fn sample_missing_atmosphere_is_black() {
+        let nishita = Nishita {
+            ray_origin: Vec3::new(0.0, 7000e3, 0.0),
+            ..default()
+        };
+        assert_eq!(nishita.sample(Vec3::X), LinearRgba::BLACK);
+    }
+
+    #[test]
+    fn sun_disc_is_limb_darkened() {
+        let nishita = Nishita {
+            sun_position: Vec3::Y,
+            ..default()
+        };
+        let radius = nishita.sun_angular_diameter * 0.5;
+        let at = |angle: f32| nishita.sample(Vec3::new(angle.sin(), angle.cos(), 0.0));
+
+        let center = at(0.0);
+        let limb = at(radius * 0.95);
+        let outside = at(radius * 1.05);
+
+        assert!(center.red > 50.0 * outside.red);
+        assert!(limb.red < center.red && limb.red > outside.red);
+        // blue darkens the most towards the limb
+        assert!(limb.blue / center.blue < limb.red / center.red);
+    }
+
+    #[test]
+    fn sun_disc_is_attenuated_by_the_atmosphere() {
+        let noon = Nishita {
+            sun_position: Vec3::Y,
+            ..default()
+        };
+        let sunset = Nishita {
+            sun_position: Vec3::new(1.0, 0.02, 0.0),
+            ..default()
+        };
+        let disc = |nishita: &Nishita| {
+            let sky = Nishita {
+                sun_disc_intensity: 0.0,
+                ..nishita.clone()
+            };
+            let direction = nishita.sun_position;
+            let disc = nishita.sample(direction).to_vec3() - sky.sample(direction).to_vec3();
+            let expected = nishita.sun_disc_intensity * nishita.transmittance(direction);
+            assert!((disc - expected).abs().cmple(expected * 1e-3 + 1e-5).all());
+            disc
+        };
+
+        let (noon, sunset) = (disc(&noon), disc(&sunset));
+        assert!(sunset.x < noon.x);
+        assert!(sunset.z / sunset.x < noon.z / noon.x);
+    }
+
+    #[test]
+    fn sun_disc_can_be_hidden() {
+        let nishita = Nishita {
+            sun_disc_intensity: 0.0,
+            ..default()
+        };
+        assert!(nishita.sample(nishita.sun_position).red < 10.0);
+    }
+
+    #[test]
+    fn ozone_is_disabled_by_default() {
+        let nishita = Nishita::default();
+        let ozone = Nishita {
+            ozone_center_height: 10e3,
+            ozone_width: 50e3,
+            ..default()
+        };
+        for direction in [Vec3::Y, Vec3::new(1.0, 0.1, 0.0), Vec3::NEG_Y] {
+            assert_eq!(nishita.sample(direction), ozone.sample(direction));
+        }
+    }
+
+    #[test]
+    fn ozone_without_width_is_finite() {
+        let nishita = Nishita {
+            ozone_coefficient: Vec3::new(0.65e-6, 1.881e-6, 0.085e-6),
+            ozone_center_height: 0.0,
+            ozone_width: 0.0,
+            ..default()
+        };
+        assert_eq!(nishita.ozone_density(0.0), 1.0);
+        assert_eq!(nishita.ozone_density(1.0), 0.0);
+        for direction in [Vec3::Y, Vec3::new(1.0, 0.1, 0.0), Vec3::NEG_Y] {
+            assert!(nishita.sample(direction).to_vec3().is_finite());
+        }
+    }
+
+    #[test]
+    fn ozone_keeps_twilight_zenith_blue() {
+        let twilight = Nishita {
+            sun_position: Vec3::new(1.0, 0.02, 0.0),
+            sun_disc_intensity: 0.0,
+            ..default()
+        };
+        let ozone = Nishita {
+            ozone_coefficient: Vec3::new(0.65e-6, 1.881e-6, 0.085e-6),
+            ..twilight.clone()
+        };
+
+        let without = twilight.sample(Vec3::Y);
+        let with = ozone.sample(Vec3::Y);
+        assert!(with.green < without.green);
+        assert!(with.blue / with.red > without.blue / without.red);
+
+        // absorbed along the primary ray as well
+        let transmittance = ozone.transmittance(Vec3::Y) / twilight.transmittance(Vec3::Y);
+        assert!(transmittance.y < transmittance.z && transmittance.z < 1.0);
+    }
+
+    fn night() -> Nishita {
+        Nishita {
+            sun_position: Vec3::new(0.0, -1.0, 0.3),
+            ..default()
+        }
+    }
+
+    #[test]
+    fn stars_appear_at_night() {
+        let nishita = night();
+        let dark = Nishita {
+            star_intensity: 0.0,
+            ..night()
+        };
+
+        // sweep a ring of directions fine enough to cross many star cells
+        let lit = (0..4000)
+            .map(|i| {
+                let angle = i as f32 / 4000.0 * std::f32::consts::TAU;
+                Vec3::new(angle.cos(), 0.5, angle.sin())
+            })
+            .filter(|&direction| {
+                let star = nishita.sample(direction).red - dark.sample(direction).red;
+                star > 1e-3
+            })
+            .count();
+        assert!(lit > 20, "only {lit} directions hit a star");
+    }
+
+    #[test]
+    fn stars_are_hidden_by_day_and_below_horizon() {
+        let no_stars = |nishita: &Nishita| Nishita {
+            star_intensity: 0.0,
+            milky_way_intensity: 0.0,
+            ..nishita.clone()
+        };
+
+        let day = Nishita::default();
+        let night = night();
+        for i in 0..200 {
+            let angle = i as f32 / 200.0 * std::f32::consts::TAU;
+            let up = Vec3::new(angle.cos(), 0.5, angle.sin());
+            assert_eq!(day.sample(up), no_stars(&day).sample(up));
+
+            let down = Vec3::new(angle.cos(), -0.2, angle.sin());
+            assert_eq!(night.sample(down), no_stars(&night).sample(down));
+        }
+    }
+
+    #[test]
+    fn milky_way_is_a_band() {
+        let nishita = Nishita {
+            star_intensity: 0.0,
+            moon_intensity: 0.0,
+            ..night()
+        };
+        let on_band = GALACTIC_POLE.cross(Vec3::Z).normalize();
+        assert!(on_band.y > 0.1);
+        assert!(nishita.sample(on_band).blue > 4.0 * nishita.sample(GALACTIC_POLE).blue);
+    }
+
+    #[test]
+    fn moon_disc_follows_phase() {
+        let moon_position = Vec3::new(0.0, 1.0, -1.0);
+        let full = Nishita {
+            moon_position,
+            ..night()
+        };
+        let new = Nishita {
+            moon_phase: 0.0,
+            ..full.clone()
+        };
+
+        let center = full.sample(moon_position).red;
+        let beside = full.sample(moon_position + Vec3::new(0.05, 0.0, 0.0)).red;
+        assert!(center > 1.0);
+        assert!(center > 20.0 * beside);
+
+        // only earthshine, and no moonlight in the sky
+        assert!(new.sample(moon_position).red < 0.1 * center);
+        assert!(new.sample(Vec3::Y).red < full.sample(Vec3::Y).red);
+    }
+}
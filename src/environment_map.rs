@@ -0,0 +1,655 @@
+//! Provides [`AtmosphereEnvironmentMapPlugin`], a `Plugin` for lighting PBR materials with the procedural sky.
+//!
+//! Whenever a sky is rendered, it is filtered into an [`AtmosphereEnvironmentMap`]:
+//! a diffuse irradiance map and a GGX prefiltered specular map with a full mip chain.
+//! These are added to every [`AtmosphereCamera`] as an `EnvironmentMapLight`.
+//!
+//! Add it after [`AtmospherePlugin`](crate::plugin::AtmospherePlugin),
+//! since it needs the [`AtmospherePipelinePlugin`](crate::pipeline::AtmospherePipelinePlugin).
+//!
+//! ```no_run
+//! # use bevy::prelude::*;
+//! # use bevy_atmosphere::prelude::*;
+//! App::new()
+//!     .add_plugins((
+//!         DefaultPlugins,
+//!         AtmospherePlugin,
+//!         AtmosphereEnvironmentMapPlugin::default(),
+//!     ))
+//!     .run();
+//! ```
+
+use bevy::{
+    asset::{load_internal_asset, weak_handle},
+    platform::collections::{HashMap, HashSet},
+    prelude::*,
+    render::{
+        graph::CameraDriverLabel,
+        render_asset::{RenderAssetUsages, RenderAssets},
+        render_graph::{self, RenderGraph, RenderLabel},
+        render_resource::{
+            binding_types::{sampler, texture_cube, texture_storage_2d_array, uniform_buffer},
+            encase::UniformBuffer,
+            BindGroupEntries, BindGroupLayout, BindGroupLayoutEntries, Buffer,
+            BufferInitDescriptor, BufferUsages, CachedComputePipelineId, CachedPipelineState,
+            ComputePassDescriptor, ComputePipelineDescriptor, Extent3d, PipelineCache, Sampler,
+            SamplerBindingType, SamplerDescriptor, ShaderStages, ShaderType, StorageTextureAccess,
+            TextureDimension, TextureFormat, TextureSampleType, TextureUsages,
+            TextureViewDescriptor, TextureViewDimension,
+        },
+        renderer::{RenderContext, RenderDevice},
+        texture::GpuImage,
+        Extract, Render, RenderApp, RenderSet,
+    },
+};
+
+use crate::{
+    pipeline::{
+        queue_atmosphere_skies, AtmosphereImage, AtmosphereSkies, AtmosphereSky, AtmosphereSkyId,
+        AtmosphereSkyQueue, BevyAtmosphereLabel, WORKGROUP_SIZE,
+    },
+    plugin::AtmosphereCamera,
+    settings::{AtmosphereProjection, AtmosphereSettings},
+};
+
+/// The `Handle` for the shader that filters the sky into environment maps.
+pub const ATMOSPHERE_ENVIRONMENT_MAP_SHADER_HANDLE: Handle<Shader> =
+    weak_handle!("0199f1a4-6c2e-7c1b-9f3e-5a4d2b8e7c10");
+
+/// Number of samples taken per texel of the specular map.
+const SPECULAR_SAMPLE_COUNT: u32 = 128;
+
+/// Number of samples taken per texel of the diffuse map.
+const DIFFUSE_SAMPLE_COUNT: u32 = 512;
+
+/// Label of the environment map filtering `render_graph::Node`.
+#[derive(Debug, Hash, PartialEq, Eq, Clone, RenderLabel)]
+pub struct AtmosphereEnvironmentMapLabel;
+
+/// A `Plugin` that generates image-based lighting from the procedural sky.
+///
+/// It is also inserted as a `Resource`, so the settings can be read back.
+#[derive(Resource, Debug, Clone, Copy)]
+pub struct AtmosphereEnvironmentMapPlugin {
+    /// Resolution of a face of the specular map (Default: `256`).
+    ///
+    /// The specular map has a mip for every power of two down to `1`.
+    pub specular_resolution: u32,
+    /// Resolution of a face of the diffuse map (Default: `32`).
+    pub diffuse_resolution: u32,
+    /// Intensity of the `EnvironmentMapLight` added to [`AtmosphereCamera`]s (Default: `1000.0`).
+    pub intensity: f32,
+}
+
+impl Default for AtmosphereEnvironmentMapPlugin {
+    fn default() -> Self {
+        Self {
+            specular_resolution: 256,
+            diffuse_resolution: 32,
+            intensity: 1000.0,
+        }
+    }
+}
+
+impl AtmosphereEnvironmentMapPlugin {
+    fn specular_mip_count(&self) -> u32 {
+        self.specular_resolution.max(1).ilog2() + 1
+    }
+}
+
+/// The roughness the specular map is filtered with at `mip`, from `0` at the first mip to `1` at the last.
+fn specular_roughness(mip: u32, mip_count: u32) -> f32 {
+    mip as f32 / (mip_count - 1).max(1) as f32
+}
+
+/// The environment maps generated from a sky.
+///
+/// As a `Resource`, it is generated from the global sky.
+/// As a `Component`, it is generated from the [`AtmosphereImage`] on the same entity.
+#[derive(Resource, Component, Debug, Clone)]
+pub struct AtmosphereEnvironmentMap {
+    /// `Handle` to the diffuse irradiance cubemap.
+    pub diffuse: Handle<Image>,
+    /// `Handle` to the GGX prefiltered specular cubemap, where roughness increases with each mip.
+    pub specular: Handle<Image>,
+}
+
+impl AtmosphereEnvironmentMap {
+    fn new(images: &mut Assets<Image>, settings: &AtmosphereEnvironmentMapPlugin) -> Self {
+        Self {
+            diffuse: images.add(new_cube_image(settings.diffuse_resolution, 1)),
+            specular: images.add(new_cube_image(
+                settings.specular_resolution,
+                settings.specular_mip_count(),
+            )),
+        }
+    }
+
+    /// Creates an `EnvironmentMapLight` that uses these environment maps.
+    pub fn light(&self, intensity: f32) -> EnvironmentMapLight {
+        EnvironmentMapLight {
+            diffuse_map: self.diffuse.clone(),
+            specular_map: self.specular.clone(),
+            intensity,
+            ..default()
+        }
+    }
+}
+
+fn new_cube_image(resolution: u32, mip_level_count: u32) -> Image {
+    let mut image = Image::new_uninit(
+        Extent3d {
+            width: resolution,
+            height: resolution,
+            depth_or_array_layers: 6,
+        },
+        TextureDimension::D2,
+        TextureFormat::Rgba16Float,
+        RenderAssetUsages::RENDER_WORLD,
+    );
+
+    image.texture_descriptor.mip_level_count = mip_level_count;
+    image.texture_descriptor.usage =
+        TextureUsages::STORAGE_BINDING | TextureUsages::TEXTURE_BINDING;
+    image.texture_view_descriptor = Some(TextureViewDescriptor {
+        dimension: Some(TextureViewDimension::Cube),
+        ..default()
+    });
+
+    image
+}
+
+impl Plugin for AtmosphereEnvironmentMapPlugin {
+    fn build(&self, app: &mut App) {
+        // there's nothing to light without rendering, see `AtmospherePlugin`
+        if app.get_sub_app(RenderApp).is_none() {
+            return;
+        }
+
+        load_internal_asset!(
+            app,
+            ATMOSPHERE_ENVIRONMENT_MAP_SHADER_HANDLE,
+            "shaders/environment_map.wgsl",
+            Shader::from_wgsl
+        );
+
+        let environment_map = {
+            let mut image_assets = app.world_mut().resource_mut::<Assets<Image>>();
+            AtmosphereEnvironmentMap::new(&mut image_assets, self)
+        };
+
+        app.insert_resource(*self)
+            .insert_resource(environment_map)
+            .add_systems(
+                PostUpdate,
+                (
+                    environment_map_added,
+                    environment_map_removed,
+                    environment_map_light,
+                )
+                    .chain()
+                    .after(crate::pipeline::atmosphere_model_added),
+            );
+
+        let render_app = app.sub_app_mut(RenderApp);
+        render_app
+            .insert_resource(*self)
+            .init_resource::<ExtractedEnvironmentMaps>()
+            .init_resource::<EnvironmentMapQueue>()
+            .add_systems(ExtractSchedule, extract_environment_maps)
+            .add_systems(
+                Render,
+                queue_environment_maps
+                    .in_set(RenderSet::PrepareBindGroups)
+                    .after(queue_atmosphere_skies),
+            );
+
+        let mut render_graph = render_app.world_mut().resource_mut::<RenderGraph>();
+        render_graph.add_node(AtmosphereEnvironmentMapLabel, AtmosphereEnvironmentMapNode);
+        render_graph.add_node_edge(BevyAtmosphereLabel, AtmosphereEnvironmentMapLabel);
+        render_graph.add_node_edge(AtmosphereEnvironmentMapLabel, CameraDriverLabel);
+    }
+
+    fn finish(&self, app: &mut App) {
+        if let Some(render_app) = app.get_sub_app_mut(RenderApp) {
+            render_app.init_resource::<AtmosphereEnvironmentMapPipeline>();
+        }
+    }
+}
+
+/// Gives entities with their own [`AtmosphereImage`] their own [`AtmosphereEnvironmentMap`].
+fn environment_map_added(
+    mut commands: Commands,
+    mut image_assets: ResMut<Assets<Image>>,
+    settings: Res<AtmosphereEnvironmentMapPlugin>,
+    images: Query<Entity, (With<AtmosphereImage>, Without<AtmosphereEnvironmentMap>)>,
+) {
+    for entity in &images {
+        commands
+            .entity(entity)
+            .insert(AtmosphereEnvironmentMap::new(&mut image_assets, &settings));
+    }
+}
+
+/// Removes the [`AtmosphereEnvironmentMap`] of entities that lost their [`AtmosphereImage`].
+fn environment_map_removed(
+    mut commands: Commands,
+    mut removed_images: RemovedComponents<AtmosphereImage>,
+) {
+    for entity in removed_images.read() {
+        if let Ok(mut entity_commands) = commands.get_entity(entity) {
+            entity_commands.try_remove::<AtmosphereEnvironmentMap>();
+        }
+    }
+}
+
+/// Keeps the `EnvironmentMapLight` of [`AtmosphereCamera`]s pointed at the environment maps of their sky.
+#[allow(clippy::type_complexity)]
+fn environment_map_light(
+    mut commands: Commands,
+    settings: Res<AtmosphereEnvironmentMapPlugin>,
+    environment_map: Res<AtmosphereEnvironmentMap>,
+    changed_cameras: Query<
+        (Entity, Option<&AtmosphereEnvironmentMap>),
+        (
+            With<AtmosphereCamera>,
+            Or<(Added<AtmosphereCamera>, Changed<AtmosphereEnvironmentMap>)>,
+        ),
+    >,
+    cameras: Query<(), With<AtmosphereCamera>>,
+    mut removed_environment_maps: RemovedComponents<AtmosphereEnvironmentMap>,
+    mut removed_cameras: RemovedComponents<AtmosphereCamera>,
+) {
+    let removed = removed_environment_maps
+        .read()
+        .filter(|camera| cameras.contains(*camera))
+        .map(|camera| (camera, None));
+
+    for (camera, camera_environment_map) in changed_cameras.iter().chain(removed) {
+        let light = camera_environment_map
+            .unwrap_or(&environment_map)
+            .light(settings.intensity);
+        commands.entity(camera).insert(light);
+    }
+
+    for camera in removed_cameras.read() {
+        if let Ok(mut entity_commands) = commands.get_entity(camera) {
+            entity_commands.try_remove::<EnvironmentMapLight>();
+        }
+    }
+}
+
+/// The environment maps of every sky, extracted from the main world.
+#[derive(Resource, Default)]
+struct ExtractedEnvironmentMaps(HashMap<AtmosphereSkyId, AtmosphereEnvironmentMap>);
+
+/// The skies waiting for their environment maps to be filtered.
+#[derive(Resource, Default)]
+struct EnvironmentMapQueue {
+    /// Skies that were rendered, but couldn't be filtered yet.
+    pending: HashSet<AtmosphereSkyId>,
+    /// Skies to filter during this frame.
+    ready: Vec<AtmosphereSkyId>,
+}
+
+fn extract_environment_maps(
+    mut extracted: ResMut<ExtractedEnvironmentMaps>,
+    main_environment_map: Extract<Option<Res<AtmosphereEnvironmentMap>>>,
+    main_entities: Extract<Query<(Entity, &AtmosphereEnvironmentMap)>>,
+) {
+    extracted.0.clear();
+
+    if let Some(environment_map) = &*main_environment_map {
+        extracted
+            .0
+            .insert(AtmosphereSkyId::Global, (**environment_map).clone());
+    }
+
+    for (entity, environment_map) in &main_entities {
+        extracted
+            .0
+            .insert(AtmosphereSkyId::Entity(entity), environment_map.clone());
+    }
+}
+
+/// Decides which rendered skies can be filtered this frame.
+#[allow(clippy::too_many_arguments)]
+fn queue_environment_maps(
+    mut queue: ResMut<EnvironmentMapQueue>,
+    sky_queue: Res<AtmosphereSkyQueue>,
+    extracted: Res<ExtractedEnvironmentMaps>,
+    pipeline: Res<AtmosphereEnvironmentMapPipeline>,
+    pipeline_cache: Res<PipelineCache>,
+    gpu_images: Res<RenderAssets<GpuImage>>,
+    settings: Res<AtmosphereSettings>,
+    skies: Res<AtmosphereSkies>,
+) {
+    let queue = &mut *queue;
+    // a time-sliced sky only changes once its last slice is rendered
+    queue.pending.extend(
+        sky_queue
+            .0
+            .iter()
+            .filter(|id| skies.0.get(*id).is_some_and(AtmosphereSky::image_updated))
+            .copied(),
+    );
+    queue.ready.clear();
+
+    // the filters sample the sky as a cube
+    if settings.projection != AtmosphereProjection::Cubemap {
+        if !queue.pending.is_empty() && !extracted.0.is_empty() {
+            warn_once!(
+                "Environment maps need the `AtmosphereProjection::Cubemap` projection, they won't be updated"
+            );
+        }
+        queue.pending.clear();
+        return;
+    }
+
+    let ready = |id: CachedComputePipelineId| {
+        matches!(
+            pipeline_cache.get_compute_pipeline_state(id),
+            CachedPipelineState::Ok(_)
+        )
+    };
+    if !ready(pipeline.specular) || !ready(pipeline.diffuse) {
+        return;
+    }
+
+    queue.pending.retain(|id| {
+        let Some(environment_map) = extracted.0.get(id) else {
+            // the sky has no environment map (anymore)
+            return false;
+        };
+        if gpu_images.get(&environment_map.diffuse).is_some()
+            && gpu_images.get(&environment_map.specular).is_some()
+        {
+            queue.ready.push(*id);
+            false
+        } else {
+            true
+        }
+    });
+}
+
+/// The `Filter` struct in `environment_map.wgsl`.
+#[derive(ShaderType)]
+struct Filter {
+    roughness: f32,
+    sample_count: u32,
+}
+
+#[derive(Resource)]
+struct AtmosphereEnvironmentMapPipeline {
+    layout: BindGroupLayout,
+    /// The layout of the mip a filter renders to.
+    image_layout: BindGroupLayout,
+    sampler: Sampler,
+    specular: CachedComputePipelineId,
+    diffuse: CachedComputePipelineId,
+    /// A `Filter` for each mip of the specular map.
+    specular_filters: Vec<Buffer>,
+    diffuse_filter: Buffer,
+}
+
+impl FromWorld for AtmosphereEnvironmentMapPipeline {
+    fn from_world(world: &mut World) -> Self {
+        let render_device = world.resource::<RenderDevice>();
+        let settings = world.resource::<AtmosphereEnvironmentMapPlugin>();
+
+        let layout = render_device.create_bind_group_layout(
+            "bevy_atmosphere_environment_map_bind_group_layout",
+            &BindGroupLayoutEntries::sequential(
+                ShaderStages::COMPUTE,
+                (
+                    texture_cube(TextureSampleType::Float { filterable: true }),
+                    sampler(SamplerBindingType::Filtering),
+                    uniform_buffer::<Filter>(false),
+                ),
+            ),
+        );
+        let image_layout = render_device.create_bind_group_layout(
+            "bevy_atmosphere_environment_map_image_bind_group_layout",
+            &BindGroupLayoutEntries::single(
+                ShaderStages::COMPUTE,
+                texture_storage_2d_array(
+                    TextureFormat::Rgba16Float,
+                    StorageTextureAccess::WriteOnly,
+                ),
+            ),
+        );
+
+        let sampler = render_device.create_sampler(&SamplerDescriptor {
+            label: Some("bevy_atmosphere_environment_map_sampler"),
+            mag_filter: bevy::render::render_resource::FilterMode::Linear,
+            min_filter: bevy::render::render_resource::FilterMode::Linear,
+            ..default()
+        });
+
+        let create_filter = |roughness: f32, sample_count: u32| {
+            let mut buffer = UniformBuffer::new(Vec::new());
+            buffer
+                .write(&Filter {
+                    roughness,
+                    sample_count,
+                })
+                .unwrap();
+            render_device.create_buffer_with_data(&BufferInitDescriptor {
+                label: Some("bevy_atmosphere_environment_map_filter"),
+                usage: BufferUsages::UNIFORM,
+                contents: buffer.as_ref(),
+            })
+        };
+
+        let mip_count = settings.specular_mip_count();
+        let specular_filters = (0..mip_count)
+            .map(|mip| create_filter(specular_roughness(mip, mip_count), SPECULAR_SAMPLE_COUNT))
+            .collect();
+        let diffuse_filter = create_filter(1.0, DIFFUSE_SAMPLE_COUNT);
+
+        let pipeline_cache = world.resource::<PipelineCache>();
+        let queue_pipeline = |entry_point: &'static str| {
+            pipeline_cache.queue_compute_pipeline(ComputePipelineDescriptor {
+                label: Some(
+                    format!("bevy_atmosphere_environment_map_{entry_point}_pipeline").into(),
+                ),
+                layout: vec![layout.clone(), image_layout.clone()],
+                push_constant_ranges: vec![],
+                shader: ATMOSPHERE_ENVIRONMENT_MAP_SHADER_HANDLE,
+                shader_defs: vec![],
+                entry_point: entry_point.into(),
+                zero_initialize_workgroup_memory: true,
+            })
+        };
+        let specular = queue_pipeline("specular");
+        let diffuse = queue_pipeline("diffuse");
+
+        Self {
+            layout,
+            image_layout,
+            sampler,
+            specular,
+            diffuse,
+            specular_filters,
+            diffuse_filter,
+        }
+    }
+}
+
+/// Filters the skies in the [`EnvironmentMapQueue`] into their environment maps.
+struct AtmosphereEnvironmentMapNode;
+
+impl render_graph::Node for AtmosphereEnvironmentMapNode {
+    fn run(
+        &self,
+        _graph: &mut render_graph::RenderGraphContext,
+        render_context: &mut RenderContext,
+        world: &World,
+    ) -> Result<(), render_graph::NodeRunError> {
+        let queue = world.resource::<EnvironmentMapQueue>();
+        if queue.ready.is_empty() {
+            return Ok(());
+        }
+
+        let skies = world.resource::<AtmosphereSkies>();
+        let extracted = world.resource::<ExtractedEnvironmentMaps>();
+        let pipeline = world.resource::<AtmosphereEnvironmentMapPipeline>();
+        let pipeline_cache = world.resource::<PipelineCache>();
+        let gpu_images = world.resource::<RenderAssets<GpuImage>>();
+
+        let (Some(specular_pipeline), Some(diffuse_pipeline)) = (
+            pipeline_cache.get_compute_pipeline(pipeline.specular),
+            pipeline_cache.get_compute_pipeline(pipeline.diffuse),
+        ) else {
+            return Ok(());
+        };
+
+        let render_device = render_context.render_device().clone();
+        let mut pass =
+            render_context
+                .command_encoder()
+                .begin_compute_pass(&ComputePassDescriptor {
+                    label: Some("atmosphere_environment_map_pass"),
+                    timestamp_writes: None,
+                });
+
+        for id in &queue.ready {
+            let (Some(sky), Some(environment_map)) = (skies.0.get(id), extracted.0.get(id)) else {
+                continue;
+            };
+            let (Some(sky_image), Some(diffuse), Some(specular)) = (
+                gpu_images.get(&sky.image.handle),
+                gpu_images.get(&environment_map.diffuse),
+                gpu_images.get(&environment_map.specular),
+            ) else {
+                continue;
+            };
+
+            let targets = pipeline
+                .specular_filters
+                .iter()
+                .enumerate()
+                .map(|(mip, filter)| (specular, mip as u32, filter, specular_pipeline))
+                .chain(std::iter::once((
+                    diffuse,
+                    0,
+                    &pipeline.diffuse_filter,
+                    diffuse_pipeline,
+                )));
+
+            for (target, mip, filter, compute_pipeline) in targets {
+                if mip >= target.mip_level_count {
+                    break;
+                }
+
+                let bind_group = render_device.create_bind_group(
+                    "bevy_atmosphere_environment_map_bind_group",
+                    &pipeline.layout,
+                    &BindGroupEntries::sequential((
+                        &sky_image.texture_view,
+                        &pipeline.sampler,
+                        filter.as_entire_binding(),
+                    )),
+                );
+
+                let view = target.texture.create_view(&TextureViewDescriptor {
+                    label: Some("atmosphere_environment_map_mip_view"),
+                    format: Some(TextureFormat::Rgba16Float),
+                    dimension: Some(TextureViewDimension::D2Array),
+                    base_mip_level: mip,
+                    mip_level_count: Some(1),
+                    base_array_layer: 0,
+                    array_layer_count: Some(6),
+                    ..default()
+                });
+                let image_bind_group = render_device.create_bind_group(
+                    "bevy_atmosphere_environment_map_image_bind_group",
+                    &pipeline.image_layout,
+                    &BindGroupEntries::single(&view),
+                );
+
+                let size = (target.size.width >> mip).max(1);
+                let workgroups = size.div_ceil(WORKGROUP_SIZE);
+
+                pass.set_pipeline(compute_pipeline);
+                pass.set_bind_group(0, &bind_group, &[]);
+                pass.set_bind_group(1, &image_bind_group, &[]);
+                pass.dispatch_workgroups(workgroups, workgroups, 6);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn specular_mips_go_down_to_one_texel() {
+        let plugin = |specular_resolution| AtmosphereEnvironmentMapPlugin {
+            specular_resolution,
+            ..default()
+        };
+        assert_eq!(plugin(256).specular_mip_count(), 9);
+        assert_eq!(plugin(300).specular_mip_count(), 9);
+        assert_eq!(plugin(1).specular_mip_count(), 1);
+        assert_eq!(plugin(0).specular_mip_count(), 1);
+    }
+
+    #[test]
+    fn specular_roughness_spans_the_mips() {
+        assert_eq!(specular_roughness(0, 9), 0.0);
+        assert_eq!(specular_roughness(4, 9), 0.5);
+        assert_eq!(specular_roughness(8, 9), 1.0);
+        assert_eq!(specular_roughness(0, 1), 0.0);
+    }
+
+    #[test]
+    fn plugin_does_nothing_without_rendering() {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, AtmosphereEnvironmentMapPlugin::default()));
+        app.finish();
+        app.cleanup();
+        app.update();
+
+        assert!(!app
+            .world()
+            .contains_resource::<AtmosphereEnvironmentMapPlugin>());
+        assert!(!app.world().contains_resource::<AtmosphereEnvironmentMap>());
+    }
+
+    #[test]
+    fn cameras_are_lit_by_their_sky() {
+        let environment_map = |id| AtmosphereEnvironmentMap {
+            diffuse: Handle::weak_from_u128(id),
+            specular: Handle::weak_from_u128(id + 1),
+        };
+        let mut world = World::new();
+        world.insert_resource(AtmosphereEnvironmentMapPlugin::default());
+        world.insert_resource(environment_map(1));
+
+        let global = world.spawn(AtmosphereCamera::default()).id();
+        let own = world
+            .spawn((AtmosphereCamera::default(), environment_map(3)))
+            .id();
+        world.run_system_cached(environment_map_light).unwrap();
+
+        let light = world.get::<EnvironmentMapLight>(global).unwrap();
+        assert_eq!(light.diffuse_map, Handle::weak_from_u128(1));
+        assert_eq!(light.intensity, 1000.0);
+        let light = world.get::<EnvironmentMapLight>(own).unwrap();
+        assert_eq!(light.specular_map, Handle::weak_from_u128(4));
+
+        // back to the global sky, then unlit
+        world.entity_mut(own).remove::<AtmosphereEnvironmentMap>();
+        world.run_system_cached(environment_map_light).unwrap();
+        let light = world.get::<EnvironmentMapLight>(own).unwrap();
+        assert_eq!(light.diffuse_map, Handle::weak_from_u128(1));
+
+        world.entity_mut(global).remove::<AtmosphereCamera>();
+        world.run_system_cached(environment_map_light).unwrap();
+        assert!(world.get::<EnvironmentMapLight>(global).is_none());
+    }
+}
@@ -0,0 +1,129 @@
+//! Provides [`AtmosphereFog`], a `Component` for fading scene geometry into the sky with `DistanceFog`.
+
+use bevy::{pbr::DistanceFog, prelude::*, render::camera::Exposure};
+
+use crate::{
+    clouds::sun_direction,
+    model::{sky_sampler, AtmosphereModel},
+};
+
+/// Angles around the horizon direction the colors of the horizon are averaged over, in radians.
+///
+/// They leave out the direction itself, so the disc of a sun on the horizon isn't part of the fog.
+const HORIZON_SPREAD: [f32; 4] = [-0.5, -0.2, 0.2, 0.5];
+
+/// A `Component` that keeps the `DistanceFog` of a camera in sync with the horizon of the sky.
+///
+/// The fog takes the color of the horizon away from the sun,
+/// and its `directional_light_color` adds the extra light of the horizon toward the sun,
+/// so distant geometry fades into the sky behind it.
+/// Only the colors follow the sky, the `falloff` of the fog is left as it is.
+///
+/// Follows the [`AtmosphereModel`] of the camera, or the global one,
+/// and leaves the fog alone for models without a CPU port (see [`sky_sampler`]).
+/// The sun is read from the model with [`sun_direction`].
+///
+/// This is not an aerial perspective: there is no volume of inscattering and transmittance computed from the model,
+/// the fog is Bevy's `DistanceFog` with the colors of the sky.
+/// For that, use an [`AtmosphereAerialPerspective`](crate::aerial_perspective::AtmosphereAerialPerspective) with the `Nishita` model.
+#[derive(Component, Default, Debug, Clone, Copy)]
+#[require(DistanceFog)]
+pub struct AtmosphereFog;
+
+/// The colors of the horizon away from and toward the sun, each averaged over a few directions.
+///
+/// Without a sun, both look along -Z.
+pub fn horizon_colors(model: &AtmosphereModel) -> Option<(LinearRgba, LinearRgba)> {
+    let sample = sky_sampler(model)?;
+
+    let toward = sun_direction(model)
+        .and_then(|sun| Vec3::new(sun.x, 0.0, sun.z).try_normalize())
+        .unwrap_or(Vec3::NEG_Z);
+    let average = |forward: Vec3| {
+        let color = HORIZON_SPREAD
+            .iter()
+            .map(|angle| sample(Quat::from_rotation_y(*angle) * forward).to_vec3())
+            .sum::<Vec3>()
+            / HORIZON_SPREAD.len() as f32;
+        LinearRgba::rgb(color.x, color.y, color.z)
+    };
+
+    Some((average(-toward), average(toward)))
+}
+
+/// Updates the `DistanceFog` of [`AtmosphereFog`] cameras when the model, the lights or the camera change.
+#[allow(clippy::type_complexity)]
+pub(crate) fn atmosphere_fog(
+    atmosphere: Option<Res<AtmosphereModel>>,
+    mut cameras: Query<(
+        Ref<AtmosphereFog>,
+        &mut DistanceFog,
+        Option<Ref<AtmosphereModel>>,
+        Option<Ref<Exposure>>,
+    )>,
+    lights: Query<Ref<DirectionalLight>>,
+) {
+    let lights_changed = lights.iter().any(|light| light.is_changed());
+    // the fog scatters light of every directional light, the sun is the brightest
+    let sun = lights
+        .iter()
+        .map(|light| light.color.to_linear().to_vec3() * light.illuminance)
+        .max_by(|a, b| a.max_element().total_cmp(&b.max_element()));
+
+    for (fog_settings, mut fog, camera_model, exposure) in &mut cameras {
+        let Some(model) = camera_model.as_deref().or(atmosphere.as_deref()) else {
+            continue;
+        };
+        let model_changed = match &camera_model {
+            Some(model) => model.is_changed(),
+            None => atmosphere.as_ref().is_some_and(Res::is_changed),
+        };
+        if !model_changed
+            && !lights_changed
+            && !fog_settings.is_changed()
+            && !exposure.as_ref().is_some_and(Ref::is_changed)
+        {
+            continue;
+        }
+
+        let Some((away, toward)) = horizon_colors(model) else {
+            continue;
+        };
+        fog.color = away.with_alpha(fog.color.alpha()).into();
+
+        // the light of the horizon toward the sun, relative to the sunlight the fog scatters
+        let exposure = exposure.as_deref().copied().unwrap_or_default().exposure();
+        let excess = (toward.to_vec3() - away.to_vec3()).max(Vec3::ZERO);
+        let scale = sun.unwrap_or_default() * exposure;
+        let directional = Vec3::select(scale.cmpgt(Vec3::ZERO), excess / scale, Vec3::ZERO);
+        fog.directional_light_color =
+            Color::linear_rgb(directional.x, directional.y, directional.z);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "gradient")]
+    #[test]
+    fn gradient_fog_is_the_horizon() {
+        let gradient = crate::collection::gradient::Gradient::default();
+        let (away, toward) = horizon_colors(&AtmosphereModel::new(gradient.clone())).unwrap();
+        assert_eq!(away, gradient.horizon.with_alpha(1.0));
+        assert_eq!(away, toward);
+    }
+
+    #[cfg(feature = "nishita")]
+    #[test]
+    fn nishita_horizon_is_brighter_toward_the_sun() {
+        let nishita = crate::collection::nishita::Nishita {
+            sun_position: Vec3::new(1.0, 0.05, 0.0),
+            ..default()
+        };
+        let (away, toward) = horizon_colors(&AtmosphereModel::new(nishita)).unwrap();
+        assert!(toward.red > away.red);
+        // the disc of the sun is left out
+        assert!(toward.red < 10.0);
+    }
+}
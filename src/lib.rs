@@ -26,6 +26,12 @@
 //! To read and modify the atmospheric model, use the [`Atmosphere<T>`](crate::system_param::Atmosphere) and
 //! [`AtmosphereMut<T>`](crate::system_param::AtmosphereMut) system params or
 //! the [`AtmosphereModel`](struct@crate::model::AtmosphereModel) resource.
+//! Systems with these params are skipped while the model is of another type.
+//! To run them anyway, use [`OptionalAtmosphere<T>`](crate::system_param::OptionalAtmosphere) and
+//! [`OptionalAtmosphereMut<T>`](crate::system_param::OptionalAtmosphereMut), which are `None` in that case
+//! and take the place of `Option<Atmosphere<T>>` and `Option<AtmosphereMut<T>>`, which can't be system params.
+//! The sky is only rendered again when the model is written to, so values that rarely change can be written with
+//! [`AtmosphereMut::set_if_neq`](crate::system_param::AtmosphereMut::set_if_neq).
 //! ```no_run
 //! # use bevy::utils::default;
 //! # use bevy::math::Vec3;
@@ -50,13 +56,33 @@
 //! # ;
 //! ```
 //!
+//! To give a camera a sky of its own, add an [`AtmosphereModel`](struct@crate::model::AtmosphereModel) component to it.
+//! ```no_run
+//! # use bevy::utils::default;
+//! # use bevy::prelude::*;
+//! # use bevy::render::view::RenderLayers;
+//! # use bevy_atmosphere::prelude::*;
+//! fn setup(mut commands: Commands) {
+//!     commands.spawn((
+//!         Camera3d::default(),
+//!         RenderLayers::from_layers(&[0, 1]),
+//!         AtmosphereCamera {
+//!             // keeps other cameras from seeing this skybox
+//!             render_layers: Some(RenderLayers::layer(1)),
+//!         },
+//!         AtmosphereModel::new(Gradient::default()),
+//!     ));
+//! }
+//! # ;
+//! ```
+//!
 //! Use the [`AtmosphereSettings`](crate::settings::AtmosphereSettings) resource to change how the sky is rendered.
 //! ```no_run
 //! # use bevy::utils::default;
 //! use bevy_atmosphere::settings::AtmosphereSettings;
 //! # let _ =
 //! AtmosphereSettings {
-//!     // changes the resolution (should be a multiple of 8)
+//!     // changes the resolution
 //!     resolution: 1024,
 //!     // turns off dithering
 //!     dithering: false,
@@ -65,6 +91,22 @@
 //! # ;
 //! ```
 //!
+//! To cover the sky with procedural clouds, insert the [`AtmosphereClouds`](crate::clouds::AtmosphereClouds) resource
+//! (or add it as a component next to an [`AtmosphereModel`](struct@crate::model::AtmosphereModel) component).
+//! ```no_run
+//! # use bevy::utils::default;
+//! # use bevy::prelude::*;
+//! # use bevy_atmosphere::prelude::*;
+//! # let _ =
+//! AtmosphereClouds {
+//!     coverage: 0.6,
+//!     // flat stratus sheets instead of cumulus
+//!     cumulus: 0.0,
+//!     ..default()
+//! }
+//! # ;
+//! ```
+//!
 //! When using the `detection` feature, you can use [`SkyboxCreationMode`](crate::settings::SkyboxCreationMode) to control the size of the generated skybox.
 //! ```no_run
 //! # use bevy::utils::default;
@@ -78,26 +120,73 @@
 //! # ;
 //! ```
 //!
+//! `SkyboxCreationMode::Background` doesn't create a skybox at all: the sky is drawn behind everything else with Bevy's `Skybox`,
+//! which suits infinite reverse-Z projections and cameras with a depth prepass or TAA.
+//!
+//! To fade distant geometry into the sky, add an [`AtmosphereFog`](crate::fog::AtmosphereFog) component to the camera,
+//! which keeps its `DistanceFog` the color of the horizon.
+//! With the [`Nishita`](crate::collection::nishita::Nishita) model, an [`AtmosphereAerialPerspective`](crate::aerial_perspective::AtmosphereAerialPerspective)
+//! scatters the light of the atmosphere into the geometry instead, by its distance and direction from the camera.
+//!
 //! To see more examples, view the ["examples"](https://github.com/JonahPlusPlus/bevy_atmosphere/tree/master/examples) directory.
 
+// needs its optional dependencies, even for docs
+#[cfg(any(doc, feature = "nishita"))]
+pub mod aerial_perspective;
+#[cfg(feature = "bake")]
+pub mod bake;
+pub mod clouds;
 pub mod collection;
+pub mod environment_map;
+pub mod fog;
 pub mod model;
 pub mod pipeline;
 pub mod plugin;
+#[cfg(any(doc, feature = "presets"))]
+pub mod preset;
 pub mod settings;
 pub mod skybox;
+#[cfg(any(doc, feature = "nishita"))]
+pub mod solar;
+#[cfg(any(doc, feature = "nishita"))]
+pub mod sun;
 pub mod system_param;
+pub mod transition;
 
 pub mod prelude {
     //! `use bevy_atmosphere::prelude::*;` to import the most commonly used items.
+    //!
+    //! For a model that may be of another type, use [`OptionalAtmosphere<T>`] or [`OptionalAtmosphereMut<T>`]
+    //! instead of `Option<Atmosphere<T>>` or `Option<AtmosphereMut<T>>`:
+    //! Bevy only implements `SystemParam` for the `Option`s of its own params, and this crate can't implement it for `Option`.
+    pub use crate::clouds::AtmosphereClouds;
+    pub use crate::environment_map::AtmosphereEnvironmentMapPlugin;
+    pub use crate::fog::AtmosphereFog;
     pub use crate::model::{AddAtmosphereModel, AtmosphereModel, Atmospheric};
     pub use crate::plugin::{AtmosphereCamera, AtmospherePlugin};
-    pub use crate::settings::AtmosphereSettings;
-    pub use crate::system_param::{Atmosphere, AtmosphereMut};
+    pub use crate::settings::{
+        AtmosphereFormat, AtmosphereProjection, AtmosphereSettings, AtmosphereUpdateMode,
+    };
+    pub use crate::system_param::{
+        Atmosphere, AtmosphereMut, OptionalAtmosphere, OptionalAtmosphereMut,
+    };
+    pub use crate::transition::AtmosphereTransition;
+
+    #[cfg(any(doc, feature = "presets"))]
+    pub use crate::preset::{AtmospherePreset, AtmospherePresetHandle};
 
+    #[cfg(any(doc, feature = "nishita"))]
+    pub use crate::aerial_perspective::AtmosphereAerialPerspective;
     #[cfg(any(doc, feature = "nishita"))]
     pub use crate::collection::nishita::Nishita;
+    #[cfg(any(doc, feature = "nishita"))]
+    pub use crate::solar::SolarClock;
+    #[cfg(any(doc, feature = "nishita"))]
+    pub use crate::sun::AtmosphereSun;
 
     #[cfg(any(doc, feature = "gradient"))]
     pub use crate::collection::gradient::Gradient;
+
+    #[cfg(any(doc, feature = "hillaire"))]
+    pub use crate::collection::hillaire::Hillaire;
 }
@@ -27,16 +27,43 @@
 //! ```
 //!
 //! It can then be registered by calling [`AddAtmosphereModel::add_atmosphere_model`].
+//!
+//! # Multi-pass models
+//! Models that precompute lookup tables can declare intermediate passes with the `pass` attribute.
+//! Every pass runs its own entry point and renders to a texture of the given size, before the `main` entry point renders the sky.
+//! ```ignore
+//! # use bevy::prelude::*;
+//! # use bevy::render::render_resource::ShaderType;
+//! # use bevy_atmosphere::prelude::*;
+//! #[derive(Atmospheric, ShaderType, Reflect, Debug, Clone)]
+//! #[uniform(0, MyModel)]
+//! #[external("shader.wgsl")]
+//! // Runs `fn transmittance` over a 256x64 texture, then `fn sky_view` over a 192x108 texture.
+//! #[pass("transmittance", 256, 64)]
+//! #[pass("sky_view", 192, 108)]
+//! struct MyModel {
+//!     color: Color,
+//! }
+//! ```
+//!
+//! A pass writes to `@group(1) @binding(0)`, a `texture_storage_2d<rgba16float, write>`.
+//! The final pass writes to the `image` declared by `#import bevy_atmosphere::image::image`,
+//! whose format follows the [`AtmosphereFormat`](crate::settings::AtmosphereFormat) through shader defs.
+//! The textures of all passes can be sampled from `@group(2)`, in the order they were declared,
+//! followed by a linear `sampler` (see [`pass_textures_bind_group_layout`](crate::pipeline::pass_textures_bind_group_layout)).
+//! A pass can only sample the textures of the passes before it.
 
 use std::any::{Any, TypeId};
 
 use bevy::{
     prelude::*,
-    reflect::GetTypeRegistration,
+    reflect::{FromType, GetTypeRegistration, ReflectMut, ReflectRef},
     render::{
         extract_resource::ExtractResource,
         render_asset::RenderAssets,
-        render_resource::{BindGroup, BindGroupLayout, CachedComputePipelineId},
+        render_resource::{
+            BindGroup, BindGroupLayout, Buffer, CachedComputePipelineId, TextureViewId,
+        },
         renderer::RenderDevice,
         texture::{FallbackImage, GpuImage},
     },
@@ -51,14 +78,30 @@ pub use bevy_atmosphere_macros::Atmospheric;
 ///
 /// The recommended way to use `Atmospheric` is to derive it with the [`Atmospheric`](derive@Atmospheric) macro.
 pub trait Atmospheric: Send + Sync + Reflect + Any + 'static {
+    /// Encodes the uniform bindings of the model, in the order [`as_bind_group`](Atmospheric::as_bind_group) binds their buffers.
+    ///
+    /// The pipeline writes them to the same buffers whenever the model changes.
+    fn uniforms(&self, images: &RenderAssets<GpuImage>) -> Vec<Vec<u8>>;
+
+    /// Creates the bind group of the model, binding `uniform_buffers` that hold its [`uniforms`](Atmospheric::uniforms).
+    ///
+    /// It is only created again when the type of model or its buffers change.
     fn as_bind_group(
         &self,
         layout: &BindGroupLayout,
         render_device: &RenderDevice,
+        uniform_buffers: &[Buffer],
         images: &RenderAssets<GpuImage>,
         fallback_image: &FallbackImage,
     ) -> BindGroup;
 
+    /// The views of the images the bind group of the model binds, `None` for those that aren't loaded and are bound to a fallback.
+    ///
+    /// The bind group is created again whenever they change.
+    fn image_views(&self, _images: &RenderAssets<GpuImage>) -> Vec<Option<TextureViewId>> {
+        Vec::new()
+    }
+
     fn clone_dynamic(&self) -> Box<dyn Atmospheric>;
 
     fn as_reflect(&self) -> &dyn Reflect;
@@ -79,8 +122,51 @@ pub struct AtmosphereModelMetadata {
     pub id: TypeId,
     /// Used to create the `BindGroup`.
     pub bind_group_layout: BindGroupLayout,
-    /// Used to get the shader's pipeline.
+    /// Used to get the shader's pipeline, specialized for the [`AtmosphereImageFormat`](crate::pipeline::AtmosphereImageFormat).
     pub pipeline: CachedComputePipelineId,
+    /// Used to specialize the shader's pipeline again when the [`AtmosphereImageFormat`](crate::pipeline::AtmosphereImageFormat) changes.
+    pub shader: Handle<Shader>,
+    /// The intermediate passes rendered before the shader's pipeline, in order.
+    pub passes: Vec<AtmospherePass>,
+    /// Used to create the `BindGroup` for sampling the textures of the passes, if there are any.
+    pub pass_bind_group_layout: Option<BindGroupLayout>,
+}
+
+/// An intermediate pass of a multi-pass [`Atmospheric`] model.
+///
+/// Every sky using the model gets a texture for each pass, which later passes can sample.
+#[derive(Clone, Debug)]
+pub struct AtmospherePass {
+    /// Size of the texture the pass renders to.
+    pub size: UVec2,
+    /// Used to get the pass's pipeline.
+    pub pipeline: CachedComputePipelineId,
+}
+
+/// The `TypeData` for creating an [`AtmosphereModel`] from a reflected [`Atmospheric`] model.
+///
+/// It is registered along with the model by [`AddAtmosphereModel::add_atmosphere_model`],
+/// so models can be created from data that only knows their type path (like an [`AtmospherePreset`](crate::preset::AtmospherePreset)).
+#[derive(Clone)]
+pub struct ReflectAtmosphereModel {
+    from_reflect: fn(&dyn PartialReflect) -> Option<AtmosphereModel>,
+}
+
+impl ReflectAtmosphereModel {
+    /// Creates an [`AtmosphereModel`] from a reflected value of the model's type.
+    ///
+    /// Returns `None` if the value doesn't represent the model.
+    pub fn from_reflect(&self, reflect: &dyn PartialReflect) -> Option<AtmosphereModel> {
+        (self.from_reflect)(reflect)
+    }
+}
+
+impl<T: Atmospheric + FromReflect> FromType<T> for ReflectAtmosphereModel {
+    fn from_type() -> Self {
+        Self {
+            from_reflect: |reflect| T::from_reflect(reflect).map(AtmosphereModel::new),
+        }
+    }
 }
 
 /// A trait for registering [`AtmosphereModelMetadata`].
@@ -92,6 +178,11 @@ pub trait RegisterAtmosphereModel: GetTypeRegistration {
 
 /// A trait for using [`RegisterAtmosphereModel`] from `App`.
 ///
+/// The pipelines of a model are queued as soon as it is added, so every registered model compiles at startup,
+/// and switching to one later doesn't have to wait for its shaders.
+/// A sky switched to a model that isn't ready yet keeps showing its previous model until it is,
+/// and a model that fails to compile is reported as an [`AtmosphereError`](crate::pipeline::AtmosphereError).
+///
 /// # Examples
 /// ```ignore
 /// # use bevy::prelude::*;
@@ -119,7 +210,10 @@ impl AddAtmosphereModel for App {
 /// A `Resource` that stores an [`Atmospheric`] model.
 ///
 /// Acts as a wrapper for accessing an [`Atmospheric`] model as a resource.
-#[derive(Resource, ExtractResource, Clone)]
+///
+/// It can also be added as a `Component` to an [`AtmosphereCamera`](crate::plugin::AtmosphereCamera),
+/// giving that camera a sky of its own instead of the global one.
+#[derive(Resource, Component, ExtractResource, Clone)]
 pub struct AtmosphereModel {
     model: Box<dyn Atmospheric>,
 }
@@ -159,6 +253,118 @@ impl AtmosphereModel {
     pub fn to_mut<T: Atmospheric>(&mut self) -> Option<&mut T> {
         Atmospheric::as_reflect_mut(&mut *self.model).downcast_mut()
     }
+
+    /// Interpolates between the models of `self` and `other` by `t`, if both are the same model.
+    ///
+    /// Walks the reflected fields, including those of nested structs:
+    /// `f32`, `Vec3` and `LinearRgba` fields are interpolated linearly,
+    /// while fields of any other type switch from `self` to `other` halfway.
+    /// Returns `None` if the models have different types.
+    pub fn lerp(&self, other: &AtmosphereModel, t: f32) -> Option<AtmosphereModel> {
+        if self.model().type_id() != other.model().type_id() {
+            return None;
+        }
+
+        let mut model = self.clone();
+        lerp_reflect(
+            Atmospheric::as_reflect_mut(&mut *model.model).as_partial_reflect_mut(),
+            Atmospheric::as_reflect(&*self.model).as_partial_reflect(),
+            Atmospheric::as_reflect(&*other.model).as_partial_reflect(),
+            t,
+        );
+        Some(model)
+    }
+}
+
+/// A function that returns the color of the sky in a direction.
+pub type SkySampler<'a> = Box<dyn Fn(Vec3) -> LinearRgba + Sync + 'a>;
+
+/// Returns the CPU port of `model`, which returns the same colors as its shader.
+///
+/// Returns `None` for models without one, like [`Hillaire`](crate::collection::hillaire::Hillaire) and custom models.
+#[cfg_attr(
+    not(any(feature = "gradient", feature = "nishita")),
+    allow(unused_variables)
+)]
+pub fn sky_sampler(model: &AtmosphereModel) -> Option<SkySampler<'_>> {
+    #[cfg(feature = "gradient")]
+    if let Some(gradient) = model.to_ref::<crate::collection::gradient::Gradient>() {
+        return Some(Box::new(|direction| gradient.sample(direction)));
+    }
+
+    #[cfg(feature = "nishita")]
+    if let Some(nishita) = model.to_ref::<crate::collection::nishita::Nishita>() {
+        return Some(Box::new(|direction| nishita.sample(direction)));
+    }
+
+    None
+}
+
+/// Sets `value` to the interpolation between `start` and `end` by `t`, which all have the same type.
+fn lerp_reflect(
+    value: &mut dyn PartialReflect,
+    start: &dyn PartialReflect,
+    end: &dyn PartialReflect,
+    t: f32,
+) {
+    fn lerp_as<T: Reflect + Copy>(
+        value: &mut dyn PartialReflect,
+        start: &dyn PartialReflect,
+        end: &dyn PartialReflect,
+        lerp: impl Fn(T, T) -> T,
+    ) -> bool {
+        match (
+            value.try_downcast_mut::<T>(),
+            start.try_downcast_ref::<T>(),
+            end.try_downcast_ref::<T>(),
+        ) {
+            (Some(value), Some(start), Some(end)) => {
+                *value = lerp(*start, *end);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    if lerp_as(value, start, end, |a: f32, b| a + (b - a) * t)
+        || lerp_as(value, start, end, |a: Vec3, b| a.lerp(b, t))
+        || lerp_as(value, start, end, |a: LinearRgba, b: LinearRgba| {
+            LinearRgba::from_vec4(a.to_vec4().lerp(b.to_vec4(), t))
+        })
+    {
+        return;
+    }
+
+    match (start.reflect_ref(), end.reflect_ref(), value.reflect_mut()) {
+        (ReflectRef::Struct(start), ReflectRef::Struct(end), ReflectMut::Struct(value)) => {
+            for i in 0..value.field_len() {
+                if let (Some(value), Some(start), Some(end)) =
+                    (value.field_at_mut(i), start.field_at(i), end.field_at(i))
+                {
+                    lerp_reflect(value, start, end, t);
+                }
+            }
+        }
+        (
+            ReflectRef::TupleStruct(start),
+            ReflectRef::TupleStruct(end),
+            ReflectMut::TupleStruct(value),
+        ) => {
+            for i in 0..value.field_len() {
+                if let (Some(value), Some(start), Some(end)) =
+                    (value.field_mut(i), start.field(i), end.field(i))
+                {
+                    lerp_reflect(value, start, end, t);
+                }
+            }
+        }
+        _ => {
+            if t >= 0.5 {
+                // `value` starts out as `start`, so it only changes past the halfway point
+                let _ = value.try_apply(end);
+            }
+        }
+    }
 }
 
 cfg_if::cfg_if! {
@@ -176,6 +382,13 @@ cfg_if::cfg_if! {
                 Self::new(Gradient::default())
             }
         }
+    } else if #[cfg(feature = "hillaire")] {
+        impl Default for AtmosphereModel {
+            fn default() -> Self {
+                use crate::collection::hillaire::Hillaire;
+                Self::new(Hillaire::default())
+            }
+        }
     } else {
         impl Default for AtmosphereModel {
             fn default() -> Self {
@@ -184,3 +397,96 @@ cfg_if::cfg_if! {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Reflect, Clone, Debug, PartialEq)]
+    struct Layer {
+        height: f32,
+        color: LinearRgba,
+    }
+
+    #[derive(Reflect, Clone, Debug, PartialEq)]
+    struct Model {
+        direction: Vec3,
+        steps: u32,
+        layer: Layer,
+    }
+
+    #[test]
+    fn lerp_walks_nested_fields() {
+        let start = Model {
+            direction: Vec3::ZERO,
+            steps: 8,
+            layer: Layer {
+                height: 100.0,
+                color: LinearRgba::BLACK,
+            },
+        };
+        let end = Model {
+            direction: Vec3::new(2.0, 4.0, 0.0),
+            steps: 16,
+            layer: Layer {
+                height: 200.0,
+                color: LinearRgba::WHITE,
+            },
+        };
+
+        let lerp = |t: f32| {
+            let mut value = start.clone();
+            lerp_reflect(&mut value, &start, &end, t);
+            value
+        };
+
+        let quarter = lerp(0.25);
+        assert_eq!(quarter.direction, Vec3::new(0.5, 1.0, 0.0));
+        assert_eq!(quarter.steps, 8);
+        assert_eq!(quarter.layer.height, 125.0);
+        assert_eq!(quarter.layer.color, LinearRgba::new(0.25, 0.25, 0.25, 1.0));
+
+        // fields that can't be interpolated switch halfway
+        assert_eq!(lerp(0.75).steps, 16);
+        assert_eq!(lerp(0.0), start);
+        assert_eq!(lerp(1.0), end);
+    }
+
+    #[test]
+    #[cfg(all(feature = "gradient", feature = "nishita"))]
+    fn lerp_needs_the_same_model() {
+        use crate::collection::{gradient::Gradient, nishita::Nishita};
+
+        let noon = AtmosphereModel::new(Nishita::default());
+        let dusk = AtmosphereModel::new(Nishita {
+            sun_position: Vec3::new(0.0, 0.0, 1.0),
+            sun_intensity: 10.0,
+            ..default()
+        });
+
+        let halfway = noon.lerp(&dusk, 0.5).unwrap();
+        assert_eq!(halfway.to_ref::<Nishita>().unwrap().sun_intensity, 16.0);
+
+        let gradient = AtmosphereModel::new(Gradient::default());
+        assert!(noon.lerp(&gradient, 0.5).is_none());
+    }
+
+    /// A model that samples an image.
+    #[derive(Atmospheric, Reflect, Debug, Clone, Default)]
+    #[internal("shaders/gradient.wgsl")]
+    struct Textured {
+        #[texture(0)]
+        #[sampler(1)]
+        image: Handle<Image>,
+        #[texture(2, dimension = "cube")]
+        environment: Option<Handle<Image>>,
+    }
+
+    #[test]
+    fn image_views_follow_the_bound_images() {
+        let model = Textured::default();
+        let images = RenderAssets::<GpuImage>::default();
+        // neither image is loaded, so both are bound to a fallback
+        assert_eq!(model.image_views(&images), vec![None, None]);
+    }
+}
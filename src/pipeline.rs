@@ -1,38 +1,101 @@
 //! Provides types and logic for a compute pipeline that renders the procedural sky texture.
 //!
 //! It's possible to use [`AtmospherePipelinePlugin`] with your own custom code to render to custom targets.
+//!
+//! Every sky is rendered to its own [`AtmosphereImage`].
+//! The global sky uses the [`AtmosphereModel`] and [`AtmosphereImage`] resources,
+//! while entities with an [`AtmosphereModel`] component are given their own [`AtmosphereImage`] and
+//! [`AtmosphereSkyBoxMaterial`] components.
 
-use std::ops::Deref;
+use std::{
+    any::TypeId,
+    fmt,
+    ops::{Deref, Range},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+};
 
 use bevy::{
+    asset::{load_internal_asset, weak_handle},
+    core_pipeline::fullscreen_vertex_shader::fullscreen_shader_vertex_state,
+    ecs::system::SystemParam,
+    platform::collections::{HashMap, HashSet},
     prelude::*,
     render::{
-        extract_resource::{ExtractResource, ExtractResourcePlugin},
+        extract_resource::ExtractResource,
         graph::CameraDriverLabel,
         render_asset::{RenderAssetUsages, RenderAssets},
         render_graph::{self, RenderGraph, RenderLabel},
         render_resource::{
-            BindGroup, BindGroupEntries, BindGroupLayout, BindGroupLayoutEntry, BindingResource,
-            BindingType, CachedPipelineState, ComputePassDescriptor, Extent3d, PipelineCache,
-            ShaderStages, StorageTextureAccess, TextureAspect, TextureDescriptor, TextureDimension,
-            TextureFormat, TextureUsages, TextureView, TextureViewDescriptor, TextureViewDimension,
+            binding_types::texture_2d, encase::internal::WriteInto, BindGroup, BindGroupEntries,
+            BindGroupEntry, BindGroupLayout, BindGroupLayoutEntries, BindGroupLayoutEntry,
+            BindingResource, BindingType, Buffer, BufferBindingType, BufferId,
+            BufferInitDescriptor, BufferUsages, CachedComputePipelineId, CachedPipelineState,
+            CachedRenderPipelineId, ColorTargetState, ColorWrites, ComputePass,
+            ComputePassDescriptor, ComputePipelineDescriptor, DynamicUniformBuffer, Extent3d,
+            FilterMode, FragmentState, LoadOp, Operations, PipelineCache, PipelineCacheError,
+            RenderPassColorAttachment, RenderPassDescriptor, RenderPipelineDescriptor, Sampler,
+            SamplerBindingType, SamplerDescriptor, ShaderStages, ShaderType,
+            SpecializedRenderPipeline, SpecializedRenderPipelines, StorageTextureAccess, StoreOp,
+            Texture, TextureAspect, TextureDescriptor, TextureDimension, TextureFormat,
+            TextureSampleType, TextureUsages, TextureView, TextureViewDescriptor,
+            TextureViewDimension, TextureViewId, UniformBuffer, WgpuFeatures,
         },
-        renderer::RenderDevice,
+        renderer::{RenderDevice, RenderQueue},
         texture::{FallbackImage, GpuImage},
         Extract, Render, RenderApp, RenderSet,
     },
 };
 
 use crate::{
-    model::{AtmosphereModel, AtmosphereModelMetadata},
-    settings::AtmosphereSettings,
+    clouds::{
+        extract_atmosphere_clouds, prepare_atmosphere_clouds, prepare_atmosphere_clouds_bind_group,
+        AtmosphereCloudsPipeline, AtmosphereSkyClouds, ATMOSPHERE_CLOUDS_SHADER_HANDLE,
+    },
+    model::{AtmosphereModel, AtmosphereModelMetadata, Atmospheric},
+    settings::{
+        AtmosphereFormat, AtmosphereProjection, AtmosphereResolutionError, AtmosphereSettings,
+        AtmosphereUpdateMode,
+    },
     skybox::{AtmosphereSkyBoxMaterial, SkyBoxMaterial},
+    transition::{
+        extract_atmosphere_fades, prepare_atmosphere_fade_bind_group, prepare_atmosphere_fades,
+        AtmosphereFadePipeline, AtmosphereSkyFade, ATMOSPHERE_FADE_SHADER_HANDLE,
+    },
 };
 
 /// Label of the compute pipeline `render_graph::Node`.
 #[derive(Debug, Hash, PartialEq, Eq, Clone, RenderLabel)]
 pub struct BevyAtmosphereLabel;
 
+/// The `Handle` for the shader that maps the texels of an [`AtmosphereImage`] to directions, for every [`AtmosphereProjection`].
+///
+/// Model shaders can `#import bevy_atmosphere::projection::texel_direction` to get the ray of the texel they render.
+pub const ATMOSPHERE_PROJECTION_SHADER_HANDLE: Handle<Shader> =
+    weak_handle!("019a2c3e-5d71-7b86-9f20-4e8a1c6d3b97");
+
+/// The `Handle` for the shader that tells which texels of an [`AtmosphereImage`] a dispatch renders.
+///
+/// Model shaders can `#import bevy_atmosphere::slice::in_slice` and return early for texels it rejects,
+/// so a time-sliced update (see [`AtmosphereUpdateMode`]) only renders a slice of the sky at a time.
+pub const ATMOSPHERE_SLICE_SHADER_HANDLE: Handle<Shader> =
+    weak_handle!("019a2d71-4c0b-7e35-8a96-2f5b3d8e1c64");
+
+/// The `Handle` for the shader that declares the texture model shaders write to, in the storage format of the [`AtmosphereFormat`].
+///
+/// Model shaders can `#import bevy_atmosphere::image::image` rather than declaring it themselves.
+pub const ATMOSPHERE_IMAGE_SHADER_HANDLE: Handle<Shader> =
+    weak_handle!("019a2e94-7b1d-7c42-b6e3-5d0f8a2c4e19");
+
+/// The `Handle` for the shader that converts back buffers and generates the mips of an [`AtmosphereImage`].
+pub const ATMOSPHERE_MIPMAP_SHADER_HANDLE: Handle<Shader> =
+    weak_handle!("019a2e95-1c8e-7a53-9f04-6b2d7e1a3c85");
+
+/// The number of slices a time-sliced update renders a sky in, one per face of a cubemap.
+pub const ATMOSPHERE_SLICE_COUNT: u32 = 6;
+
 /// Size of the compute workgroups in the x and y axis.
 ///
 /// Complete workgroup size is (8, 8, 6);
@@ -41,34 +104,156 @@ pub const WORKGROUP_SIZE: u32 = 8;
 /// The procedural sky `Image` generated by the atmosphere compute pipeline.
 ///
 /// It can be used in a material for a skybox mesh.
-#[derive(Resource, ExtractResource, Debug, Clone)]
+///
+/// As a `Resource`, it is the target of the global [`AtmosphereModel`].
+/// As a `Component`, it is the target of the [`AtmosphereModel`] on the same entity.
+#[derive(Resource, Component, ExtractResource, Debug, Clone)]
 pub struct AtmosphereImage {
     /// `Handle` to a procedural sky `Image`.
     ///
-    /// The `TextureView` associated with this handle is `TextureViewDimension::Cube`.
+    /// The `TextureView` associated with this handle is `TextureViewDimension::Cube`,
+    /// or `TextureViewDimension::D2` for a panoramic [`AtmosphereProjection`].
     pub handle: Handle<Image>,
     /// `TextureView` of the image with `TextureViewDimension::D2Array`.
     pub array_view: Option<TextureView>,
 }
 
+impl AtmosphereImage {
+    /// Creates a new sky `Image` of the given resolution, projection, format and number of mips,
+    /// ready to be written to by the compute pipeline.
+    ///
+    /// The image has no data, so it can be resized by changing its `texture_descriptor`.
+    pub fn new_image(
+        resolution: u32,
+        projection: AtmosphereProjection,
+        format: AtmosphereFormat,
+        mip_level_count: u32,
+    ) -> Image {
+        let size = projection.size(resolution);
+        let mut image = Image::new_uninit(
+            size,
+            TextureDimension::D2,
+            format.texture_format(),
+            RenderAssetUsages::default(),
+        );
+
+        image.texture_view_descriptor = Some(atmosphere_image_view_descriptor(projection));
+
+        image.texture_descriptor = TextureDescriptor {
+            size,
+            mip_level_count,
+            format: format.texture_format(),
+            usage: atmosphere_image_usages(format, mip_level_count),
+            view_formats: &[],
+            ..ATMOSPHERE_IMAGE_TEXTURE_DESCRIPTOR(resolution)
+        };
+
+        image
+    }
+}
+
+/// The [`AtmosphereFormat`] of every [`AtmosphereImage`].
+///
+/// It follows [`AtmosphereSettings::format`], and exists in both the main and render worlds.
+/// When it changes, the images are created again in the new format,
+/// and the pipelines of every model are specialized for it.
+#[derive(Resource, Deref, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct AtmosphereImageFormat(pub AtmosphereFormat);
+
+/// The `TextureUsages` of an [`AtmosphereImage`] of the given format and number of mips.
+fn atmosphere_image_usages(format: AtmosphereFormat, mip_level_count: u32) -> TextureUsages {
+    let mut usage = TextureUsages::COPY_DST | TextureUsages::TEXTURE_BINDING;
+    if format.is_storage() {
+        usage |= TextureUsages::STORAGE_BINDING;
+    }
+    // converted copies and mips are rendered
+    if !format.is_storage() || mip_level_count > 1 {
+        usage |= TextureUsages::RENDER_ATTACHMENT;
+    }
+    usage
+}
+
+/// The `TextureDescriptor` of a texture the stages of a sky write to before the [`AtmosphereImage`],
+/// in the storage format of the [`AtmosphereFormat`].
+pub(crate) fn atmosphere_stage_texture_descriptor(
+    label: &'static str,
+    size: Extent3d,
+    format: AtmosphereFormat,
+) -> TextureDescriptor<'static> {
+    TextureDescriptor {
+        label: Some(label),
+        size,
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: TextureDimension::D2,
+        format: format.storage_format(),
+        usage: TextureUsages::STORAGE_BINDING
+            | TextureUsages::TEXTURE_BINDING
+            | TextureUsages::COPY_SRC,
+        view_formats: &[],
+    }
+}
+
 /// The `BindGroupLayout` for binding [`AtmosphereImage`] to the compute shader.
 #[derive(Resource, Debug, Clone)]
 pub struct AtmosphereImageBindGroupLayout(pub BindGroupLayout);
 
 impl FromWorld for AtmosphereImageBindGroupLayout {
     fn from_world(world: &mut World) -> Self {
+        let format = world
+            .get_resource::<AtmosphereImageFormat>()
+            .copied()
+            .unwrap_or_default();
         let render_device = world.resource::<RenderDevice>();
 
         Self(render_device.create_bind_group_layout(
             "bevy_atmosphere_image_bind_group_layout",
+            &[
+                BindGroupLayoutEntry {
+                    // AtmosphereImage
+                    binding: 0,
+                    visibility: ShaderStages::COMPUTE,
+                    ty: BindingType::StorageTexture {
+                        access: StorageTextureAccess::WriteOnly,
+                        format: format.storage_format(),
+                        view_dimension: TextureViewDimension::D2Array,
+                    },
+                    count: None,
+                },
+                BindGroupLayoutEntry {
+                    // The slice rendered by the dispatch, see `AtmosphereSlices`
+                    binding: 1,
+                    visibility: ShaderStages::COMPUTE,
+                    ty: BindingType::Buffer {
+                        ty: BufferBindingType::Uniform,
+                        has_dynamic_offset: true,
+                        min_binding_size: Some(SliceUniform::min_size()),
+                    },
+                    count: None,
+                },
+            ],
+        ))
+    }
+}
+
+/// The `BindGroupLayout` for binding the texture an intermediate [`AtmospherePass`](crate::model::AtmospherePass) renders to.
+#[derive(Resource, Debug, Clone)]
+pub struct AtmospherePassBindGroupLayout(pub BindGroupLayout);
+
+impl FromWorld for AtmospherePassBindGroupLayout {
+    fn from_world(world: &mut World) -> Self {
+        let render_device = world.resource::<RenderDevice>();
+
+        Self(render_device.create_bind_group_layout(
+            "bevy_atmosphere_pass_bind_group_layout",
             &[BindGroupLayoutEntry {
-                // AtmosphereImage
+                // Texture of the pass
                 binding: 0,
                 visibility: ShaderStages::COMPUTE,
                 ty: BindingType::StorageTexture {
                     access: StorageTextureAccess::WriteOnly,
                     format: TextureFormat::Rgba16Float,
-                    view_dimension: TextureViewDimension::D2Array,
+                    view_dimension: TextureViewDimension::D2,
                 },
                 count: None,
             }],
@@ -76,47 +261,1148 @@ impl FromWorld for AtmosphereImageBindGroupLayout {
     }
 }
 
-/// Signals the pipeline (inside `RenderApp`) to render the atmosphere.
+/// The linear `Sampler` for reading the textures of intermediate passes (see [`AtmospherePass`](crate::model::AtmospherePass)).
+#[derive(Resource, Debug, Clone)]
+pub struct AtmospherePassSampler(pub Sampler);
+
+impl FromWorld for AtmospherePassSampler {
+    fn from_world(world: &mut World) -> Self {
+        let render_device = world.resource::<RenderDevice>();
+
+        Self(render_device.create_sampler(&SamplerDescriptor {
+            label: Some("bevy_atmosphere_pass_sampler"),
+            mag_filter: FilterMode::Linear,
+            min_filter: FilterMode::Linear,
+            ..default()
+        }))
+    }
+}
+
+/// Creates the `BindGroupLayout` for sampling the textures of `count` intermediate passes (see [`AtmospherePass`](crate::model::AtmospherePass)).
+///
+/// Binding `i` is the texture of pass `i`, and binding `count` is an [`AtmospherePassSampler`].
+pub fn pass_textures_bind_group_layout(
+    render_device: &RenderDevice,
+    count: u32,
+) -> BindGroupLayout {
+    let entries = (0..count)
+        .map(|binding| BindGroupLayoutEntry {
+            binding,
+            visibility: ShaderStages::COMPUTE,
+            ty: BindingType::Texture {
+                sample_type: TextureSampleType::Float { filterable: true },
+                view_dimension: TextureViewDimension::D2,
+                multisampled: false,
+            },
+            count: None,
+        })
+        .chain(std::iter::once(BindGroupLayoutEntry {
+            binding: count,
+            visibility: ShaderStages::COMPUTE,
+            ty: BindingType::Sampler(SamplerBindingType::Filtering),
+            count: None,
+        }))
+        .collect::<Vec<_>>();
+
+    render_device
+        .create_bind_group_layout("bevy_atmosphere_pass_textures_bind_group_layout", &entries)
+}
+
+/// Queues the pipeline of the `main` entry point of a model's `shader`, which renders to an [`AtmosphereImage`] of the given format.
+///
+/// `pass_bind_group_layout` is the layout of the textures of its intermediate passes, if it has any.
+pub fn queue_atmosphere_model_pipeline(
+    pipeline_cache: &PipelineCache,
+    shader: Handle<Shader>,
+    bind_group_layout: &BindGroupLayout,
+    image_bind_group_layout: &BindGroupLayout,
+    pass_bind_group_layout: Option<&BindGroupLayout>,
+    format: AtmosphereFormat,
+) -> CachedComputePipelineId {
+    let mut layout = vec![bind_group_layout.clone(), image_bind_group_layout.clone()];
+    layout.extend(pass_bind_group_layout.cloned());
+
+    pipeline_cache.queue_compute_pipeline(ComputePipelineDescriptor {
+        label: Some("bevy_atmosphere_compute_pipeline".into()),
+        layout,
+        push_constant_ranges: vec![],
+        shader,
+        shader_defs: format.shader_defs(),
+        entry_point: "main".into(),
+        zero_initialize_workgroup_memory: true,
+    })
+}
+
+/// Identifies a sky rendered by the atmosphere compute pipeline.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AtmosphereSkyId {
+    /// The sky of the [`AtmosphereModel`] and [`AtmosphereImage`] resources.
+    Global,
+    /// The sky of a main world entity with [`AtmosphereModel`] and [`AtmosphereImage`] components.
+    Entity(Entity),
+}
+
+/// Signals the pipeline (inside `RenderApp`) to render a sky.
 #[derive(Debug, Clone, Copy, Event)]
-pub struct AtmosphereUpdateEvent;
+pub struct AtmosphereUpdateEvent(pub AtmosphereSkyId);
 
-#[derive(Resource, Debug, Clone)]
-struct AtmosphereBindGroups(pub BindGroup, pub BindGroup);
+/// An error that keeps the pipeline from rendering a sky as it is set up.
+///
+/// It is logged when it occurs, and sent as an event in the main world the frame after.
+#[derive(Debug, Clone, PartialEq, Eq, Event)]
+pub enum AtmosphereError {
+    /// The model wasn't added with [`AddAtmosphereModel::add_atmosphere_model`](crate::model::AddAtmosphereModel::add_atmosphere_model),
+    /// so it has no pipeline to be rendered with.
+    UnregisteredModel {
+        /// The sky of the model.
+        sky: AtmosphereSkyId,
+        /// The type path of the model.
+        model: String,
+    },
+    /// A pipeline of the model failed to compile, so the sky keeps showing what it rendered before.
+    PipelineFailed {
+        /// The sky of the model.
+        sky: AtmosphereSkyId,
+        /// The type path of the model.
+        model: String,
+        /// The error of the `PipelineCache`.
+        error: String,
+    },
+    /// The [`AtmosphereSettings::resolution`] isn't supported by the device, so the skies are rendered at the clamped resolution instead.
+    InvalidResolution(AtmosphereResolutionError),
+    /// The device lacks the features of the [`AtmosphereSettings::format`], so the skies are rendered as `Rgba16Float` instead.
+    UnsupportedFormat {
+        /// The format of the settings.
+        format: AtmosphereFormat,
+        /// The features of the format the device lacks.
+        missing: WgpuFeatures,
+    },
+}
+
+impl fmt::Display for AtmosphereError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnregisteredModel { sky, model } => write!(
+                f,
+                "`{model}` of {sky:?} is not a registered atmospheric model, add it with `add_atmosphere_model`"
+            ),
+            Self::PipelineFailed { sky, model, error } => {
+                write!(f, "`{model}` of {sky:?} failed to compile: {error}")
+            }
+            Self::InvalidResolution(error) => write!(
+                f,
+                "Invalid `AtmosphereSettings`: {error}, it is clamped to {}",
+                error.clamped()
+            ),
+            Self::UnsupportedFormat { format, missing } => write!(
+                f,
+                "`AtmosphereFormat::{format:?}` needs the {missing:?} features, which the device lacks, `AtmosphereFormat::Rgba16Float` is used instead"
+            ),
+        }
+    }
+}
 
-#[derive(Resource, Default, Clone)]
-struct CachedAtmosphereModelMetadata(pub Option<AtmosphereModelMetadata>);
+impl std::error::Error for AtmosphereError {}
 
-/// A `Plugin` that creates the compute pipeline for rendering a procedural sky cubemap texture.
-#[derive(Debug, Clone, Copy)]
-pub struct AtmospherePipelinePlugin;
+/// The [`AtmosphereError`]s of the render world, which are sent as events in the main world.
+#[derive(Resource, Clone, Default)]
+pub(crate) struct AtmosphereErrors(Arc<Mutex<Vec<AtmosphereError>>>);
 
-impl Plugin for AtmospherePipelinePlugin {
-    fn build(&self, app: &mut App) {
-        let settings = match app.world().get_resource::<AtmosphereSettings>() {
-            Some(s) => *s,
-            None => default(),
+impl AtmosphereErrors {
+    /// Logs `error`, and sends it to the main world.
+    pub(crate) fn report(&self, error: AtmosphereError) {
+        error!("{error}");
+        self.0
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .push(error);
+    }
+}
+
+/// Clamps the resolution of `settings` to what a device with a `max_texture_dimension_2d` limit supports.
+///
+/// Returns `true` and reports an [`AtmosphereError::InvalidResolution`] if it had to be clamped.
+fn clamp_resolution(
+    settings: &mut AtmosphereSettings,
+    max_texture_dimension_2d: u32,
+    errors: &AtmosphereErrors,
+) -> bool {
+    let Err(error) = settings.validate_resolution(max_texture_dimension_2d) else {
+        return false;
+    };
+    settings.resolution = error.clamped();
+    errors.report(AtmosphereError::InvalidResolution(error));
+    true
+}
+
+/// The [`AtmosphereSettings`] of `world`, with the resolution clamped to the limit of the `RenderDevice` once it exists.
+fn validate_atmosphere_settings(world: &mut World) -> AtmosphereSettings {
+    let Some(mut settings) = world.get_resource::<AtmosphereSettings>().copied() else {
+        return default();
+    };
+    if let (Some(render_device), Some(errors)) = (
+        world.get_resource::<RenderDevice>(),
+        world.get_resource::<AtmosphereErrors>(),
+    ) {
+        let max_texture_dimension_2d = render_device.limits().max_texture_dimension_2d;
+        if clamp_resolution(&mut settings, max_texture_dimension_2d, errors) {
+            // an existing image is resized by `atmosphere_settings_changed` before it is rendered to
+            world.insert_resource(settings);
+        }
+    }
+    settings
+}
+
+/// The `format` if a device with the given `features` supports it, or [`AtmosphereFormat::Rgba16Float`] otherwise,
+/// in which case an [`AtmosphereError::UnsupportedFormat`] is reported.
+fn supported_format(
+    format: AtmosphereFormat,
+    features: WgpuFeatures,
+    errors: &AtmosphereErrors,
+) -> AtmosphereFormat {
+    let missing = format.required_features().difference(features);
+    if missing.is_empty() {
+        return format;
+    }
+    errors.report(AtmosphereError::UnsupportedFormat { format, missing });
+    AtmosphereFormat::Rgba16Float
+}
+
+/// Falls back to [`AtmosphereFormat::Rgba16Float`] if the device lacks the features of the [`AtmosphereImageFormat`],
+/// reporting an [`AtmosphereError::UnsupportedFormat`].
+///
+/// The device only exists once the plugins are finished,
+/// so this runs as `AtmospherePlugin` finishes, before anything is specialized for the format.
+pub(crate) fn validate_atmosphere_format(app: &mut App) {
+    let world = app.world();
+    let (Some(format), Some(render_device), Some(errors)) = (
+        world.get_resource::<AtmosphereImageFormat>(),
+        world.get_resource::<RenderDevice>(),
+        world.get_resource::<AtmosphereErrors>(),
+    ) else {
+        return;
+    };
+    let supported = supported_format(**format, render_device.features(), errors);
+    if supported == **format {
+        return;
+    }
+
+    let format = AtmosphereImageFormat(supported);
+    app.insert_resource(format);
+    app.sub_app_mut(RenderApp).insert_resource(format);
+    if let Some(mut settings) = app.world_mut().get_resource_mut::<AtmosphereSettings>() {
+        settings.format = *format;
+    }
+
+    let handle = app.world().resource::<AtmosphereImage>().handle.clone();
+    if let Some(image) = app
+        .world_mut()
+        .resource_mut::<Assets<Image>>()
+        .get_mut(&handle)
+    {
+        image.texture_descriptor.format = format.texture_format();
+        image.texture_descriptor.usage =
+            atmosphere_image_usages(*format, image.texture_descriptor.mip_level_count);
+    }
+}
+
+/// Sends the [`AtmosphereError`]s of the render world as events.
+fn send_atmosphere_errors(errors: Res<AtmosphereErrors>, mut events: EventWriter<AtmosphereError>) {
+    let errors = std::mem::take(
+        &mut *errors
+            .0
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()),
+    );
+    events.write_batch(errors);
+}
+
+/// The bind groups of a model.
+#[derive(Debug, Clone)]
+struct AtmosphereBindGroups {
+    model: BindGroup,
+    /// The output and inputs of every intermediate pass.
+    passes: Vec<(BindGroup, BindGroup)>,
+    /// The textures of all intermediate passes, for the final pass.
+    pass_textures: Option<BindGroup>,
+}
+
+/// The GPU resources of a model, which are created again when the type of model changes.
+struct AtmosphereModelGpuResources {
+    /// The buffers of the uniforms of the model, in the order of [`Atmospheric::uniforms`].
+    uniform_buffers: Vec<Buffer>,
+    /// Also keeps the textures the intermediate passes of the model render to.
+    bind_groups: AtmosphereBindGroups,
+}
+
+/// The view of a target and the slice buffer an image bind group binds.
+pub(crate) type AtmosphereImageBinding = (TextureViewId, BufferId);
+
+/// The type of a model and the views of the images its bind group binds, `None` for those bound to a fallback.
+pub(crate) type AtmosphereModelBinding = (TypeId, Vec<Option<TextureViewId>>);
+
+/// A GPU resource that is only created again when the inputs it was created from change.
+///
+/// Every creation of a buffer, texture or bind group kept across frames goes through one of these,
+/// so that idle frames create nothing.
+#[derive(Debug)]
+pub(crate) struct AtmosphereCached<K, T> {
+    inputs: Option<K>,
+    value: Option<T>,
+    /// How many times the resource was created.
+    created: usize,
+}
+
+impl<K, T> Default for AtmosphereCached<K, T> {
+    fn default() -> Self {
+        Self {
+            inputs: None,
+            value: None,
+            created: 0,
+        }
+    }
+}
+
+impl<K: PartialEq, T> AtmosphereCached<K, T> {
+    /// Returns the resource created from `inputs`, calling `create` if there is none yet or it was created from other inputs.
+    pub(crate) fn get_or_create(&mut self, inputs: K, create: impl FnOnce() -> T) -> &T {
+        if self.inputs.as_ref() != Some(&inputs) {
+            self.inputs = Some(inputs);
+            self.value = None;
+        }
+        self.value.get_or_insert_with(|| {
+            self.created += 1;
+            create()
+        })
+    }
+
+    /// The resource, once it is created.
+    pub(crate) fn get(&self) -> Option<&T> {
+        self.value.as_ref()
+    }
+
+    /// The inputs the resource was created from.
+    pub(crate) fn inputs(&self) -> Option<&K> {
+        self.inputs.as_ref()
+    }
+
+    /// Drops the resource, so it is created on the next [`get_or_create`](Self::get_or_create).
+    pub(crate) fn clear(&mut self) {
+        self.inputs = None;
+        self.value = None;
+    }
+
+    /// How many times the resource was created.
+    pub(crate) fn created(&self) -> usize {
+        self.created
+    }
+}
+
+/// How many buffers, textures and bind groups the pipeline has created in the render world.
+///
+/// They are all created through an [`AtmosphereRenderDevice`], so that frames which create nothing can be told apart.
+#[derive(Resource, Default, Debug)]
+pub(crate) struct AtmosphereCreated(AtomicUsize);
+
+/// The `RenderDevice`, which the pipeline creates the buffers, textures and bind groups of the skies with,
+/// counting them in [`AtmosphereCreated`].
+#[derive(SystemParam)]
+pub(crate) struct AtmosphereRenderDevice<'w> {
+    render_device: Res<'w, RenderDevice>,
+    created: Res<'w, AtmosphereCreated>,
+}
+
+impl AtmosphereRenderDevice<'_> {
+    fn count(&self) {
+        self.created.0.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn create_bind_group<'a>(
+        &self,
+        label: &'static str,
+        layout: &'a BindGroupLayout,
+        entries: &'a [BindGroupEntry<'a>],
+    ) -> BindGroup {
+        self.count();
+        self.render_device.create_bind_group(label, layout, entries)
+    }
+
+    pub(crate) fn create_buffer_with_data(&self, descriptor: &BufferInitDescriptor) -> Buffer {
+        self.count();
+        self.render_device.create_buffer_with_data(descriptor)
+    }
+
+    pub(crate) fn create_texture(&self, descriptor: &TextureDescriptor) -> Texture {
+        self.count();
+        self.render_device.create_texture(descriptor)
+    }
+
+    /// Creates the bind group of `model`, see [`Atmospheric::as_bind_group`].
+    fn create_model_bind_group(
+        &self,
+        model: &dyn Atmospheric,
+        layout: &BindGroupLayout,
+        uniform_buffers: &[Buffer],
+        gpu_images: &RenderAssets<GpuImage>,
+        fallback_image: &FallbackImage,
+    ) -> BindGroup {
+        self.count();
+        model.as_bind_group(
+            layout,
+            &self.render_device,
+            uniform_buffers,
+            gpu_images,
+            fallback_image,
+        )
+    }
+
+    /// Writes a `UniformBuffer`, which creates its buffer the first time.
+    pub(crate) fn write_uniform_buffer<T: ShaderType + WriteInto>(
+        &self,
+        buffer: &mut UniformBuffer<T>,
+        render_queue: &RenderQueue,
+    ) {
+        let previous = buffer.buffer().map(Buffer::id);
+        buffer.write_buffer(&self.render_device, render_queue);
+        if buffer.buffer().map(Buffer::id) != previous {
+            self.count();
+        }
+    }
+
+    /// Writes a `DynamicUniformBuffer`, which creates its buffer the first time and whenever it grows.
+    fn write_dynamic_uniform_buffer<T: ShaderType + WriteInto>(
+        &self,
+        buffer: &mut DynamicUniformBuffer<T>,
+        render_queue: &RenderQueue,
+    ) {
+        let previous = buffer.buffer().map(Buffer::id);
+        buffer.write_buffer(&self.render_device, render_queue);
+        if buffer.buffer().map(Buffer::id) != previous {
+            self.count();
+        }
+    }
+}
+
+/// The render world state of a model, which renders to a single target.
+pub(crate) struct AtmosphereSkyModel {
+    model: AtmosphereModel,
+    metadata: Option<AtmosphereModelMetadata>,
+    /// The buffers, textures and bind groups of the type of model and the images it binds.
+    resources: AtmosphereCached<AtmosphereModelBinding, AtmosphereModelGpuResources>,
+    image_bind_group: AtmosphereCached<AtmosphereImageBinding, BindGroup>,
+    /// Set when the model changed since its uniforms were written.
+    changed: bool,
+    /// The type of model last reported with an [`AtmosphereError`], so it is reported once.
+    reported: Option<TypeId>,
+}
+
+impl AtmosphereSkyModel {
+    pub(crate) fn new(model: AtmosphereModel) -> Self {
+        Self {
+            model,
+            metadata: None,
+            resources: AtmosphereCached::default(),
+            image_bind_group: AtmosphereCached::default(),
+            changed: false,
+            reported: None,
+        }
+    }
+
+    /// The model rendered.
+    pub(crate) fn model(&self) -> &AtmosphereModel {
+        &self.model
+    }
+
+    /// Replaces the model rendered, whose uniforms are written when it is prepared next.
+    pub(crate) fn set_model(&mut self, model: AtmosphereModel) {
+        self.model = model;
+        self.changed = true;
+    }
+
+    /// Prepares the resources for rendering the model of `sky` to `target`.
+    ///
+    /// Buffers, textures and bind groups are only created when the type of model, the images it binds or the target change,
+    /// otherwise the uniforms of a changed model are written to the buffers it already has.
+    ///
+    /// Reports an [`AtmosphereError`] if the model can't be rendered.
+    pub(crate) fn prepare(
+        &mut self,
+        sky: AtmosphereSkyId,
+        target: &TextureView,
+        resources: &AtmosphereModelResources,
+    ) {
+        let AtmosphereModelResources {
+            gpu_images,
+            render_device,
+            render_queue,
+            fallback_image,
+            type_registry,
+            image_bind_group_layout,
+            pass_bind_group_layout,
+            pass_sampler,
+            slices,
+            errors,
+        } = resources;
+        let (Some(slice_binding), Some(slice_buffer)) = (slices.binding(), slices.buffer_id())
+        else {
+            return;
+        };
+
+        let id = self.model.model().type_id();
+        if self.resources.inputs().map(|(inputs, _)| inputs) != Some(&id) {
+            self.metadata = type_registry
+                .read()
+                .get_type_data::<AtmosphereModelMetadata>(id)
+                .cloned();
+            if self.metadata.is_none() {
+                // the resources of the previous model don't fit the new model
+                self.resources.clear();
+                if self.reported != Some(id) {
+                    self.reported = Some(id);
+                    errors.report(AtmosphereError::UnregisteredModel {
+                        sky,
+                        model: self.type_path(),
+                    });
+                }
+                return;
+            }
+            self.reported = None;
+        }
+        let Some(metadata) = &self.metadata else {
+            return;
+        };
+
+        self.image_bind_group
+            .get_or_create((target.id(), slice_buffer), || {
+                render_device.create_bind_group(
+                    "bevy_atmosphere_image_bind_group",
+                    &image_bind_group_layout.0,
+                    &BindGroupEntries::sequential((target, slice_binding)),
+                )
+            });
+
+        // new buffers are created with the uniforms in them
+        let created = self.resources.created();
+        let model = self.model.model();
+        self.resources
+            .get_or_create((id, model.image_views(gpu_images)), || {
+                let uniform_buffers: Vec<_> = model
+                    .uniforms(gpu_images)
+                    .iter()
+                    .map(|uniform| {
+                        render_device.create_buffer_with_data(&BufferInitDescriptor {
+                            label: Some("bevy_atmosphere_model_uniform_buffer"),
+                            usage: BufferUsages::COPY_DST | BufferUsages::UNIFORM,
+                            contents: uniform,
+                        })
+                    })
+                    .collect();
+
+                let pass_views: Vec<_> = metadata
+                    .passes
+                    .iter()
+                    .map(|pass| {
+                        render_device
+                            .create_texture(&TextureDescriptor {
+                                label: Some("atmosphere_pass_texture"),
+                                size: Extent3d {
+                                    width: pass.size.x,
+                                    height: pass.size.y,
+                                    depth_or_array_layers: 1,
+                                },
+                                mip_level_count: 1,
+                                sample_count: 1,
+                                dimension: TextureDimension::D2,
+                                format: TextureFormat::Rgba16Float,
+                                usage: TextureUsages::STORAGE_BINDING
+                                    | TextureUsages::TEXTURE_BINDING,
+                                view_formats: &[],
+                            })
+                            .create_view(&TextureViewDescriptor::default())
+                    })
+                    .collect();
+
+                let atmosphere_bind_group = render_device.create_model_bind_group(
+                    model,
+                    &metadata.bind_group_layout,
+                    &uniform_buffers,
+                    gpu_images,
+                    fallback_image,
+                );
+
+                // A pass can't sample the texture it renders to, so its own slot (and those after it) use a fallback.
+                let pass_textures_bind_group = |layout: &BindGroupLayout, pass: usize| {
+                    let count = pass_views.len();
+                    let entries = pass_views
+                        .iter()
+                        .enumerate()
+                        .map(|(i, pass_view)| BindGroupEntry {
+                            binding: i as u32,
+                            resource: BindingResource::TextureView(if i < pass {
+                                pass_view
+                            } else {
+                                &fallback_image.d2.texture_view
+                            }),
+                        })
+                        .chain(std::iter::once(BindGroupEntry {
+                            binding: count as u32,
+                            resource: BindingResource::Sampler(&pass_sampler.0),
+                        }))
+                        .collect::<Vec<_>>();
+                    render_device.create_bind_group(
+                        "bevy_atmosphere_pass_textures_bind_group",
+                        layout,
+                        &entries,
+                    )
+                };
+
+                let (passes, pass_textures) = match &metadata.pass_bind_group_layout {
+                    Some(layout) => (
+                        pass_views
+                            .iter()
+                            .enumerate()
+                            .map(|(i, pass_view)| {
+                                let output = render_device.create_bind_group(
+                                    "bevy_atmosphere_pass_bind_group",
+                                    &pass_bind_group_layout.0,
+                                    &BindGroupEntries::single(BindingResource::TextureView(
+                                        pass_view,
+                                    )),
+                                );
+                                (output, pass_textures_bind_group(layout, i))
+                            })
+                            .collect(),
+                        Some(pass_textures_bind_group(layout, pass_views.len())),
+                    ),
+                    None => (Vec::new(), None),
+                };
+
+                AtmosphereModelGpuResources {
+                    bind_groups: AtmosphereBindGroups {
+                        model: atmosphere_bind_group,
+                        passes,
+                        pass_textures,
+                    },
+                    uniform_buffers,
+                }
+            });
+
+        let reused = self.resources.created() == created;
+        if let (true, Some(gpu_resources)) = (self.changed && reused, self.resources.get()) {
+            let uniforms = model.uniforms(gpu_images);
+            for (buffer, uniform) in gpu_resources.uniform_buffers.iter().zip(&uniforms) {
+                render_queue.write_buffer(buffer, 0, uniform);
+            }
+        }
+        self.changed = false;
+    }
+
+    /// Whether the images the model binds were loaded, changed or unloaded since its bind group was created.
+    pub(crate) fn images_changed(&self, gpu_images: &RenderAssets<GpuImage>) -> bool {
+        self.resources
+            .inputs()
+            .is_some_and(|(_, views)| *views != self.model.model().image_views(gpu_images))
+    }
+
+    /// Drops the buffers, textures and bind groups of the model, which are created again when it is prepared next.
+    pub(crate) fn drop_resources(&mut self) {
+        self.metadata = None;
+        self.resources.clear();
+        self.image_bind_group.clear();
+    }
+
+    /// The type path of the model, for errors.
+    fn type_path(&self) -> String {
+        Atmospheric::as_reflect(self.model.model())
+            .reflect_type_path()
+            .to_string()
+    }
+
+    /// The pipelines of the final pass and the intermediate passes of the model.
+    fn pipelines(&self) -> impl Iterator<Item = CachedComputePipelineId> + '_ {
+        self.metadata.iter().flat_map(|metadata| {
+            std::iter::once(metadata.pipeline)
+                .chain(metadata.passes.iter().map(|pass| pass.pipeline))
+        })
+    }
+
+    /// Returns `true` if the model is prepared and all of its pipelines are ready.
+    ///
+    /// Until then, the sky isn't rendered, so it keeps showing the previous model.
+    pub(crate) fn is_ready(&self, pipeline_cache: &PipelineCache) -> bool {
+        self.resources.get().is_some()
+            && self.image_bind_group.get().is_some()
+            && self.metadata.is_some()
+            && self.pipelines().all(|pipeline| {
+                matches!(
+                    pipeline_cache.get_compute_pipeline_state(pipeline),
+                    CachedPipelineState::Ok(_)
+                )
+            })
+    }
+
+    /// Reports an [`AtmosphereError`] if a pipeline of the model failed to compile, which it would never be ready without.
+    pub(crate) fn check_pipelines(
+        &mut self,
+        sky: AtmosphereSkyId,
+        pipeline_cache: &PipelineCache,
+        errors: &AtmosphereErrors,
+    ) {
+        let Some(id) = self.metadata.as_ref().map(|metadata| metadata.id) else {
+            return;
+        };
+        if self.reported == Some(id) {
+            return;
+        }
+
+        let error = self.pipelines().find_map(|pipeline| {
+            match pipeline_cache.get_compute_pipeline_state(pipeline) {
+                // the cache tries these again once the shaders are loaded
+                CachedPipelineState::Err(
+                    PipelineCacheError::ShaderNotLoaded(_)
+                    | PipelineCacheError::ShaderImportNotYetAvailable,
+                ) => None,
+                CachedPipelineState::Err(error) => Some(error.to_string()),
+                _ => None,
+            }
+        });
+        if let Some(error) = error {
+            self.reported = Some(id);
+            errors.report(AtmosphereError::PipelineFailed {
+                sky,
+                model: self.type_path(),
+                error,
+            });
+        }
+    }
+
+    /// Dispatches the passes of the model, rendering it to its target.
+    ///
+    /// The intermediate passes are only rendered by the first dispatch of an update.
+    pub(crate) fn render(
+        &self,
+        pass: &mut ComputePass,
+        pipeline_cache: &PipelineCache,
+        dispatch: &AtmosphereDispatch,
+    ) {
+        let (Some(metadata), Some(resources), Some(image_bind_group)) = (
+            &self.metadata,
+            self.resources.get(),
+            self.image_bind_group.get(),
+        ) else {
+            return;
         };
+        let bind_groups = &resources.bind_groups;
 
-        let atmosphere = match app.world().get_resource::<AtmosphereModel>() {
-            Some(a) => a.clone(),
-            None => default(),
+        pass.set_bind_group(0, &bind_groups.model, &[]);
+
+        let passes = if dispatch.first {
+            &metadata.passes[..]
+        } else {
+            &[]
         };
+        for (atmosphere_pass, (output, inputs)) in passes.iter().zip(&bind_groups.passes) {
+            let Some(pass_pipeline) = pipeline_cache.get_compute_pipeline(atmosphere_pass.pipeline)
+            else {
+                continue;
+            };
+            pass.set_bind_group(1, output, &[]);
+            pass.set_bind_group(2, inputs, &[]);
+            pass.set_pipeline(pass_pipeline);
+            pass.dispatch_workgroups(
+                atmosphere_pass.size.x.div_ceil(WORKGROUP_SIZE),
+                atmosphere_pass.size.y.div_ceil(WORKGROUP_SIZE),
+                1,
+            );
+        }
+
+        dispatch.set_image_bind_group(pass, image_bind_group);
+        if let Some(pass_textures) = &bind_groups.pass_textures {
+            pass.set_bind_group(2, pass_textures, &[]);
+        }
+
+        let Some(update_pipeline) = pipeline_cache.get_compute_pipeline(metadata.pipeline) else {
+            return;
+        };
+        pass.set_pipeline(update_pipeline);
+        dispatch.dispatch(pass);
+    }
+}
+
+/// The `Slice` struct in `slice.wgsl`.
+#[derive(ShaderType, Debug, Clone, Copy)]
+struct SliceUniform {
+    start: u32,
+    end: u32,
+}
+
+/// The rows of the textures of a sky rendered by every range of slices, bound with dynamic offsets.
+///
+/// Slices split the rows of every layer, in order, so a slice of a cubemap is a face
+/// and a slice of a panoramic projection is a horizontal band.
+#[derive(Resource, Default)]
+pub(crate) struct AtmosphereSlices {
+    buffer: DynamicUniformBuffer<SliceUniform>,
+    /// The dynamic offset of slices `first..end`, at `first * (ATMOSPHERE_SLICE_COUNT + 1) + end`.
+    offsets: Vec<u32>,
+    size: Option<Extent3d>,
+}
+
+impl AtmosphereSlices {
+    /// The binding of the buffer, once it is written.
+    pub(crate) fn binding(&self) -> Option<BindingResource<'_>> {
+        self.buffer.binding()
+    }
+
+    /// The id of the buffer, once it is written.
+    pub(crate) fn buffer_id(&self) -> Option<BufferId> {
+        self.buffer.buffer().map(Buffer::id)
+    }
+
+    /// The dynamic offset of a range of slices, once the buffer is written.
+    fn offset(&self, slices: Range<u32>) -> Option<u32> {
+        let index = slices.start * (ATMOSPHERE_SLICE_COUNT + 1) + slices.end;
+        self.offsets.get(index as usize).copied()
+    }
+}
+
+/// The rows of the textures of a sky of `size` covered by a range of slices.
+fn slice_rows(size: Extent3d, slices: Range<u32>) -> Range<u32> {
+    let rows = size.height * size.depth_or_array_layers;
+    let row = |slice: u32| slice * rows / ATMOSPHERE_SLICE_COUNT;
+    row(slices.start)..row(slices.end.max(slices.start))
+}
+
+/// Writes the rows of every range of slices whenever the size of the skies changed.
+fn prepare_atmosphere_slices(
+    mut slices: ResMut<AtmosphereSlices>,
+    settings: Res<AtmosphereSettings>,
+    render_device: AtmosphereRenderDevice,
+    render_queue: Res<RenderQueue>,
+) {
+    let size = settings.projection.size(settings.resolution);
+    if slices.size == Some(size) {
+        return;
+    }
+
+    let slices = &mut *slices;
+    slices.buffer.clear();
+    slices.offsets = (0..=ATMOSPHERE_SLICE_COUNT)
+        .flat_map(|first| (0..=ATMOSPHERE_SLICE_COUNT).map(move |end| (first, end)))
+        .map(|(first, end)| {
+            let rows = slice_rows(size, first..end);
+            slices.buffer.push(&SliceUniform {
+                start: rows.start,
+                end: rows.end,
+            })
+        })
+        .collect();
+    render_device.write_dynamic_uniform_buffer(&mut slices.buffer, &render_queue);
+    slices.size = Some(size);
+}
+
+/// A dispatch of the stages of a sky, over some of its slices.
+pub(crate) struct AtmosphereDispatch {
+    /// The size of the textures of the sky.
+    size: Extent3d,
+    /// The number of rows in the slices, see [`slice_rows`].
+    rows: u32,
+    /// The dynamic offset of the slices in [`AtmosphereSlices`].
+    slice_offset: u32,
+    /// Set for the first dispatch of an update.
+    first: bool,
+}
+
+impl AtmosphereDispatch {
+    /// Sets the bind group of the texture a stage renders to, along with the slices it renders.
+    pub(crate) fn set_image_bind_group(&self, pass: &mut ComputePass, image: &BindGroup) {
+        pass.set_bind_group(1, image, &[self.slice_offset]);
+    }
+
+    /// The workgroups covering the rows of the slices, which `slice.wgsl` offsets to the first of them.
+    fn workgroups(&self) -> [u32; 3] {
+        [
+            self.size.width.div_ceil(WORKGROUP_SIZE),
+            self.rows.div_ceil(WORKGROUP_SIZE),
+            1,
+        ]
+    }
+
+    /// Dispatches the texels of the slices.
+    pub(crate) fn dispatch(&self, pass: &mut ComputePass) {
+        let [x, y, z] = self.workgroups();
+        pass.dispatch_workgroups(x, y, z);
+    }
+}
 
-        let mut image = Image::new_fill(
-            Extent3d {
-                width: settings.resolution,
-                height: settings.resolution,
-                depth_or_array_layers: 6,
+/// The resources needed to prepare an [`AtmosphereSkyModel`].
+#[derive(SystemParam)]
+pub(crate) struct AtmosphereModelResources<'w> {
+    pub(crate) gpu_images: Res<'w, RenderAssets<GpuImage>>,
+    pub(crate) render_device: AtmosphereRenderDevice<'w>,
+    render_queue: Res<'w, RenderQueue>,
+    fallback_image: Res<'w, FallbackImage>,
+    type_registry: Res<'w, AtmosphereTypeRegistry>,
+    pub(crate) image_bind_group_layout: Res<'w, AtmosphereImageBindGroupLayout>,
+    pass_bind_group_layout: Res<'w, AtmospherePassBindGroupLayout>,
+    pass_sampler: Res<'w, AtmospherePassSampler>,
+    pub(crate) slices: Res<'w, AtmosphereSlices>,
+    errors: Res<'w, AtmosphereErrors>,
+}
+
+/// The render world state of a single sky.
+///
+/// The model is rendered to the [`AtmosphereImage`], with the optional stages in between:
+/// while fading, the model renders to the second texture of the fade, which blends it with the faded out model,
+/// and with clouds, the model (or the fade) renders to the base texture the clouds are composited over.
+///
+/// With [`AtmosphereUpdateMode::TimeSliced`], the last stage renders to a back buffer instead,
+/// which is copied to the [`AtmosphereImage`] once every slice is rendered.
+/// The stages aren't prepared again until then, so every slice is rendered with the same state.
+/// An [`AtmosphereFormat`] that can't be written by compute shaders uses a back buffer as well, converted when copied.
+///
+/// Once the image is written to, its mips are rendered from one another.
+pub(crate) struct AtmosphereSky {
+    pub(crate) model: AtmosphereSkyModel,
+    pub(crate) image: AtmosphereImage,
+    /// The cross-fade from a model of another type, if any.
+    pub(crate) fade: Option<AtmosphereSkyFade>,
+    /// The clouds composited over the model, if any.
+    pub(crate) clouds: Option<AtmosphereSkyClouds>,
+    /// Set when the model was extracted from the main world.
+    pub(crate) changed: bool,
+    /// Set while the sky is waiting to be rendered.
+    pub(crate) dirty: bool,
+    /// Set when the model is rendered during the current frame.
+    render_model: bool,
+    /// The size, texture and `TextureViewDimension::D2Array` view of the back buffer of time-sliced updates and converted formats.
+    back_buffer: Option<(Extent3d, Texture, TextureView)>,
+    /// The render passes converting the back buffer and rendering the mips, once the image exists.
+    blits: Option<Vec<AtmosphereBlit>>,
+    /// The slices rendered during the current frame.
+    slices: Range<u32>,
+    /// The first slice rendered during the next frame, while a time-sliced update is in progress.
+    next_slice: Option<u32>,
+}
+
+impl AtmosphereSky {
+    fn new(model: AtmosphereModel, handle: Handle<Image>) -> Self {
+        Self {
+            model: AtmosphereSkyModel::new(model),
+            image: AtmosphereImage {
+                handle,
+                array_view: None,
             },
-            TextureDimension::D2,
-            &[0; 4 * 4],
-            TextureFormat::Rgba16Float,
-            RenderAssetUsages::default(),
+            fade: None,
+            clouds: None,
+            changed: false,
+            dirty: true,
+            render_model: false,
+            back_buffer: None,
+            blits: None,
+            slices: 0..0,
+            next_slice: None,
+        }
+    }
+
+    /// The view of the texture the last stage renders to, the back buffer or the image.
+    pub(crate) fn output(&self) -> Option<&TextureView> {
+        match &self.back_buffer {
+            Some((_, _, back_buffer)) => Some(back_buffer),
+            None => self.image.array_view.as_ref(),
+        }
+    }
+
+    /// The view of the texture the clouds are composited over, or of the output if there are no clouds.
+    pub(crate) fn clouds_base(&self) -> Option<&TextureView> {
+        match &self.clouds {
+            Some(clouds) => clouds.base(),
+            None => self.output(),
+        }
+    }
+
+    /// The view of the texture the model renders to.
+    fn model_target(&self) -> Option<&TextureView> {
+        match &self.fade {
+            Some(fade) => fade.target(),
+            None => self.clouds_base(),
+        }
+    }
+
+    /// Whether a time-sliced update is in progress, during which the stages of the sky aren't prepared again.
+    pub(crate) fn updating(&self) -> bool {
+        self.next_slice.is_some()
+    }
+
+    /// Drops the time-sliced update in progress, after the stages of the sky changed.
+    pub(crate) fn cancel_update(&mut self) {
+        self.next_slice = None;
+    }
+
+    /// Whether the [`AtmosphereImage`] is written to during the current frame, if the sky is queued.
+    pub(crate) fn image_updated(&self) -> bool {
+        self.back_buffer.is_none() || self.slices.end == ATMOSPHERE_SLICE_COUNT
+    }
+
+    /// Drops the textures and bind groups of every stage, after the [`AtmosphereImageFormat`] changed.
+    ///
+    /// They are created again before the sky is rendered, which it is as soon as they are.
+    fn drop_stages(&mut self) {
+        self.model.drop_resources();
+        if let Some(fade) = &mut self.fade {
+            fade.drop_resources();
+        }
+        if let Some(clouds) = &mut self.clouds {
+            clouds.drop_resources();
+        }
+        self.back_buffer = None;
+        self.blits = None;
+        self.cancel_update();
+        self.changed = true;
+    }
+
+    /// Whether the passes after the image is written to can be rendered.
+    fn blits_ready(&self, pipeline_cache: &PipelineCache) -> bool {
+        self.blits.as_ref().is_some_and(|blits| {
+            blits
+                .iter()
+                .all(|blit| pipeline_cache.get_render_pipeline(blit.pipeline).is_some())
+        })
+    }
+}
+
+/// A render pass writing a layer and mip of an [`AtmosphereImage`] from another texture.
+pub(crate) struct AtmosphereBlit {
+    pipeline: CachedRenderPipelineId,
+    bind_group: BindGroup,
+    target: TextureView,
+}
+
+/// The render pipelines copying the back buffer of a sky to an [`AtmosphereImage`] of another format,
+/// and rendering the mips of the image.
+#[derive(Resource)]
+pub(crate) struct AtmosphereMipmapPipeline {
+    layout: BindGroupLayout,
+}
+
+impl FromWorld for AtmosphereMipmapPipeline {
+    fn from_world(world: &mut World) -> Self {
+        let render_device = world.resource::<RenderDevice>();
+
+        // the texels are loaded, so any format can be read
+        let layout = render_device.create_bind_group_layout(
+            "bevy_atmosphere_mipmap_bind_group_layout",
+            &BindGroupLayoutEntries::single(
+                ShaderStages::FRAGMENT,
+                texture_2d(TextureSampleType::Float { filterable: false }),
+            ),
         );
 
-        image.texture_view_descriptor = Some(ATMOSPHERE_CUBE_TEXTURE_VIEW_DESCRIPTOR);
+        Self { layout }
+    }
+}
 
-        image.texture_descriptor = ATMOSPHERE_IMAGE_TEXTURE_DESCRIPTOR(settings.resolution);
+/// The target format of an [`AtmosphereMipmapPipeline`], and whether it downsamples the previous mip or copies a back buffer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) struct AtmosphereMipmapPipelineKey {
+    format: TextureFormat,
+    downsample: bool,
+}
+
+impl SpecializedRenderPipeline for AtmosphereMipmapPipeline {
+    type Key = AtmosphereMipmapPipelineKey;
+
+    fn specialize(&self, key: Self::Key) -> RenderPipelineDescriptor {
+        RenderPipelineDescriptor {
+            label: Some("bevy_atmosphere_mipmap_pipeline".into()),
+            layout: vec![self.layout.clone()],
+            push_constant_ranges: vec![],
+            vertex: fullscreen_shader_vertex_state(),
+            fragment: Some(FragmentState {
+                shader: ATMOSPHERE_MIPMAP_SHADER_HANDLE,
+                shader_defs: vec![],
+                entry_point: if key.downsample { "downsample" } else { "copy" }.into(),
+                targets: vec![Some(ColorTargetState {
+                    format: key.format,
+                    blend: None,
+                    write_mask: ColorWrites::ALL,
+                })],
+            }),
+            primitive: default(),
+            depth_stencil: None,
+            multisample: default(),
+            zero_initialize_workgroup_memory: false,
+        }
+    }
+}
+
+/// All skies known to the render world.
+#[derive(Resource, Default)]
+pub(crate) struct AtmosphereSkies(pub(crate) HashMap<AtmosphereSkyId, AtmosphereSky>);
+
+/// The skies rendered by the atmosphere compute pipeline during the current frame.
+///
+/// Filled in at the end of `RenderSet::PrepareBindGroups`,
+/// so nodes after [`BevyAtmosphereLabel`] can post-process the freshly rendered textures.
+#[derive(Resource, Default, Debug, Clone)]
+pub struct AtmosphereSkyQueue(pub Vec<AtmosphereSkyId>);
+
+/// A `Plugin` that creates the compute pipeline for rendering a procedural sky cubemap texture.
+#[derive(Debug, Clone, Copy)]
+pub struct AtmospherePipelinePlugin;
+
+impl Plugin for AtmospherePipelinePlugin {
+    fn build(&self, app: &mut App) {
+        load_internal_asset!(
+            app,
+            ATMOSPHERE_CLOUDS_SHADER_HANDLE,
+            "shaders/clouds.wgsl",
+            Shader::from_wgsl
+        );
+        load_internal_asset!(
+            app,
+            ATMOSPHERE_FADE_SHADER_HANDLE,
+            "shaders/fade.wgsl",
+            Shader::from_wgsl
+        );
+        load_internal_asset!(
+            app,
+            ATMOSPHERE_PROJECTION_SHADER_HANDLE,
+            "shaders/projection.wgsl",
+            Shader::from_wgsl
+        );
+        load_internal_asset!(
+            app,
+            ATMOSPHERE_SLICE_SHADER_HANDLE,
+            "shaders/slice.wgsl",
+            Shader::from_wgsl
+        );
+        load_internal_asset!(
+            app,
+            ATMOSPHERE_IMAGE_SHADER_HANDLE,
+            "shaders/image.wgsl",
+            Shader::from_wgsl
+        );
+        load_internal_asset!(
+            app,
+            ATMOSPHERE_MIPMAP_SHADER_HANDLE,
+            "shaders/mipmap.wgsl",
+            Shader::from_wgsl
+        );
+
+        let errors = AtmosphereErrors::default();
+        app.insert_resource(errors.clone());
+
+        // the device may not exist yet, then the settings are validated in `finish`
+        let settings = validate_atmosphere_settings(app.world_mut());
+        let format = AtmosphereImageFormat(settings.format);
+
+        let image = AtmosphereImage::new_image(
+            settings.resolution,
+            settings.projection,
+            settings.format,
+            settings.mip_level_count(),
+        );
 
         let mut image_assets = app.world_mut().resource_mut::<Assets<Image>>();
         let handle = image_assets.add(image);
@@ -124,144 +1410,276 @@ impl Plugin for AtmospherePipelinePlugin {
         app.insert_resource(AtmosphereImage {
             handle,
             array_view: None,
-        });
-
-        app.add_plugins(ExtractResourcePlugin::<AtmosphereImage>::default());
+        })
+        .insert_resource(format);
 
-        app.add_systems(Update, atmosphere_settings_changed);
+        app.add_event::<AtmosphereError>()
+            .add_systems(First, send_atmosphere_errors)
+            .add_systems(Update, atmosphere_settings_changed)
+            .add_systems(
+                PostUpdate,
+                (atmosphere_model_added, atmosphere_model_removed),
+            );
 
         let type_registry = app.world().resource::<AppTypeRegistry>().clone();
 
         let render_app = app.sub_app_mut(RenderApp);
         render_app
-            .insert_resource(atmosphere)
             .insert_resource(settings)
+            .insert_resource(format)
             .insert_resource(AtmosphereTypeRegistry(type_registry))
-            .init_resource::<CachedAtmosphereModelMetadata>()
+            .insert_resource(errors)
+            .init_resource::<AtmosphereSkies>()
+            .init_resource::<AtmosphereCreated>()
+            .init_resource::<SpecializedRenderPipelines<AtmosphereMipmapPipeline>>()
+            .init_resource::<AtmosphereSkyQueue>()
+            .init_resource::<AtmosphereSlices>()
             .init_resource::<Events<AtmosphereUpdateEvent>>()
-            .add_systems(ExtractSchedule, extract_atmosphere_resources)
+            .add_systems(
+                ExtractSchedule,
+                (
+                    extract_atmosphere_resources,
+                    extract_atmosphere_format,
+                    extract_atmosphere_fades,
+                    extract_atmosphere_clouds,
+                )
+                    .chain(),
+            )
             .add_systems(
                 Render,
                 (
-                    prepare_atmosphere_resources.in_set(RenderSet::PrepareResources),
-                    prepare_atmosphere_bind_group.in_set(RenderSet::PrepareBindGroups),
+                    (
+                        specialize_atmosphere_format,
+                        prepare_atmosphere_slices,
+                        prepare_atmosphere_resources,
+                        prepare_atmosphere_blits,
+                        prepare_atmosphere_fades,
+                        prepare_atmosphere_clouds,
+                    )
+                        .chain()
+                        .in_set(RenderSet::PrepareResources),
+                    (
+                        prepare_atmosphere_bind_group,
+                        prepare_atmosphere_fade_bind_group,
+                        prepare_atmosphere_clouds_bind_group,
+                        queue_atmosphere_skies,
+                    )
+                        .chain()
+                        .in_set(RenderSet::PrepareBindGroups),
                     clear_update_events.in_set(RenderSet::Cleanup),
                 ),
             );
 
-        let mut render_graph = render_app.world_mut().resource_mut::<RenderGraph>();
-        render_graph.add_node(BevyAtmosphereLabel, AtmosphereNode::default());
-        render_graph.add_node_edge(BevyAtmosphereLabel, CameraDriverLabel);
+        let mut render_graph = render_app.world_mut().resource_mut::<RenderGraph>();
+        render_graph.add_node(BevyAtmosphereLabel, AtmosphereNode);
+        render_graph.add_node_edge(BevyAtmosphereLabel, CameraDriverLabel);
+    }
+
+    fn finish(&self, app: &mut App) {
+        validate_atmosphere_settings(app.world_mut());
+
+        app.sub_app_mut(RenderApp)
+            .init_resource::<AtmosphereFadePipeline>()
+            .init_resource::<AtmosphereCloudsPipeline>()
+            .init_resource::<AtmosphereMipmapPipeline>();
+    }
+}
+
+/// Gives entities with an [`AtmosphereModel`] component their own [`AtmosphereImage`] and [`AtmosphereSkyBoxMaterial`].
+pub(crate) fn atmosphere_model_added(
+    mut commands: Commands,
+    mut image_assets: ResMut<Assets<Image>>,
+    mut material_assets: ResMut<Assets<SkyBoxMaterial>>,
+    settings: Option<Res<AtmosphereSettings>>,
+    format: Res<AtmosphereImageFormat>,
+    models: Query<Entity, (With<AtmosphereModel>, Without<AtmosphereImage>)>,
+) {
+    let settings = settings.map(|s| *s).unwrap_or_default();
+
+    for entity in &models {
+        let handle = image_assets.add(AtmosphereImage::new_image(
+            settings.resolution,
+            settings.projection,
+            **format,
+            settings.mip_level_count(),
+        ));
+        let material = material_assets.add(SkyBoxMaterial {
+            sky_texture: handle.clone(),
+            projection: settings.projection,
+            #[cfg(feature = "dithering")]
+            dithering: settings.dithering,
+        });
+
+        trace!("Creating sky image for entity (ID:{:?})", entity);
+
+        commands.entity(entity).insert((
+            AtmosphereImage {
+                handle,
+                array_view: None,
+            },
+            AtmosphereSkyBoxMaterial(material),
+        ));
+    }
+}
+
+/// Removes the [`AtmosphereImage`] and [`AtmosphereSkyBoxMaterial`] of entities that lost their [`AtmosphereModel`].
+fn atmosphere_model_removed(
+    mut commands: Commands,
+    mut removed_models: RemovedComponents<AtmosphereModel>,
+) {
+    for entity in removed_models.read() {
+        if let Ok(mut entity_commands) = commands.get_entity(entity) {
+            trace!("Removing sky image from entity (ID:{:?})", entity);
+            entity_commands.try_remove::<(AtmosphereImage, AtmosphereSkyBoxMaterial)>();
+        }
     }
 }
 
-/// Whenever settings are changed, resize the image to the appropriate size.
+/// Whenever settings are changed, resize the images to the appropriate size and format.
+#[allow(clippy::too_many_arguments)]
 fn atmosphere_settings_changed(
     mut image_assets: ResMut<Assets<Image>>,
     mut material_assets: ResMut<Assets<SkyBoxMaterial>>,
-    mut atmosphere_image: ResMut<AtmosphereImage>,
+    atmosphere_image: ResMut<AtmosphereImage>,
+    mut entity_images: Query<(&mut AtmosphereImage, &AtmosphereSkyBoxMaterial)>,
     mut settings_existed: Local<bool>,
-    settings: Option<Res<AtmosphereSettings>>,
+    settings: Option<ResMut<AtmosphereSettings>>,
+    mut format: ResMut<AtmosphereImageFormat>,
     material: Res<AtmosphereSkyBoxMaterial>,
+    render_device: Option<Res<RenderDevice>>,
+    errors: Res<AtmosphereErrors>,
 ) {
-    if let Some(settings) = settings {
-        if settings.is_changed() {
-            let _atmosphere_settings_changed_executed_span = info_span!(
-                "executed",
-                name = "bevy_atmosphere::pipeline::atmosphere_settings_changed"
-            )
-            .entered();
-            if let Some(image) = image_assets.get_mut(&atmosphere_image.handle) {
-                if settings.resolution % 8 != 0 {
-                    warn!("Resolution is not a multiple of 8, issues may be encountered");
-                }
-                let size = Extent3d {
-                    width: settings.resolution,
-                    height: settings.resolution,
-                    depth_or_array_layers: 6,
-                };
-                image.resize(size);
-                #[cfg(feature = "dithering")]
-                if let Some(skybox_material) = material_assets.get_mut(&material.0) {
-                    // `get_mut` tells the material to update, so it's needed anyways
-                    skybox_material.dithering = settings.dithering;
-                }
-                atmosphere_image.array_view = None; // drop the previous texture view
-                trace!("Resized image to {:?}", size);
+    let settings = match settings {
+        Some(mut settings) => {
+            let changed = settings.is_changed();
+            *settings_existed = true;
+            if !changed {
+                return;
             }
+            // the render world reads the resolution from the settings too, so they are clamped in place
+            if let Some(render_device) = render_device {
+                let settings = settings.bypass_change_detection();
+                let max_texture_dimension_2d = render_device.limits().max_texture_dimension_2d;
+                clamp_resolution(settings, max_texture_dimension_2d, &errors);
+                settings.format =
+                    supported_format(settings.format, render_device.features(), &errors);
+            }
+            *settings
         }
-        *settings_existed = true;
-    } else {
-        if *settings_existed {
-            let _atmosphere_settings_changed_executed_span = info_span!(
-                "executed",
-                name = "bevy_atmosphere::pipeline::atmosphere_settings_changed"
-            )
-            .entered();
-            if let Some(image) = image_assets.get_mut(&atmosphere_image.handle) {
-                let resolution = AtmosphereSettings::default().resolution;
-                if resolution % 8 != 0 {
-                    warn!("Resolution is not a multiple of 8, issues may be encountered");
+        None => {
+            if !*settings_existed {
+                return;
+            }
+            *settings_existed = false;
+            AtmosphereSettings::default()
+        }
+    };
+
+    let _atmosphere_settings_changed_executed_span = info_span!(
+        "executed",
+        name = "bevy_atmosphere::pipeline::atmosphere_settings_changed"
+    )
+    .entered();
+
+    // the render world specializes the pipelines for the new format
+    format.set_if_neq(AtmosphereImageFormat(settings.format));
+
+    let size = settings.projection.size(settings.resolution);
+    let mip_level_count = settings.mip_level_count();
+
+    let targets = std::iter::once((atmosphere_image.into_inner(), &material.0)).chain(
+        entity_images
+            .iter_mut()
+            .map(|(image, material)| (image.into_inner(), &material.0)),
+    );
+
+    for (atmosphere_image, material) in targets {
+        if let Some(image) = image_assets.get_mut(&atmosphere_image.handle) {
+            // the image has no data, the texture is created again from the descriptor
+            image.texture_descriptor.size = size;
+            image.texture_descriptor.mip_level_count = mip_level_count;
+            image.texture_descriptor.format = format.texture_format();
+            image.texture_descriptor.usage = atmosphere_image_usages(**format, mip_level_count);
+            image.texture_view_descriptor =
+                Some(atmosphere_image_view_descriptor(settings.projection));
+            if let Some(skybox_material) = material_assets.get_mut(material) {
+                skybox_material.projection = settings.projection;
+                #[cfg(feature = "dithering")]
+                {
+                    skybox_material.dithering = settings.dithering;
                 }
-                let size = Extent3d {
-                    width: resolution,
-                    height: resolution,
-                    depth_or_array_layers: 6,
-                };
-                image.resize(size);
-                let _ = material_assets.get_mut(&material.0); // `get_mut` tells the material to update
-                atmosphere_image.array_view = None; // drop the previous texture view
-                trace!("Resized image to {:?}", size);
             }
+            atmosphere_image.array_view = None; // drop the previous texture view
+            trace!("Resized image to {:?}", size);
         }
-        *settings_existed = false;
     }
 }
 
-/// Extracts [`AtmosphereModel`] and [`AtmosphereSettings`] from main world.
-#[allow(clippy::too_many_arguments)]
+/// Extracts [`AtmosphereModel`]s, [`AtmosphereImage`]s and [`AtmosphereSettings`] from main world.
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
 fn extract_atmosphere_resources(
-    type_registry: Res<AtmosphereTypeRegistry>,
-    mut cached_metadata: ResMut<CachedAtmosphereModelMetadata>,
+    mut skies: ResMut<AtmosphereSkies>,
     main_atmosphere: Extract<Option<Res<AtmosphereModel>>>,
-    mut render_atmosphere: ResMut<AtmosphereModel>,
+    main_image: Extract<Option<Res<AtmosphereImage>>>,
+    main_entities: Extract<Query<(Entity, Ref<AtmosphereModel>, Ref<AtmosphereImage>)>>,
     mut atmosphere_existed: Local<bool>,
     main_settings: Extract<Option<Res<AtmosphereSettings>>>,
     mut render_settings: ResMut<AtmosphereSettings>,
     mut settings_existed: Local<bool>,
 ) {
-    macro_rules! cache_metadata {
-        ($id:ident) => {
-            *cached_metadata = CachedAtmosphereModelMetadata(Some({
-                let type_registry = type_registry.read();
-                type_registry
-                    .get_type_data::<AtmosphereModelMetadata>($id)
-                    .expect("Failed to get type data")
-                    .clone()
-            }));
-        };
-    }
-
-    if let Some(atmosphere) = &*main_atmosphere {
-        if atmosphere.is_changed() {
-            *render_atmosphere = AtmosphereModel::extract_resource(atmosphere);
-            let id = render_atmosphere.model().type_id();
-            if let CachedAtmosphereModelMetadata(Some(metadata)) = cached_metadata.as_ref() {
-                if metadata.id != id {
-                    cache_metadata!(id);
+    let mut extract_sky = |id: AtmosphereSkyId,
+                           model: Option<&AtmosphereModel>,
+                           image: &AtmosphereImage,
+                           image_changed: bool| {
+        match skies.0.get_mut(&id) {
+            Some(sky) => {
+                if let Some(model) = model {
+                    sky.model
+                        .set_model(AtmosphereModel::extract_resource(model));
+                    sky.changed = true;
+                }
+                if image_changed {
+                    sky.image = AtmosphereImage::extract_resource(image);
                 }
             }
+            None => {
+                let model = model.cloned().unwrap_or_default();
+                skies
+                    .0
+                    .insert(id, AtmosphereSky::new(model, image.handle.clone()));
+            }
         }
-        *atmosphere_existed = true;
-    } else {
-        if *atmosphere_existed {
-            *render_atmosphere = AtmosphereModel::extract_resource(&AtmosphereModel::default());
-            let id = render_atmosphere.model().type_id();
-            cache_metadata!(id);
+    };
+
+    if let Some(image) = &*main_image {
+        if let Some(atmosphere) = &*main_atmosphere {
+            let model = atmosphere.is_changed().then_some(&**atmosphere);
+            extract_sky(AtmosphereSkyId::Global, model, image, image.is_changed());
+            *atmosphere_existed = true;
+        } else {
+            let default_model = AtmosphereModel::default();
+            let model = (*atmosphere_existed).then_some(&default_model);
+            extract_sky(AtmosphereSkyId::Global, model, image, image.is_changed());
+            *atmosphere_existed = false;
         }
-        *atmosphere_existed = false;
     }
 
+    let mut entities = HashSet::new();
+    for (entity, model, image) in &main_entities {
+        let model_changed = model.is_changed();
+        extract_sky(
+            AtmosphereSkyId::Entity(entity),
+            model_changed.then_some(&*model),
+            &image,
+            image.is_changed(),
+        );
+        entities.insert(entity);
+    }
+    skies.0.retain(|id, _| match id {
+        AtmosphereSkyId::Global => main_image.is_some(),
+        AtmosphereSkyId::Entity(entity) => entities.contains(entity),
+    });
+
     if let Some(settings) = &*main_settings {
         if settings.is_changed() {
             *render_settings = AtmosphereSettings::extract_resource(settings);
@@ -275,10 +1693,62 @@ fn extract_atmosphere_resources(
     }
 }
 
+/// Extracts the [`AtmosphereImageFormat`] from the main world.
+fn extract_atmosphere_format(
+    main_format: Extract<Res<AtmosphereImageFormat>>,
+    mut format: ResMut<AtmosphereImageFormat>,
+) {
+    format.set_if_neq(**main_format);
+}
+
+/// Specializes the layouts and pipelines for the [`AtmosphereImageFormat`] again whenever it changes,
+/// and drops the textures and bind groups of every sky, which are created again in the new format.
+fn specialize_atmosphere_format(
+    world: &mut World,
+    mut specialized: Local<Option<AtmosphereFormat>>,
+) {
+    let format = **world.resource::<AtmosphereImageFormat>();
+    // the plugin specializes everything for the format it starts with
+    if specialized
+        .replace(format)
+        .is_none_or(|specialized| specialized == format)
+    {
+        return;
+    }
+
+    let image_bind_group_layout = AtmosphereImageBindGroupLayout::from_world(world);
+    {
+        let pipeline_cache = world.resource::<PipelineCache>();
+        let mut type_registry = world.resource::<AtmosphereTypeRegistry>().write();
+        for registration in type_registry.iter_mut() {
+            if let Some(metadata) = registration.data_mut::<AtmosphereModelMetadata>() {
+                metadata.pipeline = queue_atmosphere_model_pipeline(
+                    pipeline_cache,
+                    metadata.shader.clone(),
+                    &metadata.bind_group_layout,
+                    &image_bind_group_layout.0,
+                    metadata.pass_bind_group_layout.as_ref(),
+                    format,
+                );
+            }
+        }
+    }
+    world.insert_resource(image_bind_group_layout);
+
+    let fade_pipeline = AtmosphereFadePipeline::from_world(world);
+    let clouds_pipeline = AtmosphereCloudsPipeline::from_world(world);
+    world.insert_resource(fade_pipeline);
+    world.insert_resource(clouds_pipeline);
+
+    for sky in world.resource_mut::<AtmosphereSkies>().0.values_mut() {
+        sky.drop_stages();
+    }
+}
+
 /// For creating a `TextureView` with `TextureViewDimension::Cube`.
 pub const ATMOSPHERE_CUBE_TEXTURE_VIEW_DESCRIPTOR: TextureViewDescriptor = TextureViewDescriptor {
     label: Some("atmosphere_image_array_view"),
-    format: Some(TextureFormat::Rgba16Float),
+    format: None,
     dimension: Some(TextureViewDimension::Cube),
     aspect: TextureAspect::All,
     base_mip_level: 0,
@@ -288,20 +1758,39 @@ pub const ATMOSPHERE_CUBE_TEXTURE_VIEW_DESCRIPTOR: TextureViewDescriptor = Textu
     usage: None,
 };
 
-/// For creating a `TextureView` with `TextureViewDimension::D2Array`.
+/// For creating a `TextureView` with `TextureViewDimension::D2Array`, over every layer of the first mip of the texture.
 pub const ATMOSPHERE_ARRAY_TEXTURE_VIEW_DESCRIPTOR: TextureViewDescriptor = TextureViewDescriptor {
     label: Some("atmosphere_image_cube_view"),
-    format: Some(TextureFormat::Rgba16Float),
+    format: None,
     dimension: Some(TextureViewDimension::D2Array),
     aspect: TextureAspect::All,
     base_mip_level: 0,
-    mip_level_count: None,
+    mip_level_count: Some(1),
     base_array_layer: 0,
-    array_layer_count: Some(6),
+    array_layer_count: None,
     usage: None,
 };
 
-/// For creating a `Texture` with 6 layers.
+/// The `TextureViewDescriptor` of an [`AtmosphereImage`] with the given projection.
+fn atmosphere_image_view_descriptor(
+    projection: AtmosphereProjection,
+) -> TextureViewDescriptor<'static> {
+    match projection {
+        AtmosphereProjection::Cubemap => ATMOSPHERE_CUBE_TEXTURE_VIEW_DESCRIPTOR,
+        AtmosphereProjection::Equirectangular | AtmosphereProjection::Octahedral => {
+            TextureViewDescriptor {
+                label: Some("atmosphere_image_view"),
+                dimension: Some(projection.view_dimension()),
+                array_layer_count: Some(1),
+                ..ATMOSPHERE_CUBE_TEXTURE_VIEW_DESCRIPTOR
+            }
+        }
+    }
+}
+
+/// For creating a `Texture` with 6 layers, in the default [`AtmosphereFormat`] without mips.
+///
+/// Replace its `size` with [`AtmosphereProjection::size`] for other projections.
 pub const ATMOSPHERE_IMAGE_TEXTURE_DESCRIPTOR: fn(u32) -> TextureDescriptor<'static> =
     |res| TextureDescriptor {
         label: Some("atmosphere_image_texture"),
@@ -320,99 +1809,165 @@ pub const ATMOSPHERE_IMAGE_TEXTURE_DESCRIPTOR: fn(u32) -> TextureDescriptor<'sta
         view_formats: &[TextureFormat::Rgba16Float],
     };
 
-/// Whenever settings changed, the texture view needs to be updated to use the new texture.
+/// Whenever settings changed, the texture views need to be updated to use the new textures.
+///
+/// Sends an [`AtmosphereUpdateEvent`] for every sky that needs to be rendered.
 fn prepare_atmosphere_resources(
     mut update_events: ResMut<Events<AtmosphereUpdateEvent>>,
-    mut atmosphere_image: ResMut<AtmosphereImage>,
+    mut skies: ResMut<AtmosphereSkies>,
     gpu_images: Res<RenderAssets<GpuImage>>,
-    atmosphere: Res<AtmosphereModel>,
+    settings: Res<AtmosphereSettings>,
+    format: Res<AtmosphereImageFormat>,
+    render_device: AtmosphereRenderDevice,
 ) {
-    let mut update = || update_events.send(AtmosphereUpdateEvent);
+    let size = settings.projection.size(settings.resolution);
+    let back_buffered = !format.is_storage()
+        || matches!(
+            settings.update_mode,
+            AtmosphereUpdateMode::TimeSliced { .. }
+        );
 
-    if atmosphere_image.array_view.is_none() {
-        let _prepare_atmosphere_assets_executed_span = info_span!(
-            "executed",
-            name = "bevy_atmosphere::pipeline::prepare_atmosphere_assets"
-        )
-        .entered();
-        let texture = &gpu_images.get(&atmosphere_image.handle).unwrap().texture;
-        let view = texture.create_view(&ATMOSPHERE_ARRAY_TEXTURE_VIEW_DESCRIPTOR);
-        atmosphere_image.array_view = Some(view);
-        update();
-        if let Some(image) = gpu_images.get(&atmosphere_image.handle) {
+    for (id, sky) in skies.0.iter_mut() {
+        let back_buffer_size = sky.back_buffer.as_ref().map(|(size, _, _)| *size);
+        if back_buffer_size != back_buffered.then_some(size) {
+            sky.back_buffer = back_buffered.then(|| {
+                let texture = render_device.create_texture(&atmosphere_stage_texture_descriptor(
+                    "atmosphere_back_buffer_texture",
+                    size,
+                    **format,
+                ));
+                let view = texture.create_view(&ATMOSPHERE_ARRAY_TEXTURE_VIEW_DESCRIPTOR);
+                (size, texture, view)
+            });
+            // the stages render to another texture from now on
+            sky.cancel_update();
+            sky.blits = None;
+            sky.changed = true;
+        }
+
+        if sky.image.array_view.is_none() {
+            sky.cancel_update();
+            sky.blits = None;
+            let _prepare_atmosphere_assets_executed_span = info_span!(
+                "executed",
+                name = "bevy_atmosphere::pipeline::prepare_atmosphere_assets"
+            )
+            .entered();
+            let Some(image) = gpu_images.get(&sky.image.handle) else {
+                trace!("Failed to find gpu_image for {:?}", &sky.image.handle);
+                continue;
+            };
+            let view = image
+                .texture
+                .create_view(&ATMOSPHERE_ARRAY_TEXTURE_VIEW_DESCRIPTOR);
+            sky.image.array_view = Some(view);
+            update_events.send(AtmosphereUpdateEvent(*id));
             trace!(
                 "Created new 2D array texture view from atmosphere texture of size {:?}",
                 image.size
             );
-        } else {
-            trace!(
-                "Failed to find gpu_image for {:?}",
-                &atmosphere_image.handle
-            );
+        } else if sky.changed || sky.model.images_changed(&gpu_images) {
+            // the model is rendered again with the images it binds
+            update_events.send(AtmosphereUpdateEvent(*id));
         }
-    }
-
-    if atmosphere.is_changed() {
-        update();
+        sky.changed = false;
     }
 }
 
-/// Queue the generated bind groups for the compute pipeline.
-#[allow(clippy::too_many_arguments)]
-fn prepare_atmosphere_bind_group(
-    mut commands: Commands,
-    mut cached_metadata: ResMut<CachedAtmosphereModelMetadata>,
+/// Creates the render passes converting the back buffer and rendering the mips of every sky whose textures changed.
+fn prepare_atmosphere_blits(
+    mut skies: ResMut<AtmosphereSkies>,
     gpu_images: Res<RenderAssets<GpuImage>>,
-    atmosphere_image: Res<AtmosphereImage>,
-    render_device: Res<RenderDevice>,
-    fallback_image: Res<FallbackImage>,
-    type_registry: Res<AtmosphereTypeRegistry>,
-    image_bind_group_layout: Res<AtmosphereImageBindGroupLayout>,
-    atmosphere: Option<Res<AtmosphereModel>>,
+    render_device: AtmosphereRenderDevice,
+    pipeline_cache: Res<PipelineCache>,
+    mipmap_pipeline: Res<AtmosphereMipmapPipeline>,
+    mut pipelines: ResMut<SpecializedRenderPipelines<AtmosphereMipmapPipeline>>,
 ) {
-    let view = atmosphere_image.array_view.as_ref().expect("prepare_changed_settings should have took care of making AtmosphereImage.array_value Some(TextureView)");
-
-    let atmosphere = match atmosphere {
-        Some(a) => a.clone(),
-        None => default(),
+    let layer_view = |texture: &Texture, mip, layer| {
+        texture.create_view(&TextureViewDescriptor {
+            label: Some("atmosphere_image_layer_view"),
+            dimension: Some(TextureViewDimension::D2),
+            base_mip_level: mip,
+            mip_level_count: Some(1),
+            base_array_layer: layer,
+            array_layer_count: Some(1),
+            ..default()
+        })
     };
 
-    let bind_group_layout = {
-        let data = cached_metadata.clone().0.unwrap_or_else(|| {
-            let data = {
-                let type_registry = type_registry.read();
-                type_registry
-                    .get_type_data::<AtmosphereModelMetadata>(atmosphere.model().type_id())
-                    .expect("Failed to get type data")
-                    .clone()
-            };
-            *cached_metadata = CachedAtmosphereModelMetadata(Some(data.clone()));
-            data
-        });
-        data.bind_group_layout
-    };
+    for sky in skies.0.values_mut() {
+        if sky.blits.is_some() || sky.image.array_view.is_none() {
+            continue;
+        }
+        let Some(image) = gpu_images.get(&sky.image.handle) else {
+            continue;
+        };
+        let texture = &image.texture;
 
-    let atmosphere_bind_group = atmosphere.model().as_bind_group(
-        &bind_group_layout,
-        &render_device,
-        &gpu_images,
-        &fallback_image,
-    );
+        let mut blit = |downsample, source: &TextureView, target| AtmosphereBlit {
+            pipeline: pipelines.specialize(
+                &pipeline_cache,
+                &mipmap_pipeline,
+                AtmosphereMipmapPipelineKey {
+                    format: texture.format(),
+                    downsample,
+                },
+            ),
+            bind_group: render_device.create_bind_group(
+                "bevy_atmosphere_mipmap_bind_group",
+                &mipmap_pipeline.layout,
+                &BindGroupEntries::single(source),
+            ),
+            target,
+        };
 
-    let image_bind_group = render_device.create_bind_group(
-        "bevy_atmosphere_image_bind_group",
-        &image_bind_group_layout.0,
-        &BindGroupEntries::single(BindingResource::TextureView(view)),
-    );
+        // a back buffer of the same format is copied instead
+        let converted = sky
+            .back_buffer
+            .as_ref()
+            .filter(|(_, back_buffer, _)| back_buffer.format() != texture.format());
+
+        let mut blits = Vec::new();
+        for layer in 0..texture.depth_or_array_layers() {
+            if let Some((_, back_buffer, _)) = converted {
+                let source = layer_view(back_buffer, 0, layer);
+                blits.push(blit(false, &source, layer_view(texture, 0, layer)));
+            }
+            for mip in 1..texture.mip_level_count() {
+                let source = layer_view(texture, mip - 1, layer);
+                blits.push(blit(true, &source, layer_view(texture, mip, layer)));
+            }
+        }
+        sky.blits = Some(blits);
+    }
+}
+
+/// Creates the bind groups for the model of every sky that needs to be rendered.
+fn prepare_atmosphere_bind_group(
+    mut skies: ResMut<AtmosphereSkies>,
+    update_events: Res<Events<AtmosphereUpdateEvent>>,
+    resources: AtmosphereModelResources,
+) {
+    for AtmosphereUpdateEvent(id) in update_events.iter_current_update_events() {
+        if let Some(sky) = skies.0.get_mut(id) {
+            sky.dirty = true;
+        }
+    }
 
-    commands.insert_resource(AtmosphereBindGroups(
-        atmosphere_bind_group,
-        image_bind_group,
-    ));
+    for (id, sky) in skies
+        .0
+        .iter_mut()
+        .filter(|(_, sky)| sky.dirty && !sky.updating())
+    {
+        let Some(target) = sky.model_target().cloned() else {
+            continue;
+        };
+        sky.model.prepare(*id, &target, &resources);
+    }
 }
 
 #[derive(Resource)]
-struct AtmosphereTypeRegistry(AppTypeRegistry);
+pub(crate) struct AtmosphereTypeRegistry(AppTypeRegistry);
 
 impl Deref for AtmosphereTypeRegistry {
     type Target = AppTypeRegistry;
@@ -422,104 +1977,198 @@ impl Deref for AtmosphereTypeRegistry {
     }
 }
 
-enum AtmosphereState {
-    Loading,
-    Update,
-}
+/// Picks the skies with a stage that is ready to be rendered this frame.
+///
+/// Every stage of a sky renders again when the texture it reads from was rendered to,
+/// so a sky whose fade or clouds changed is queued as well, even if its model doesn't need to be rendered again.
+///
+/// A time-sliced update renders the same stages during every frame, until its last slice is rendered.
+pub(crate) fn queue_atmosphere_skies(
+    mut skies: ResMut<AtmosphereSkies>,
+    mut queue: ResMut<AtmosphereSkyQueue>,
+    pipeline_cache: Res<PipelineCache>,
+    fade_pipeline: Res<AtmosphereFadePipeline>,
+    clouds_pipeline: Res<AtmosphereCloudsPipeline>,
+    settings: Res<AtmosphereSettings>,
+    errors: Res<AtmosphereErrors>,
+) {
+    queue.0.clear();
 
-struct AtmosphereNode {
-    state: AtmosphereState,
-}
+    let slices_per_frame = match settings.update_mode {
+        AtmosphereUpdateMode::Immediate => ATMOSPHERE_SLICE_COUNT,
+        AtmosphereUpdateMode::TimeSliced { slices_per_frame } => {
+            slices_per_frame.clamp(1, ATMOSPHERE_SLICE_COUNT)
+        }
+    };
 
-impl Default for AtmosphereNode {
-    fn default() -> Self {
-        Self {
-            state: AtmosphereState::Loading,
+    let ready = |pipeline| {
+        matches!(
+            pipeline_cache.get_compute_pipeline_state(pipeline),
+            CachedPipelineState::Ok(_)
+        )
+    };
+    let fade_ready = ready(fade_pipeline.pipeline);
+    let clouds_ready = ready(clouds_pipeline.pipeline);
+
+    for (id, sky) in skies.0.iter_mut() {
+        sky.model.check_pipelines(*id, &pipeline_cache, &errors);
+        if let Some(fade) = &mut sky.fade {
+            fade.from.check_pipelines(*id, &pipeline_cache, &errors);
         }
-    }
-}
 
-impl render_graph::Node for AtmosphereNode {
-    fn update(&mut self, world: &mut World) {
-        match self.state {
-            AtmosphereState::Loading => {
-                let cached_metadata = world.resource::<CachedAtmosphereModelMetadata>();
-                let pipeline = {
-                    let data = cached_metadata.clone().0.unwrap_or_else(|| {
-                        let atmosphere = world.resource::<AtmosphereModel>();
-                        let type_registry = world.resource::<AtmosphereTypeRegistry>();
-                        let data = {
-                            let type_registry = type_registry.read();
-                            type_registry
-                                .get_type_data::<AtmosphereModelMetadata>(
-                                    atmosphere.model().type_id(),
-                                )
-                                .expect("Failed to get type data")
-                                .clone()
-                        };
-                        let mut cached_metadata =
-                            world.resource_mut::<CachedAtmosphereModelMetadata>();
-                        *cached_metadata = CachedAtmosphereModelMetadata(Some(data.clone()));
-                        data
-                    });
-                    data.pipeline
-                };
+        let first_slice = match sky.next_slice {
+            Some(slice) => slice,
+            // the image can't be finished yet
+            None if !sky.blits_ready(&pipeline_cache) => continue,
+            None => {
+                sky.render_model = sky.dirty && sky.model.is_ready(&pipeline_cache);
+                sky.dirty &= !sky.render_model;
 
-                let pipeline_cache = world.resource::<PipelineCache>();
+                let mut rendered = sky.render_model;
+                let mut render = rendered;
+                if let Some(fade) = &mut sky.fade {
+                    rendered = fade.queue(rendered, fade_ready, &pipeline_cache);
+                    render |= rendered || fade.render_from;
+                }
+                if let Some(clouds) = &mut sky.clouds {
+                    rendered = clouds.queue(rendered, clouds_ready);
+                    render |= rendered;
+                }
 
-                if let CachedPipelineState::Ok(_) =
-                    pipeline_cache.get_compute_pipeline_state(pipeline)
-                {
-                    let mut event_writer = world.resource_mut::<Events<AtmosphereUpdateEvent>>();
-                    event_writer.send(AtmosphereUpdateEvent);
-                    self.state = AtmosphereState::Update;
+                if !render {
+                    continue;
                 }
+                0
             }
-            AtmosphereState::Update => {}
-        }
+        };
+
+        // without a back buffer, the image can't be shown half updated
+        let end = if sky.back_buffer.is_some() {
+            (first_slice + slices_per_frame).min(ATMOSPHERE_SLICE_COUNT)
+        } else {
+            ATMOSPHERE_SLICE_COUNT
+        };
+        sky.slices = first_slice..end;
+        sky.next_slice = (end < ATMOSPHERE_SLICE_COUNT).then_some(end);
+        queue.0.push(*id);
     }
+}
 
+/// Renders the skies in the [`AtmosphereSkyQueue`].
+struct AtmosphereNode;
+
+impl render_graph::Node for AtmosphereNode {
     fn run(
         &self,
         _graph: &mut render_graph::RenderGraphContext,
         render_context: &mut bevy::render::renderer::RenderContext,
         world: &World,
     ) -> Result<(), render_graph::NodeRunError> {
-        let update_events = world.resource::<Events<AtmosphereUpdateEvent>>();
-        match self.state {
-            AtmosphereState::Loading => {}
-            AtmosphereState::Update => {
-                if !update_events.is_empty() {
-                    // only run when there are update events available
-                    let bind_groups = world.resource::<AtmosphereBindGroups>();
-                    let pipeline_cache = world.resource::<PipelineCache>();
-                    let cached_metadata = world.resource::<CachedAtmosphereModelMetadata>();
-                    let settings = world.resource::<AtmosphereSettings>();
-
-                    let pipeline = {
-                        let data = cached_metadata.0.clone().expect("Failed to get type data!");
-                        data.pipeline
-                    };
-
-                    let mut pass = render_context.command_encoder().begin_compute_pass(
-                        &ComputePassDescriptor {
-                            label: Some("atmosphere_pass"),
-                            timestamp_writes: None,
-                        },
-                    );
+        let AtmosphereSkyQueue(queue) = world.resource::<AtmosphereSkyQueue>();
+        if queue.is_empty() {
+            return Ok(());
+        }
+
+        let skies = world.resource::<AtmosphereSkies>();
+        let pipeline_cache = world.resource::<PipelineCache>();
+        let settings = world.resource::<AtmosphereSettings>();
+        let size = settings.projection.size(settings.resolution);
+        let fade_pipeline = world.resource::<AtmosphereFadePipeline>();
+        let clouds_pipeline = world.resource::<AtmosphereCloudsPipeline>();
+        let slices = world.resource::<AtmosphereSlices>();
+        let gpu_images = world.resource::<RenderAssets<GpuImage>>();
+
+        let mut pass =
+            render_context
+                .command_encoder()
+                .begin_compute_pass(&ComputePassDescriptor {
+                    label: Some("atmosphere_pass"),
+                    timestamp_writes: None,
+                });
+
+        for id in queue {
+            let Some(sky) = skies.0.get(id) else {
+                continue;
+            };
+
+            let Some(slice_offset) = slices.offset(sky.slices.clone()) else {
+                continue;
+            };
+            let dispatch = AtmosphereDispatch {
+                size,
+                rows: slice_rows(size, sky.slices.clone()).len() as u32,
+                slice_offset,
+                first: sky.slices.start == 0,
+            };
+
+            if let Some(fade) = sky.fade.as_ref().filter(|fade| fade.render_from) {
+                fade.from.render(&mut pass, pipeline_cache, &dispatch);
+            }
 
-                    pass.set_bind_group(0, &bind_groups.0, &[]);
-                    pass.set_bind_group(1, &bind_groups.1, &[]);
+            if sky.render_model {
+                sky.model.render(&mut pass, pipeline_cache, &dispatch);
+            }
+
+            // the stages after the model read the texture the previous one rendered to
+            if let Some(fade) = sky.fade.as_ref().filter(|fade| fade.render) {
+                fade.render(&mut pass, pipeline_cache, fade_pipeline, &dispatch);
+            }
+
+            if let Some(clouds) = sky.clouds.as_ref().filter(|clouds| clouds.render) {
+                clouds.render(&mut pass, pipeline_cache, clouds_pipeline, &dispatch);
+            }
+        }
+
+        drop(pass);
+
+        // time-sliced updates are shown at once, when their last slice is rendered
+        for id in queue {
+            let Some(sky) = skies.0.get(id).filter(|sky| sky.image_updated()) else {
+                continue;
+            };
+            let Some(image) = gpu_images.get(&sky.image.handle) else {
+                continue;
+            };
 
-                    let update_pipeline = pipeline_cache.get_compute_pipeline(pipeline).unwrap();
-                    pass.set_pipeline(update_pipeline);
-                    pass.dispatch_workgroups(
-                        settings.resolution / WORKGROUP_SIZE,
-                        settings.resolution / WORKGROUP_SIZE,
-                        6,
+            if let Some((back_buffer_size, back_buffer, _)) = &sky.back_buffer {
+                if image.texture.size() != *back_buffer_size {
+                    continue;
+                }
+                // other formats are converted by the first blits
+                if back_buffer.format() == image.texture.format() {
+                    render_context.command_encoder().copy_texture_to_texture(
+                        back_buffer.as_image_copy(),
+                        image.texture.as_image_copy(),
+                        *back_buffer_size,
                     );
                 }
             }
+
+            for blit in sky.blits.iter().flatten() {
+                let Some(pipeline) = pipeline_cache.get_render_pipeline(blit.pipeline) else {
+                    continue;
+                };
+                let mut pass =
+                    render_context
+                        .command_encoder()
+                        .begin_render_pass(&RenderPassDescriptor {
+                            label: Some("atmosphere_mipmap_pass"),
+                            color_attachments: &[Some(RenderPassColorAttachment {
+                                view: &blit.target,
+                                resolve_target: None,
+                                ops: Operations {
+                                    load: LoadOp::Clear(default()),
+                                    store: StoreOp::Store,
+                                },
+                            })],
+                            depth_stencil_attachment: None,
+                            timestamp_writes: None,
+                            occlusion_query_set: None,
+                        });
+                pass.set_pipeline(pipeline);
+                pass.set_bind_group(0, &*blit.bind_group, &[]);
+                pass.draw(0..3, 0..1);
+            }
         }
 
         Ok(())
@@ -529,3 +2178,383 @@ impl render_graph::Node for AtmosphereNode {
 fn clear_update_events(mut update_events: ResMut<Events<AtmosphereUpdateEvent>>) {
     update_events.clear();
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn new_image_matches_projection() {
+        for (projection, size, dimension) in [
+            (
+                AtmosphereProjection::Cubemap,
+                (64, 64, 6),
+                TextureViewDimension::Cube,
+            ),
+            (
+                AtmosphereProjection::Equirectangular,
+                (128, 64, 1),
+                TextureViewDimension::D2,
+            ),
+            (
+                AtmosphereProjection::Octahedral,
+                (64, 64, 1),
+                TextureViewDimension::D2,
+            ),
+        ] {
+            let image = AtmosphereImage::new_image(64, projection, AtmosphereFormat::default(), 1);
+            let extent = image.texture_descriptor.size;
+
+            assert_eq!(
+                (extent.width, extent.height, extent.depth_or_array_layers),
+                size,
+                "{projection:?}"
+            );
+            assert_eq!(
+                image.texture_view_descriptor.unwrap().dimension,
+                Some(dimension),
+                "{projection:?}"
+            );
+            // the texture is rendered to, and its mips can't be uploaded from a single level
+            assert!(image.data.is_none(), "{projection:?}");
+        }
+    }
+
+    #[test]
+    fn new_image_matches_format() {
+        let settings = AtmosphereSettings {
+            resolution: 64,
+            mipmaps: true,
+            ..default()
+        };
+        assert_eq!(settings.mip_level_count(), 7);
+        assert_eq!(AtmosphereSettings::default().mip_level_count(), 1);
+
+        for format in [
+            AtmosphereFormat::Rgba16Float,
+            AtmosphereFormat::Rgba32Float,
+            AtmosphereFormat::Rg11b10Ufloat,
+            AtmosphereFormat::Rgba8Unorm,
+        ] {
+            let image = AtmosphereImage::new_image(
+                64,
+                AtmosphereProjection::Cubemap,
+                format,
+                settings.mip_level_count(),
+            );
+            let descriptor = image.texture_descriptor;
+
+            assert_eq!(descriptor.format, format.texture_format());
+            assert_eq!(descriptor.mip_level_count, 7);
+            assert_eq!(
+                descriptor.usage.contains(TextureUsages::STORAGE_BINDING),
+                format.is_storage(),
+                "{format:?}"
+            );
+            assert!(descriptor.usage.contains(TextureUsages::RENDER_ATTACHMENT));
+            assert!(descriptor
+                .format
+                .guaranteed_format_features(WgpuFeatures::RG11B10UFLOAT_RENDERABLE)
+                .allowed_usages
+                .contains(descriptor.usage));
+        }
+    }
+
+    #[test]
+    fn slices_cover_every_row_once() {
+        let cubemap = AtmosphereProjection::Cubemap.size(64);
+        for face in 0..ATMOSPHERE_SLICE_COUNT {
+            assert_eq!(
+                slice_rows(cubemap, face..face + 1),
+                face * 64..(face + 1) * 64
+            );
+        }
+
+        let equirectangular = AtmosphereProjection::Equirectangular.size(64);
+        let mut next_row = 0;
+        for slice in 0..ATMOSPHERE_SLICE_COUNT {
+            let rows = slice_rows(equirectangular, slice..slice + 1);
+            assert_eq!(rows.start, next_row);
+            next_row = rows.end;
+        }
+        assert_eq!(next_row, 64);
+
+        assert_eq!(
+            slice_rows(equirectangular, 0..ATMOSPHERE_SLICE_COUNT),
+            0..64
+        );
+        assert!(slice_rows(cubemap, 4..2).is_empty());
+    }
+
+    #[test]
+    fn dispatches_cover_only_their_slices() {
+        let dispatch = |size: Extent3d, slices| AtmosphereDispatch {
+            size,
+            rows: slice_rows(size, slices).len() as u32,
+            slice_offset: 0,
+            first: true,
+        };
+
+        let cubemap = AtmosphereProjection::Cubemap.size(60);
+        assert_eq!(dispatch(cubemap, 2..3).workgroups(), [8, 8, 1]);
+        assert_eq!(
+            dispatch(cubemap, 0..ATMOSPHERE_SLICE_COUNT).workgroups(),
+            [8, 45, 1]
+        );
+
+        let equirectangular = AtmosphereProjection::Equirectangular.size(60);
+        assert_eq!(dispatch(equirectangular, 0..1).workgroups(), [15, 2, 1]);
+    }
+
+    /// An app rendering `model` with the first Vulkan, Metal or DX12 adapter found, or `None` without one.
+    #[cfg(all(feature = "gradient", feature = "nishita"))]
+    fn render_app(model: AtmosphereModel) -> Option<App> {
+        use bevy::{
+            log::LogPlugin,
+            render::{
+                pipelined_rendering::PipelinedRenderingPlugin,
+                settings::{Backends, RenderCreation, WgpuSettings},
+                RenderPlugin,
+            },
+            time::TimeUpdateStrategy,
+            window::ExitCondition,
+        };
+        use std::{panic::AssertUnwindSafe, time::Duration};
+
+        let mut plugins = DefaultPlugins
+            .set(WindowPlugin {
+                primary_window: None,
+                exit_condition: ExitCondition::DontExit,
+                ..default()
+            })
+            .set(RenderPlugin {
+                // like WebGL, the GL backend can't run the compute shaders
+                render_creation: RenderCreation::Automatic(WgpuSettings {
+                    backends: Some(Backends::PRIMARY),
+                    ..default()
+                }),
+                synchronous_pipeline_compilation: true,
+                ..default()
+            })
+            .disable::<LogPlugin>();
+        // keeps the render world in the `RenderApp`, so it can be inspected between frames
+        if plugins.contains::<PipelinedRenderingPlugin>() {
+            plugins = plugins.disable::<PipelinedRenderingPlugin>();
+        }
+
+        // without an adapter, `RenderPlugin` panics
+        let mut app = std::panic::catch_unwind(AssertUnwindSafe(|| {
+            let mut app = App::new();
+            app.add_plugins((plugins, crate::plugin::AtmospherePlugin));
+            app
+        }))
+        .ok()?;
+        app.insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_millis(
+            100,
+        )))
+        .insert_resource(model);
+        app.finish();
+        app.cleanup();
+        Some(app)
+    }
+
+    /// Runs `frames` frames of `app`, returning how many resources the render world created.
+    #[cfg(all(feature = "gradient", feature = "nishita"))]
+    fn created_during(app: &mut App, frames: usize) -> usize {
+        let created = |app: &App| {
+            app.sub_app(RenderApp)
+                .world()
+                .resource::<AtmosphereCreated>()
+                .0
+                .load(Ordering::Relaxed)
+        };
+        let before = created(app);
+        for _ in 0..frames {
+            app.update();
+        }
+        created(app) - before
+    }
+
+    #[test]
+    #[cfg(all(feature = "gradient", feature = "nishita"))]
+    fn idle_frames_create_nothing() {
+        use crate::{
+            clouds::AtmosphereClouds,
+            collection::{gradient::Gradient, nishita::Nishita},
+            transition::AtmosphereTransition,
+        };
+        use std::time::Duration;
+
+        let Some(mut app) = render_app(AtmosphereModel::new(Nishita::default())) else {
+            eprintln!("skipped: no adapter");
+            return;
+        };
+
+        // the pipelines compile and the global sky is rendered once
+        let mut frames = 0;
+        while app
+            .sub_app(RenderApp)
+            .world()
+            .resource::<AtmosphereSkies>()
+            .0
+            .get(&AtmosphereSkyId::Global)
+            .is_none_or(|sky| sky.dirty)
+        {
+            app.update();
+            frames += 1;
+            assert!(frames < 100, "the global sky isn't rendered");
+        }
+        assert_eq!(created_during(&mut app, 10), 0);
+
+        // only the uniforms of the model are written
+        app.world_mut()
+            .resource_mut::<AtmosphereModel>()
+            .to_mut::<Nishita>()
+            .unwrap()
+            .sun_position = Vec3::Y;
+        assert_eq!(created_during(&mut app, 10), 0);
+
+        // the clouds drift with the wind, and are rendered every frame
+        app.insert_resource(AtmosphereClouds::default());
+        assert!(created_during(&mut app, 2) > 0);
+        assert_eq!(created_during(&mut app, 10), 0);
+
+        // the fade blends with another factor every frame
+        app.insert_resource(AtmosphereTransition::new(
+            Gradient::default(),
+            Duration::from_secs(100),
+        ));
+        assert!(created_during(&mut app, 2) > 0);
+        assert_eq!(created_during(&mut app, 10), 0);
+    }
+
+    #[test]
+    fn cleared_resources_are_created_again() {
+        let mut cached = AtmosphereCached::default();
+        let mut created = 0;
+        for _ in 0..10 {
+            cached.get_or_create(1, || created += 1);
+        }
+        assert_eq!((created, cached.created()), (1, 1));
+
+        cached.clear();
+        assert!(cached.get().is_none());
+        cached.get_or_create(1, || created += 1);
+        assert_eq!((created, cached.created()), (2, 2));
+    }
+
+    #[test]
+    fn errors_are_sent_as_events() {
+        let mut world = World::new();
+        let errors = AtmosphereErrors::default();
+        world.insert_resource(errors.clone());
+        world.init_resource::<Events<AtmosphereError>>();
+
+        let error = AtmosphereError::UnregisteredModel {
+            sky: AtmosphereSkyId::Global,
+            model: "custom::Model".to_string(),
+        };
+        errors.report(error.clone());
+        world.run_system_cached(send_atmosphere_errors).unwrap();
+        world.run_system_cached(send_atmosphere_errors).unwrap();
+
+        let events = world.resource::<Events<AtmosphereError>>();
+        assert_eq!(
+            events.iter_current_update_events().collect::<Vec<_>>(),
+            [&error]
+        );
+    }
+
+    #[test]
+    fn clamped_resolutions_are_reported() {
+        let errors = AtmosphereErrors::default();
+        let mut settings = AtmosphereSettings {
+            resolution: 4096,
+            ..default()
+        };
+        assert!(!clamp_resolution(&mut settings, 8192, &errors));
+        assert!(clamp_resolution(&mut settings, 2048, &errors));
+        assert_eq!(settings.resolution, 2048);
+        assert_eq!(
+            *errors.0.lock().unwrap(),
+            [AtmosphereError::InvalidResolution(
+                AtmosphereResolutionError {
+                    resolution: 4096,
+                    max_resolution: 2048,
+                }
+            )]
+        );
+    }
+
+    #[test]
+    fn unsupported_formats_fall_back() {
+        let errors = AtmosphereErrors::default();
+        let format = AtmosphereFormat::Rgba32Float;
+        assert_eq!(
+            supported_format(format, WgpuFeatures::FLOAT32_FILTERABLE, &errors),
+            format
+        );
+        assert_eq!(
+            supported_format(format, WgpuFeatures::empty(), &errors),
+            AtmosphereFormat::Rgba16Float
+        );
+        assert_eq!(
+            *errors.0.lock().unwrap(),
+            [AtmosphereError::UnsupportedFormat {
+                format,
+                missing: WgpuFeatures::FLOAT32_FILTERABLE,
+            }]
+        );
+    }
+
+    #[test]
+    fn changed_formats_recreate_the_images() {
+        let mut world = World::new();
+        let settings = AtmosphereSettings::default();
+        let mut images = Assets::<Image>::default();
+        let handle = images.add(AtmosphereImage::new_image(
+            settings.resolution,
+            settings.projection,
+            settings.format,
+            settings.mip_level_count(),
+        ));
+        let mut materials = Assets::<SkyBoxMaterial>::default();
+        let material = materials.add(SkyBoxMaterial {
+            sky_texture: handle.clone(),
+            projection: settings.projection,
+            #[cfg(feature = "dithering")]
+            dithering: settings.dithering,
+        });
+        world.insert_resource(images);
+        world.insert_resource(materials);
+        world.insert_resource(AtmosphereImage {
+            handle: handle.clone(),
+            array_view: None,
+        });
+        world.insert_resource(AtmosphereSkyBoxMaterial(material));
+        world.insert_resource(AtmosphereImageFormat(settings.format));
+        world.insert_resource(AtmosphereErrors::default());
+        world.insert_resource(settings);
+
+        let mut system = IntoSystem::into_system(atmosphere_settings_changed);
+        system.initialize(&mut world);
+        system.run((), &mut world);
+
+        world.resource_mut::<AtmosphereSettings>().format = AtmosphereFormat::Rgba8Unorm;
+        system.run((), &mut world);
+
+        assert_eq!(
+            **world.resource::<AtmosphereImageFormat>(),
+            AtmosphereFormat::Rgba8Unorm
+        );
+        let image = world.resource::<Assets<Image>>().get(&handle).unwrap();
+        assert_eq!(
+            image.texture_descriptor.format,
+            AtmosphereFormat::Rgba8Unorm.texture_format()
+        );
+        assert!(image
+            .texture_descriptor
+            .usage
+            .contains(TextureUsages::STORAGE_BINDING));
+    }
+}
@@ -12,84 +12,165 @@ use crate::{
 };
 
 #[cfg(feature = "detection")]
-use crate::settings::{AtmosphereSettings, SkyboxCreationMode};
+use crate::settings::{AtmosphereProjection, AtmosphereSettings, SkyboxCreationMode};
 #[cfg(feature = "detection")]
 use bevy::{
+    core_pipeline::Skybox,
     pbr::{NotShadowCaster, NotShadowReceiver},
-    render::camera::CameraProjection as _,
+    render::camera::{CameraProjection as _, Exposure},
 };
 
-#[cfg(any(feature = "gradient", feature = "nishita"))]
+#[cfg(any(feature = "gradient", feature = "hillaire", feature = "nishita"))]
 use crate::model::AddAtmosphereModel as _;
 
 /// A `Plugin` that adds the prerequisites for a procedural sky.
+///
+/// Without a `RenderApp` (like under `MinimalPlugins` on a dedicated server), it runs headless:
+/// the models are registered and the systems acting on them (transitions, presets, the [`SolarClock`](crate::solar::SolarClock), ...) still run,
+/// but nothing is rendered and no GPU is needed.
 #[derive(Default, Debug, Clone, Copy)]
 pub struct AtmospherePlugin;
 
 impl Plugin for AtmospherePlugin {
     fn build(&self, app: &mut App) {
-        load_internal_asset!(
-            app,
-            ATMOSPHERE_SKYBOX_SHADER_HANDLE,
-            "shaders/skybox.wgsl",
-            Shader::from_wgsl
-        );
-
-        app.add_plugins(MaterialPlugin::<SkyBoxMaterial>::default());
-
-        #[cfg(feature = "procedural")]
-        app.add_plugins(AtmospherePipelinePlugin);
-
-        {
-            let image_handle = {
-                let image = app.world().get_resource::<AtmosphereImage>().expect("`AtmosphereImage` missing! If the `procedural` feature is disabled, add the resource before `AtmospherePlugin`");
-                image.handle.clone()
-            };
-
-            #[cfg(feature = "dithering")]
-            let settings = {
-                let settings = app
-                    .world()
-                    .get_resource::<crate::settings::AtmosphereSettings>();
-                settings.copied().unwrap_or_default()
-            };
+        if app.get_sub_app(RenderApp).is_some() {
+            build_rendering(app);
+        }
 
-            let mut material_assets = app.world_mut().resource_mut::<Assets<SkyBoxMaterial>>();
-            let material = material_assets.add(SkyBoxMaterial {
-                sky_texture: image_handle,
-                #[cfg(feature = "dithering")]
-                dithering: settings.dithering,
-            });
+        app.add_systems(
+            Update,
+            (
+                atmosphere_cancel_rotation,
+                crate::transition::atmosphere_transition,
+            ),
+        );
 
-            app.insert_resource(AtmosphereSkyBoxMaterial(material));
+        // presets are loaded as assets, which headless apps may not have
+        #[cfg(feature = "presets")]
+        if app.world().contains_resource::<AssetServer>() {
+            app.init_asset::<crate::preset::AtmospherePreset>()
+                .init_asset_loader::<crate::preset::AtmospherePresetLoader>()
+                .add_systems(Update, crate::preset::atmosphere_preset_changed);
         }
 
-        #[cfg(feature = "detection")]
-        {
-            app.add_systems(PostUpdate, (atmosphere_insert, atmosphere_remove));
-        }
+        #[cfg(feature = "nishita")]
+        app.add_systems(
+            Update,
+            // the clock owns the sun, even while the model is transitioning
+            crate::solar::solar_clock.after(crate::transition::atmosphere_transition),
+        );
 
-        app.add_systems(Update, atmosphere_cancel_rotation);
+        #[cfg(feature = "nishita")]
+        app.add_systems(
+            PostUpdate,
+            crate::sun::atmosphere_sun.before(bevy::transform::TransformSystem::TransformPropagate),
+        );
+
+        // the fog follows the sun once it has been moved
+        let fog = crate::fog::atmosphere_fog;
+        #[cfg(feature = "nishita")]
+        let fog = fog.after(crate::sun::atmosphere_sun);
+        app.add_systems(PostUpdate, fog);
     }
 
     fn finish(&self, app: &mut App) {
-        let render_app = app.sub_app_mut(RenderApp);
+        // before the layouts and model pipelines of the format are created
+        #[cfg(feature = "procedural")]
+        if app.get_sub_app(RenderApp).is_some() {
+            crate::pipeline::validate_atmosphere_format(app);
+        }
 
-        render_app.init_resource::<AtmosphereImageBindGroupLayout>();
+        if let Some(render_app) = app.get_sub_app_mut(RenderApp) {
+            render_app
+                .init_resource::<AtmosphereImageBindGroupLayout>()
+                .init_resource::<AtmospherePassBindGroupLayout>()
+                .init_resource::<AtmospherePassSampler>();
+        }
 
         #[cfg(feature = "gradient")]
         app.add_atmosphere_model::<crate::collection::gradient::Gradient>();
 
+        #[cfg(feature = "hillaire")]
+        app.add_atmosphere_model::<crate::collection::hillaire::Hillaire>();
+
         #[cfg(feature = "nishita")]
         app.add_atmosphere_model::<crate::collection::nishita::Nishita>();
     }
 }
 
+/// Adds the shaders, the skybox and the detection of [`AtmosphereCamera`]s, for apps that render.
+fn build_rendering(app: &mut App) {
+    load_internal_asset!(
+        app,
+        ATMOSPHERE_SKYBOX_SHADER_HANDLE,
+        "shaders/skybox.wgsl",
+        Shader::from_wgsl
+    );
+    // the skybox samples panoramic projections with it, even without the compute pipeline
+    load_internal_asset!(
+        app,
+        ATMOSPHERE_PROJECTION_SHADER_HANDLE,
+        "shaders/projection.wgsl",
+        Shader::from_wgsl
+    );
+
+    app.add_plugins(MaterialPlugin::<SkyBoxMaterial>::default());
+
+    #[cfg(feature = "procedural")]
+    app.add_plugins(AtmospherePipelinePlugin);
+
+    #[cfg(feature = "nishita")]
+    app.add_plugins(crate::aerial_perspective::AtmosphereAerialPerspectivePlugin);
+
+    {
+        let image_handle = {
+            let image = app.world().get_resource::<AtmosphereImage>().expect("`AtmosphereImage` missing! If the `procedural` feature is disabled, add the resource before `AtmospherePlugin`");
+            image.handle.clone()
+        };
+
+        let settings = {
+            let settings = app
+                .world()
+                .get_resource::<crate::settings::AtmosphereSettings>();
+            settings.copied().unwrap_or_default()
+        };
+
+        let mut material_assets = app.world_mut().resource_mut::<Assets<SkyBoxMaterial>>();
+        let material = material_assets.add(SkyBoxMaterial {
+            sky_texture: image_handle,
+            projection: settings.projection,
+            #[cfg(feature = "dithering")]
+            dithering: settings.dithering,
+        });
+
+        app.insert_resource(AtmosphereSkyBoxMaterial(material));
+    }
+
+    #[cfg(feature = "detection")]
+    {
+        let detection = (
+            atmosphere_insert,
+            atmosphere_remove,
+            atmosphere_material_changed,
+            atmosphere_background.after(atmosphere_insert),
+        );
+        // cameras with their own model need their material before the skybox is created
+        #[cfg(feature = "procedural")]
+        let detection = detection.after(crate::pipeline::atmosphere_model_added);
+
+        app.add_systems(PostUpdate, detection);
+    }
+}
+
 /// A marker `Component` for a `Camera` that receives a skybox.
 ///
 /// When added, a skybox will be created as a child.
 /// When removed, that skybox will also be removed.
 /// This behaviour can be disabled by turning off the "detection" feature.
+///
+/// Adding an [`AtmosphereModel`](crate::model::AtmosphereModel) component to the camera gives it a sky of its own.
+/// Since every skybox is placed in the scene, use `render_layers` to hide it from other cameras.
+/// With the `SkyboxCreationMode::Background` mode, the sky is drawn by the camera itself (see [`AtmosphereBackground`]) and `render_layers` is unused.
 #[derive(Component, Default, Debug, Clone)]
 pub struct AtmosphereCamera {
     /// Controls whether or not the skybox will be seen only on certain render layers.
@@ -102,25 +183,48 @@ pub struct AtmosphereCamera {
 #[derive(Component, Debug, Clone, Copy)]
 pub struct AtmosphereSkyBox;
 
+/// A marker `Component` for cameras that draw the sky as a background.
+///
+/// Added instead of a skybox when an `AtmosphereCamera` is detected with the `SkyboxCreationMode::Background` mode.
+/// The camera is given a `Skybox` of the sky, which is kept in sync with the camera's sky and `Exposure`.
+#[derive(Component, Debug, Clone, Copy)]
+pub struct AtmosphereBackground;
+
 /// Inserts a skybox when the `AtmosphereCamera` component is added.
 #[cfg(feature = "detection")]
+#[allow(clippy::type_complexity)]
 fn atmosphere_insert(
     mut commands: Commands,
     mut mesh_assets: ResMut<Assets<Mesh>>,
     settings: Option<Res<AtmosphereSettings>>,
     material: Res<AtmosphereSkyBoxMaterial>,
-    atmosphere_cameras: Query<(Entity, &Projection, &AtmosphereCamera), Added<AtmosphereCamera>>,
+    atmosphere_cameras: Query<
+        (
+            Entity,
+            &Projection,
+            &AtmosphereCamera,
+            Option<&AtmosphereSkyBoxMaterial>,
+        ),
+        Added<AtmosphereCamera>,
+    >,
 ) {
     let skybox_creation_mode = match settings {
         Some(settings) => settings.skybox_creation_mode,
         None => SkyboxCreationMode::default(),
     };
 
-    for (camera, projection, atmosphere_camera) in &atmosphere_cameras {
+    for (camera, projection, atmosphere_camera, camera_material) in &atmosphere_cameras {
+        let material = camera_material.unwrap_or(&material);
+
         let far_size = match skybox_creation_mode {
             // TODO: Use `unwrap_or(fallback)` when `projection.far()` becomes an `Option<f32>`
             SkyboxCreationMode::FromProjectionFarWithFallback(_fallback) => projection.far(),
             SkyboxCreationMode::FromSpecifiedFar(specified) => specified,
+            SkyboxCreationMode::Background => {
+                trace!("Adding background to camera entity (ID:{:?})", camera);
+                commands.entity(camera).insert(AtmosphereBackground);
+                continue;
+            }
         };
 
         trace!(
@@ -157,9 +261,18 @@ fn atmosphere_remove(
     mut commands: Commands,
     parents: Query<&Children>,
     atmosphere_skyboxes: Query<Entity, With<AtmosphereSkyBox>>,
+    atmosphere_backgrounds: Query<(), With<AtmosphereBackground>>,
     mut atmosphere_cameras: RemovedComponents<AtmosphereCamera>,
 ) {
     for camera in &mut atmosphere_cameras.read() {
+        if atmosphere_backgrounds.contains(camera) {
+            trace!("Removing background from camera entity (ID:{:?})", camera);
+            commands
+                .entity(camera)
+                .remove::<(AtmosphereBackground, Skybox)>();
+            continue;
+        }
+
         trace!("Removing skybox from camera entity (ID:{:?})", camera);
         let Ok(children) = parents.get(camera) else {
             error!("Failed to get skybox children entities from camera entity.");
@@ -177,6 +290,84 @@ fn atmosphere_remove(
     }
 }
 
+/// Swaps the skybox material when an `AtmosphereCamera` gains or loses a sky of its own.
+#[cfg(feature = "detection")]
+#[allow(clippy::type_complexity)]
+fn atmosphere_material_changed(
+    material: Res<AtmosphereSkyBoxMaterial>,
+    changed_cameras: Query<
+        (&Children, &AtmosphereSkyBoxMaterial),
+        (With<AtmosphereCamera>, Changed<AtmosphereSkyBoxMaterial>),
+    >,
+    cameras: Query<&Children, With<AtmosphereCamera>>,
+    mut removed_materials: RemovedComponents<AtmosphereSkyBoxMaterial>,
+    mut atmosphere_skyboxes: Query<&mut MeshMaterial3d<SkyBoxMaterial>, With<AtmosphereSkyBox>>,
+) {
+    let removed = removed_materials
+        .read()
+        .filter_map(|camera| cameras.get(camera).ok())
+        .map(|children| (children, &*material));
+
+    for (children, material) in changed_cameras.iter().chain(removed) {
+        let mut skyboxes = atmosphere_skyboxes.iter_many_mut(children);
+        while let Some(mut skybox_material) = skyboxes.fetch_next() {
+            skybox_material.0 = material.0.clone();
+        }
+    }
+}
+
+/// Keeps the `Skybox` of background cameras in sync with their sky.
+///
+/// The `Skybox` is only kept while the sky is a cubemap, and its brightness cancels the camera's `Exposure`,
+/// so the sky looks the same as on a skybox mesh.
+#[cfg(feature = "detection")]
+#[allow(clippy::type_complexity)]
+fn atmosphere_background(
+    mut commands: Commands,
+    image: Res<AtmosphereImage>,
+    settings: Option<Res<AtmosphereSettings>>,
+    mut atmosphere_cameras: Query<
+        (
+            Entity,
+            Option<&mut Skybox>,
+            Option<&AtmosphereImage>,
+            Option<&Exposure>,
+        ),
+        With<AtmosphereBackground>,
+    >,
+) {
+    let projection = settings
+        .map(|settings| settings.projection)
+        .unwrap_or_default();
+
+    for (camera, skybox, camera_image, exposure) in &mut atmosphere_cameras {
+        if projection != AtmosphereProjection::Cubemap {
+            if skybox.is_some() {
+                warn_once!("Background cameras need the `AtmosphereProjection::Cubemap` projection, their sky is hidden");
+                commands.entity(camera).remove::<Skybox>();
+            }
+            continue;
+        }
+
+        let background = Skybox {
+            image: camera_image.unwrap_or(&image).handle.clone(),
+            brightness: exposure.copied().unwrap_or_default().exposure().recip(),
+            rotation: Quat::IDENTITY,
+        };
+
+        match skybox {
+            Some(mut skybox) => {
+                if skybox.image != background.image || skybox.brightness != background.brightness {
+                    *skybox = background;
+                }
+            }
+            None => {
+                commands.entity(camera).insert(background);
+            }
+        }
+    }
+}
+
 /// Cancels the rotation of the camera.
 fn atmosphere_cancel_rotation(
     mut atmosphere_sky_boxes: Query<(&mut Transform, &ChildOf), With<AtmosphereSkyBox>>,
@@ -191,3 +382,53 @@ fn atmosphere_cancel_rotation(
         }
     }
 }
+
+#[cfg(all(test, feature = "detection"))]
+mod tests {
+    use super::*;
+
+    fn app(projection: AtmosphereProjection) -> App {
+        let mut app = App::new();
+        app.insert_resource(AtmosphereImage {
+            handle: Handle::weak_from_u128(1),
+            array_view: None,
+        })
+        .insert_resource(AtmosphereSettings {
+            projection,
+            ..default()
+        })
+        .add_systems(Update, atmosphere_background);
+        app
+    }
+
+    #[test]
+    fn background_follows_sky_and_exposure() {
+        let mut app = app(AtmosphereProjection::Cubemap);
+        let global = app.world().resource::<AtmosphereImage>().handle.clone();
+        let camera = app
+            .world_mut()
+            .spawn((AtmosphereBackground, Exposure { ev100: 0.0 }))
+            .id();
+        app.update();
+
+        let skybox = app.world().get::<Skybox>(camera).unwrap();
+        assert_eq!(skybox.image, global);
+        assert!((skybox.brightness - 1.2).abs() < 1e-6);
+
+        let own = AtmosphereImage {
+            handle: Handle::weak_from_u128(2),
+            array_view: None,
+        };
+        app.world_mut().entity_mut(camera).insert(own.clone());
+        app.update();
+        assert_eq!(app.world().get::<Skybox>(camera).unwrap().image, own.handle);
+    }
+
+    #[test]
+    fn background_is_hidden_without_cubemap() {
+        let mut app = app(AtmosphereProjection::Equirectangular);
+        let camera = app.world_mut().spawn(AtmosphereBackground).id();
+        app.update();
+        assert!(app.world().get::<Skybox>(camera).is_none());
+    }
+}
@@ -0,0 +1,318 @@
+//! Provides [`AtmospherePreset`], an asset for storing atmospheric models in `.atmosphere.ron` files.
+//!
+//! A preset names the type path of a registered [`Atmospheric`](crate::model::Atmospheric) model, followed by its fields:
+//! ```ron
+//! {
+//!     "bevy_atmosphere::collection::gradient::Gradient": (
+//!         ground: (red: 0.0, green: 0.0, blue: 0.0, alpha: 1.0),
+//!         horizon: (red: 0.1, green: 0.3, blue: 0.7, alpha: 1.0),
+//!         sky: (red: 0.0, green: 0.0, blue: 0.5, alpha: 1.0),
+//!     ),
+//! }
+//! ```
+//!
+//! Fields that are left out keep their default value, if the model reflects `Default` (like every model in the [`collection`](crate::collection)).
+//!
+//! To drive a sky from a preset, insert an [`AtmospherePresetHandle`] as a `Resource` (for the global sky)
+//! or as a `Component` (for an entity's sky). Whenever the preset is loaded or changes, the [`AtmosphereModel`] is replaced.
+//! With Bevy's `file_watcher` feature, editing the file updates the sky while the app is running.
+//! ```no_run
+//! # use bevy::prelude::*;
+//! # use bevy_atmosphere::prelude::*;
+//! fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
+//!     commands.insert_resource(AtmospherePresetHandle(
+//!         asset_server.load("presets/nishita.atmosphere.ron"),
+//!     ));
+//! }
+//! ```
+
+use std::fmt;
+
+use bevy::{
+    asset::{io::Reader, AssetLoader, LoadContext},
+    platform::collections::HashSet,
+    prelude::*,
+    reflect::{serde::ReflectDeserializer, TypeRegistry, TypeRegistryArc},
+};
+use serde::de::DeserializeSeed;
+
+use crate::model::{AtmosphereModel, ReflectAtmosphereModel};
+
+/// An [`AtmosphereModel`] loaded from a `.atmosphere.ron` file.
+#[derive(Asset, TypePath, Clone)]
+pub struct AtmospherePreset {
+    /// The model described by the file.
+    pub model: AtmosphereModel,
+}
+
+/// Binds an [`AtmospherePreset`] to an [`AtmosphereModel`].
+///
+/// As a `Resource`, it replaces the global [`AtmosphereModel`].
+/// As a `Component`, it replaces the [`AtmosphereModel`] on the same entity.
+#[derive(Resource, Component, Default, Debug, Clone)]
+pub struct AtmospherePresetHandle(pub Handle<AtmospherePreset>);
+
+/// An error that occurs while loading an [`AtmospherePreset`].
+#[derive(Debug)]
+pub enum AtmospherePresetLoaderError {
+    /// The file couldn't be read.
+    Io(std::io::Error),
+    /// The file isn't valid RON.
+    Ron(ron::error::SpannedError),
+    /// The file doesn't describe a registered type.
+    Deserialize(ron::Error),
+    /// The type isn't a registered atmospheric model.
+    NotAModel(String),
+    /// The fields don't make up the model, because some are missing or have the wrong type.
+    InvalidModel(String),
+}
+
+impl fmt::Display for AtmospherePresetLoaderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(error) => write!(f, "could not read preset: {error}"),
+            Self::Ron(error) => write!(f, "could not parse preset: {error}"),
+            Self::Deserialize(error) => write!(f, "could not deserialize preset: {error}"),
+            Self::NotAModel(type_path) => write!(
+                f,
+                "`{type_path}` is not an atmospheric model registered with `add_atmosphere_model`"
+            ),
+            Self::InvalidModel(type_path) => {
+                write!(f, "fields of preset don't make up a `{type_path}`")
+            }
+        }
+    }
+}
+
+impl std::error::Error for AtmospherePresetLoaderError {}
+
+impl From<std::io::Error> for AtmospherePresetLoaderError {
+    fn from(error: std::io::Error) -> Self {
+        Self::Io(error)
+    }
+}
+
+impl From<ron::error::SpannedError> for AtmospherePresetLoaderError {
+    fn from(error: ron::error::SpannedError) -> Self {
+        Self::Ron(error)
+    }
+}
+
+impl From<ron::Error> for AtmospherePresetLoaderError {
+    fn from(error: ron::Error) -> Self {
+        Self::Deserialize(error)
+    }
+}
+
+/// Deserializes an [`AtmosphereModel`] from the contents of a `.atmosphere.ron` file.
+pub fn deserialize_model(
+    bytes: &[u8],
+    type_registry: &TypeRegistry,
+) -> Result<AtmosphereModel, AtmospherePresetLoaderError> {
+    let mut deserializer = ron::de::Deserializer::from_bytes(bytes)?;
+    let reflect = ReflectDeserializer::new(type_registry).deserialize(&mut deserializer)?;
+
+    let Some(type_info) = reflect.get_represented_type_info() else {
+        return Err(AtmospherePresetLoaderError::NotAModel(
+            "unknown".to_string(),
+        ));
+    };
+    let type_path = type_info.type_path();
+    let reflect_model = type_registry
+        .get_type_data::<ReflectAtmosphereModel>(type_info.type_id())
+        .ok_or_else(|| AtmospherePresetLoaderError::NotAModel(type_path.to_string()))?;
+
+    reflect_model
+        .from_reflect(&*reflect)
+        .ok_or_else(|| AtmospherePresetLoaderError::InvalidModel(type_path.to_string()))
+}
+
+/// The `AssetLoader` for [`AtmospherePreset`]s, which deserializes models with the `AppTypeRegistry`.
+pub struct AtmospherePresetLoader {
+    type_registry: TypeRegistryArc,
+}
+
+impl FromWorld for AtmospherePresetLoader {
+    fn from_world(world: &mut World) -> Self {
+        Self {
+            type_registry: world.resource::<AppTypeRegistry>().0.clone(),
+        }
+    }
+}
+
+impl AssetLoader for AtmospherePresetLoader {
+    type Asset = AtmospherePreset;
+    type Settings = ();
+    type Error = AtmospherePresetLoaderError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &Self::Settings,
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+
+        let model = deserialize_model(&bytes, &self.type_registry.read())?;
+        Ok(AtmospherePreset { model })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["atmosphere.ron"]
+    }
+}
+
+/// Replaces the [`AtmosphereModel`] of every [`AtmospherePresetHandle`] whose preset was loaded, changed or swapped.
+pub(crate) fn atmosphere_preset_changed(
+    mut commands: Commands,
+    mut preset_events: EventReader<AssetEvent<AtmospherePreset>>,
+    presets: Res<Assets<AtmospherePreset>>,
+    global_handle: Option<Res<AtmospherePresetHandle>>,
+    entity_handles: Query<(Entity, Ref<AtmospherePresetHandle>)>,
+) {
+    let changed = preset_events
+        .read()
+        .filter_map(|event| match event {
+            AssetEvent::LoadedWithDependencies { id } | AssetEvent::Modified { id } => Some(*id),
+            _ => None,
+        })
+        .collect::<HashSet<_>>();
+
+    let needs_update = |handle: &AtmospherePresetHandle, is_changed: bool| {
+        is_changed || changed.contains(&handle.0.id())
+    };
+
+    if let Some(handle) = global_handle {
+        if let Some(preset) = presets
+            .get(&handle.0)
+            .filter(|_| needs_update(&handle, handle.is_changed()))
+        {
+            trace!("Applying atmosphere preset {:?}", handle.0.path());
+            commands.insert_resource(preset.model.clone());
+        }
+    }
+
+    for (entity, handle) in &entity_handles {
+        if !needs_update(&handle, handle.is_changed()) {
+            continue;
+        }
+        if let Some(preset) = presets.get(&handle.0) {
+            trace!(
+                "Applying atmosphere preset {:?} to entity (ID:{:?})",
+                handle.0.path(),
+                entity
+            );
+            commands.entity(entity).insert(preset.model.clone());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::reflect::FromType;
+
+    fn type_registry() -> TypeRegistry {
+        let mut type_registry = TypeRegistry::default();
+
+        #[cfg(feature = "gradient")]
+        {
+            use crate::collection::gradient::Gradient;
+            type_registry.register::<Gradient>();
+            type_registry.register_type_data::<Gradient, ReflectAtmosphereModel>();
+        }
+
+        #[cfg(feature = "nishita")]
+        {
+            use crate::collection::nishita::Nishita;
+            type_registry.register::<Nishita>();
+            type_registry.register_type_data::<Nishita, ReflectAtmosphereModel>();
+        }
+
+        type_registry
+    }
+
+    #[test]
+    #[cfg(feature = "nishita")]
+    fn nishita_preset_is_the_default() {
+        use crate::collection::nishita::Nishita;
+
+        let bytes = include_bytes!("../assets/presets/nishita.atmosphere.ron");
+        let model = deserialize_model(bytes, &type_registry()).unwrap();
+        let nishita = model.to_ref::<Nishita>().unwrap();
+        let default = Nishita::default();
+        assert_eq!(nishita.sun_position, default.sun_position);
+        assert_eq!(nishita.rayleigh_coefficient, default.rayleigh_coefficient);
+        assert_eq!(nishita.sun_limb_darkening, default.sun_limb_darkening);
+        assert_eq!(nishita.star_density, default.star_density);
+    }
+
+    #[test]
+    #[cfg(feature = "gradient")]
+    fn gradient_preset_is_the_default() {
+        use crate::collection::gradient::Gradient;
+
+        let bytes = include_bytes!("../assets/presets/gradient.atmosphere.ron");
+        let model = deserialize_model(bytes, &type_registry()).unwrap();
+        let gradient = model.to_ref::<Gradient>().unwrap();
+        let default = Gradient::default();
+        assert_eq!(gradient.ground, default.ground);
+        assert_eq!(gradient.horizon, default.horizon);
+        assert_eq!(gradient.sky, default.sky);
+    }
+
+    #[test]
+    fn rejects_types_that_are_not_models() {
+        let mut type_registry = type_registry();
+        type_registry.register::<f32>();
+
+        let result = deserialize_model(br#"{ "f32": 1.0 }"#, &type_registry);
+        assert!(
+            matches!(result, Err(AtmospherePresetLoaderError::NotAModel(path)) if path == "f32")
+        );
+
+        let result = deserialize_model(br#"{ "not::a::Type": () }"#, &type_registry);
+        assert!(matches!(
+            result,
+            Err(AtmospherePresetLoaderError::Deserialize(_))
+        ));
+    }
+
+    #[test]
+    #[cfg(feature = "gradient")]
+    fn missing_fields_keep_their_default() {
+        use crate::collection::gradient::Gradient;
+
+        let bytes = br#"{
+            "bevy_atmosphere::collection::gradient::Gradient": (
+                sky: (red: 1.0, green: 0.0, blue: 0.0, alpha: 1.0),
+            ),
+        }"#;
+        let model = deserialize_model(bytes, &type_registry()).unwrap();
+        let gradient = model.to_ref::<Gradient>().unwrap();
+        assert_eq!(gradient.sky, LinearRgba::RED);
+        assert_eq!(gradient.ground, Gradient::default().ground);
+
+        let bytes = br#"{
+            "bevy_atmosphere::collection::gradient::Gradient": (
+                sky: 1.0,
+            ),
+        }"#;
+        assert!(deserialize_model(bytes, &type_registry()).is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "gradient")]
+    fn type_data_creates_models() {
+        use crate::collection::gradient::Gradient;
+
+        let reflect_model = <ReflectAtmosphereModel as FromType<Gradient>>::from_type();
+        let gradient = Gradient {
+            sky: LinearRgba::RED,
+            ..default()
+        };
+        let model = reflect_model.from_reflect(&gradient).unwrap();
+        assert_eq!(model.to_ref::<Gradient>().unwrap().sky, LinearRgba::RED);
+    }
+}
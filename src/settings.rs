@@ -1,6 +1,133 @@
 //! Provides [`AtmosphereSettings`] resource, a type that controls how the sky is rendered.
 
-use bevy::{prelude::Resource, render::extract_resource::ExtractResource};
+use std::fmt;
+
+use bevy::{
+    prelude::Resource,
+    render::{
+        extract_resource::ExtractResource,
+        render_resource::{
+            Extent3d, ShaderDefVal, TextureFormat, TextureViewDimension, WgpuFeatures,
+        },
+    },
+};
+
+/// The layout of the sky in an [`AtmosphereImage`](crate::pipeline::AtmosphereImage).
+///
+/// Every projection is rendered by the same model pipelines, which tell them apart by the size of the texture.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AtmosphereProjection {
+    /// Six faces of `resolution` by `resolution` texels, one per layer, viewed as a cube.
+    ///
+    /// This is the only projection that can be used by an [`AtmosphereEnvironmentMapPlugin`](crate::environment_map::AtmosphereEnvironmentMapPlugin).
+    #[default]
+    Cubemap,
+    /// A single `2 * resolution` by `resolution` latitude-longitude image.
+    ///
+    /// The center of the image looks along -Z, and the top row straight up.
+    Equirectangular,
+    /// A single `resolution` by `resolution` image of the sky folded onto an octahedron.
+    ///
+    /// The upper hemisphere is the diamond in the middle of the image, with the zenith at its center.
+    Octahedral,
+}
+
+impl AtmosphereProjection {
+    /// The size of a texture of this projection, for a given [`AtmosphereSettings::resolution`].
+    pub const fn size(self, resolution: u32) -> Extent3d {
+        match self {
+            Self::Cubemap => Extent3d {
+                width: resolution,
+                height: resolution,
+                depth_or_array_layers: 6,
+            },
+            Self::Equirectangular => Extent3d {
+                width: resolution * 2,
+                height: resolution,
+                depth_or_array_layers: 1,
+            },
+            Self::Octahedral => Extent3d {
+                width: resolution,
+                height: resolution,
+                depth_or_array_layers: 1,
+            },
+        }
+    }
+
+    /// The dimension of the `TextureView` a material samples the sky with.
+    pub const fn view_dimension(self) -> TextureViewDimension {
+        match self {
+            Self::Cubemap => TextureViewDimension::Cube,
+            Self::Equirectangular | Self::Octahedral => TextureViewDimension::D2,
+        }
+    }
+}
+
+/// The texel format of an [`AtmosphereImage`](crate::pipeline::AtmosphereImage) (see [`AtmosphereSettings::format`]).
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AtmosphereFormat {
+    /// Half precision floats, enough for a sky of any brightness.
+    #[default]
+    Rgba16Float,
+    /// Single precision floats, for the skies sampled by other shaders that need the precision.
+    ///
+    /// Sampling it with a filtering sampler, as the skybox does, needs the `FLOAT32_FILTERABLE` feature.
+    /// Without it, `Rgba16Float` is used instead.
+    Rgba32Float,
+    /// Packed floats without alpha, at half the memory of `Rgba16Float`.
+    ///
+    /// It can't be written by compute shaders, so the sky is rendered as `Rgba16Float` and converted when copied to the image,
+    /// which needs the `RG11B10UFLOAT_RENDERABLE` feature.
+    /// Without it, `Rgba16Float` is used instead.
+    Rg11b10Ufloat,
+    /// 8 bits per channel, for low memory.
+    ///
+    /// The sky is clamped to `[0, 1]`, so bright suns and HDR skies are lost.
+    Rgba8Unorm,
+}
+
+impl AtmosphereFormat {
+    /// The `WgpuFeatures` the device needs to render and sample the images.
+    pub const fn required_features(self) -> WgpuFeatures {
+        match self {
+            Self::Rgba32Float => WgpuFeatures::FLOAT32_FILTERABLE,
+            Self::Rg11b10Ufloat => WgpuFeatures::RG11B10UFLOAT_RENDERABLE,
+            Self::Rgba16Float | Self::Rgba8Unorm => WgpuFeatures::empty(),
+        }
+    }
+
+    /// The `TextureFormat` of the images.
+    pub const fn texture_format(self) -> TextureFormat {
+        match self {
+            Self::Rgba16Float => TextureFormat::Rgba16Float,
+            Self::Rgba32Float => TextureFormat::Rgba32Float,
+            Self::Rg11b10Ufloat => TextureFormat::Rg11b10Ufloat,
+            Self::Rgba8Unorm => TextureFormat::Rgba8Unorm,
+        }
+    }
+
+    /// The `TextureFormat` the compute shaders write the sky in.
+    pub const fn storage_format(self) -> TextureFormat {
+        match self {
+            Self::Rg11b10Ufloat => TextureFormat::Rgba16Float,
+            _ => self.texture_format(),
+        }
+    }
+
+    /// Whether the sky is written to the images directly, rather than converted from the storage format.
+    pub const fn is_storage(self) -> bool {
+        !matches!(self, Self::Rg11b10Ufloat)
+    }
+
+    /// The shader defs that pick the storage format of `bevy_atmosphere::image::image` in model shaders.
+    pub fn shader_defs(self) -> Vec<ShaderDefVal> {
+        match self.storage_format() {
+            TextureFormat::Rgba32Float => vec!["ATMOSPHERE_FORMAT_RGBA32FLOAT".into()],
+            TextureFormat::Rgba8Unorm => vec!["ATMOSPHERE_FORMAT_RGBA8UNORM".into()],
+            _ => vec![],
+        }
+    }
+}
 
 /// Available methods for determining the size of the auto created skybox
 #[cfg(feature = "detection")]
@@ -10,6 +137,13 @@ pub enum SkyboxCreationMode {
     FromProjectionFarWithFallback(f32),
     /// Ignores any camera projection `far` value and always uses the specified value.
     FromSpecifiedFar(f32),
+    /// Doesn't create a skybox, the sky is drawn behind everything else with Bevy's `Skybox` instead.
+    ///
+    /// The sky is at infinite depth and follows the camera within the frame, so it works with infinite
+    /// reverse-Z projections, the depth prepass and TAA.
+    /// It needs the [`AtmosphereProjection::Cubemap`] projection (cameras fall back to no sky with the others)
+    /// and it isn't dithered.
+    Background,
 }
 
 #[cfg(feature = "detection")]
@@ -19,13 +153,52 @@ impl Default for SkyboxCreationMode {
     }
 }
 
+/// How a sky that changed is rendered (see [`AtmosphereSettings::update_mode`]).
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AtmosphereUpdateMode {
+    /// The whole sky is rendered during the frame it changed.
+    #[default]
+    Immediate,
+    /// The sky is rendered in [`ATMOSPHERE_SLICE_COUNT`](crate::pipeline::ATMOSPHERE_SLICE_COUNT) slices,
+    /// `slices_per_frame` at a time, into a back buffer that is copied to the
+    /// [`AtmosphereImage`](crate::pipeline::AtmosphereImage) once every slice is rendered.
+    ///
+    /// A slice is a face of a cubemap, or a horizontal band of a panoramic [`AtmosphereProjection`].
+    /// Changes made while the sky is being rendered are picked up by the next update,
+    /// so a sky animated every frame costs a bounded amount of GPU time per frame, and is never shown half updated.
+    TimeSliced {
+        /// How many slices are rendered per frame, at least 1.
+        slices_per_frame: u32,
+    },
+}
+
 /// Provides settings for how the sky is rendered.
 #[derive(Resource, ExtractResource, Debug, Clone, Copy)]
 pub struct AtmosphereSettings {
-    /// Resolution of a face of a skybox (Default: `512`).
+    /// Resolution of a face of a skybox, or the height of a panoramic sky (Default: `512`).
     ///
-    /// It should be a multiple of 8, any different and there may be issues.
+    /// It can be any size from 1 up to the largest texture the device supports (see [`AtmosphereSettings::validate_resolution`]),
+    /// anything outside of that is clamped and reported as an [`AtmosphereError::InvalidResolution`](crate::pipeline::AtmosphereError::InvalidResolution).
     pub resolution: u32,
+    /// Layout of the sky in the [`AtmosphereImage`](crate::pipeline::AtmosphereImage) (Default: `AtmosphereProjection::Cubemap`).
+    ///
+    /// See [`AtmosphereProjection`]
+    pub projection: AtmosphereProjection,
+    /// Whether a sky is rendered at once or over several frames (Default: `AtmosphereUpdateMode::Immediate`).
+    ///
+    /// See [`AtmosphereUpdateMode`]
+    pub update_mode: AtmosphereUpdateMode,
+    /// Texel format of the [`AtmosphereImage`](crate::pipeline::AtmosphereImage) (Default: `AtmosphereFormat::Rgba16Float`).
+    ///
+    /// Changing it creates the images again in the new format, and specializes the model pipelines for it.
+    /// A format the device lacks the features of is replaced with `AtmosphereFormat::Rgba16Float`.
+    ///
+    /// See [`AtmosphereFormat`]
+    pub format: AtmosphereFormat,
+    /// Controls whether a mip chain is generated every time the sky is rendered (Default: `false`).
+    ///
+    /// Mips keep distant reflections and low resolution sampling of the sky from aliasing.
+    pub mipmaps: bool,
     /// Controls whether or not dithering is applied (Default: `true`).
     ///
     /// Dithering will prevent noticeable color banding in some models.
@@ -42,10 +215,88 @@ pub struct AtmosphereSettings {
     pub skybox_creation_mode: SkyboxCreationMode,
 }
 
+impl AtmosphereSettings {
+    /// The number of mips of an [`AtmosphereImage`](crate::pipeline::AtmosphereImage),
+    /// down to a single texel high if [`mipmaps`](Self::mipmaps) is set.
+    pub const fn mip_level_count(&self) -> u32 {
+        if self.mipmaps && self.resolution > 0 {
+            u32::BITS - self.resolution.leading_zeros()
+        } else {
+            1
+        }
+    }
+
+    /// The largest resolution of the [`projection`](Self::projection) whose textures are at most `max_texture_dimension_2d` texels wide,
+    /// the limit of the same name of the `RenderDevice`.
+    pub const fn max_resolution(&self, max_texture_dimension_2d: u32) -> u32 {
+        match self.projection {
+            AtmosphereProjection::Equirectangular => max_texture_dimension_2d / 2,
+            AtmosphereProjection::Cubemap | AtmosphereProjection::Octahedral => {
+                max_texture_dimension_2d
+            }
+        }
+    }
+
+    /// Checks that textures of the [`resolution`](Self::resolution) can be created by a device
+    /// with a `max_texture_dimension_2d` limit.
+    pub const fn validate_resolution(
+        &self,
+        max_texture_dimension_2d: u32,
+    ) -> Result<(), AtmosphereResolutionError> {
+        let max_resolution = self.max_resolution(max_texture_dimension_2d);
+        if self.resolution == 0 || self.resolution > max_resolution {
+            Err(AtmosphereResolutionError {
+                resolution: self.resolution,
+                max_resolution,
+            })
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// An error for an [`AtmosphereSettings::resolution`] that textures can't be created with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AtmosphereResolutionError {
+    /// The resolution of the settings.
+    pub resolution: u32,
+    /// The largest resolution the device supports for the projection of the settings.
+    pub max_resolution: u32,
+}
+
+impl AtmosphereResolutionError {
+    /// The closest resolution that is supported.
+    pub const fn clamped(&self) -> u32 {
+        if self.resolution == 0 {
+            1
+        } else if self.resolution > self.max_resolution {
+            self.max_resolution
+        } else {
+            self.resolution
+        }
+    }
+}
+
+impl fmt::Display for AtmosphereResolutionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "resolution {} is not between 1 and {}, the largest the device supports for the projection",
+            self.resolution, self.max_resolution
+        )
+    }
+}
+
+impl std::error::Error for AtmosphereResolutionError {}
+
 impl Default for AtmosphereSettings {
     fn default() -> Self {
         Self {
             resolution: 512,
+            projection: AtmosphereProjection::Cubemap,
+            update_mode: AtmosphereUpdateMode::Immediate,
+            format: AtmosphereFormat::Rgba16Float,
+            mipmaps: false,
             #[cfg(feature = "dithering")]
             dithering: true,
             #[cfg(feature = "detection")]
@@ -53,3 +304,42 @@ impl Default for AtmosphereSettings {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolution_is_clamped_to_the_device() {
+        let settings = |resolution, projection| AtmosphereSettings {
+            resolution,
+            projection,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            settings(500, AtmosphereProjection::Cubemap).validate_resolution(2048),
+            Ok(())
+        );
+
+        let error = settings(1500, AtmosphereProjection::Equirectangular)
+            .validate_resolution(2048)
+            .unwrap_err();
+        assert_eq!(error.max_resolution, 1024);
+        assert_eq!(error.clamped(), 1024);
+
+        let error = settings(0, AtmosphereProjection::Octahedral)
+            .validate_resolution(2048)
+            .unwrap_err();
+        assert_eq!(error.clamped(), 1);
+    }
+
+    #[test]
+    fn fallback_format_needs_no_features() {
+        assert!(AtmosphereFormat::Rgba16Float.required_features().is_empty());
+        assert_eq!(
+            AtmosphereFormat::Rgba32Float.required_features(),
+            WgpuFeatures::FLOAT32_FILTERABLE
+        );
+    }
+}
@@ -0,0 +1,201 @@
+#import bevy_render::view::View
+#import bevy_core_pipeline::fullscreen_vertex_shader::FullscreenVertexOutput
+
+struct AerialPerspective {
+    ray_origin: vec3<f32>,
+    sun_position: vec3<f32>,
+    sun_intensity: f32,
+    planet_radius: f32,
+    atmosphere_radius: f32,
+    rayleigh_coefficient: vec3<f32>,
+    rayleigh_scale_height: f32,
+    mie_coefficient: f32,
+    mie_scale_height: f32,
+    mie_direction: f32,
+    ozone_coefficient: vec3<f32>,
+    ozone_center_height: f32,
+    ozone_width: f32,
+    max_distance: f32,
+    scale: f32,
+}
+
+struct AerialPerspectiveOutput {
+    @location(0) inscatter: vec4<f32>,
+#ifdef DUAL_SOURCE_BLENDING
+    @location(0) @second_blend_source transmittance: vec4<f32>,
+#endif
+}
+
+const PI: f32 = 3.141592653589793;
+// Samples of the view ray per slice of the volume.
+const STEPS: u32 = 4u;
+// Samples of the ray toward the sun.
+const LIGHT_STEPS: u32 = 8u;
+
+@group(0) @binding(0)
+var<uniform> view: View;
+@group(0) @binding(1)
+var<uniform> aerial: AerialPerspective;
+
+// The volume, written by `volume`.
+@group(1) @binding(0)
+var inscatter_out: texture_storage_3d<rgba16float, write>;
+@group(1) @binding(1)
+var transmittance_out: texture_storage_3d<rgba16float, write>;
+
+// The volume, read by `apply`.
+@group(1) @binding(2)
+var inscatter_volume: texture_3d<f32>;
+@group(1) @binding(3)
+var transmittance_volume: texture_3d<f32>;
+@group(1) @binding(4)
+var volume_sampler: sampler;
+#ifdef MULTISAMPLED
+@group(1) @binding(5)
+var depth_texture: texture_depth_multisampled_2d;
+#else
+@group(1) @binding(5)
+var depth_texture: texture_depth_2d;
+#endif
+
+fn rsi(rd: vec3<f32>, r0: vec3<f32>, sr: f32) -> vec2<f32> {
+    // ray-sphere intersection that assumes
+    // the sphere is centered at the origin.
+    // No intersection when result.x > result.y
+    let a = dot(rd, rd);
+    let b = 2.0 * dot(rd, r0);
+    let c = dot(r0, r0) - (sr * sr);
+    let d = (b * b) - (4.0 * a * c);
+
+    if d < 0.0 {
+        return vec2<f32>(1e5, -1e5);
+    } else {
+        return vec2<f32>(
+            (-b - sqrt(d)) / (2.0 * a),
+            (-b + sqrt(d)) / (2.0 * a)
+        );
+    }
+}
+
+// Density of ozone, a tent profile around its center height.
+fn ozone_density(height: f32) -> f32 {
+    return max(0f, 1f - abs(height - aerial.ozone_center_height) / max(0.5 * aerial.ozone_width, 1e-3));
+}
+
+// The Rayleigh, Mie and ozone optical depths from `position` to the edge of the atmosphere toward the sun.
+fn sun_optical_depth(position: vec3<f32>, sun: vec3<f32>) -> vec3<f32> {
+    let step_size = max(rsi(sun, position, aerial.atmosphere_radius).y, 0f) / f32(LIGHT_STEPS);
+
+    var od = vec3<f32>(0f);
+    for (var j = 0u; j < LIGHT_STEPS; j++) {
+        let height = length(position + sun * (step_size * (f32(j) + 0.5))) - aerial.planet_radius;
+        od += vec3<f32>(
+            exp(-height / aerial.rayleigh_scale_height),
+            exp(-height / aerial.mie_scale_height),
+            ozone_density(height),
+        ) * step_size;
+    }
+    return od;
+}
+
+fn extinction(od: vec3<f32>) -> vec3<f32> {
+    return aerial.rayleigh_coefficient * od.x + aerial.mie_coefficient * od.y + aerial.ozone_coefficient * od.z;
+}
+
+fn uv_to_ndc(uv: vec2<f32>) -> vec2<f32> {
+    return uv * vec2<f32>(2.0, -2.0) + vec2<f32>(-1.0, 1.0);
+}
+
+// The direction of the view ray through `uv`, in world space.
+fn ray_direction(uv: vec2<f32>) -> vec3<f32> {
+    // the near plane, since the far plane of an infinite reverse-Z projection is at infinity
+    let view_position = view.view_from_clip * vec4<f32>(uv_to_ndc(uv), 1.0, 1.0);
+    let direction = view.world_from_view * vec4<f32>(view_position.xyz / view_position.w, 0.0);
+    return normalize(direction.xyz);
+}
+
+// Marches the view ray through every texel of the volume, in slices of equal length up to `max_distance`.
+//
+// A texel holds the light scattered toward the camera and the transmittance up to the far end of its slice.
+@compute @workgroup_size(8, 8, 1)
+fn volume(@builtin(global_invocation_id) invocation_id: vec3<u32>) {
+    let size = textureDimensions(inscatter_out);
+    if any(invocation_id.xy >= size.xy) {
+        return;
+    }
+
+    let r = ray_direction((vec2<f32>(invocation_id.xy) + 0.5) / vec2<f32>(size.xy));
+    // the camera is above the ray origin of the sky by its height
+    let r0 = aerial.ray_origin + vec3<f32>(0.0, view.world_position.y * aerial.scale, 0.0);
+    let p_sun = normalize(aerial.sun_position);
+
+    // Calculate the Rayleigh and Mie phases, like the sky.
+    let mu = dot(r, p_sun);
+    let mumu = mu * mu;
+    let g = aerial.mie_direction;
+    let gg = g * g;
+    let p_rlh = 3.0 / (16.0 * PI) * (1.0 + mumu);
+    let p_mie = 3.0 / (8.0 * PI) * ((1.0 - gg) * (mumu + 1.0)) / (pow(1.0 + gg - 2.0 * mu * g, 1.5) * (2.0 + gg));
+
+    let step_size = aerial.max_distance / f32(size.z * STEPS);
+    var od = vec3<f32>(0f);
+    var total_rlh = vec3<f32>(0f);
+    var total_mie = vec3<f32>(0f);
+    var depth = 0f;
+
+    for (var slice = 0u; slice < size.z; slice++) {
+        for (var i = 0u; i < STEPS; i++) {
+            let position = r0 + r * (depth + step_size * 0.5);
+            let height = length(position) - aerial.planet_radius;
+
+            let od_step = vec3<f32>(
+                exp(-height / aerial.rayleigh_scale_height),
+                exp(-height / aerial.mie_scale_height),
+                ozone_density(height),
+            ) * step_size;
+            od += od_step;
+
+            // the sunlight that reaches the sample, and then the camera
+            let attn = exp(-extinction(od + sun_optical_depth(position, p_sun)));
+            total_rlh += od_step.x * attn;
+            total_mie += od_step.y * attn;
+
+            depth += step_size;
+        }
+
+        let inscatter = aerial.sun_intensity * (p_rlh * aerial.rayleigh_coefficient * total_rlh + p_mie * aerial.mie_coefficient * total_mie);
+        let transmittance = exp(-extinction(od));
+        let texel = vec3<u32>(invocation_id.xy, slice);
+        textureStore(inscatter_out, texel, vec4<f32>(inscatter, 1.0));
+        textureStore(transmittance_out, texel, vec4<f32>(transmittance, (transmittance.r + transmittance.g + transmittance.b) / 3.0));
+    }
+}
+
+// Scatters the light of the volume into the opaque geometry already rendered, by its distance to the camera.
+@fragment
+fn apply(in: FullscreenVertexOutput) -> AerialPerspectiveOutput {
+    let depth = textureLoad(depth_texture, vec2<i32>(in.position.xy), 0);
+
+    var inscatter = vec3<f32>(0f);
+    var transmittance = vec4<f32>(1f);
+    // the sky already has its own
+    if depth > 0.0 {
+        let view_position = view.view_from_clip * vec4<f32>(uv_to_ndc(in.uv), depth, 1.0);
+        let distance = length(view_position.xyz / view_position.w) * aerial.scale;
+
+        // the texel of a slice holds the values at its far end
+        let slices = f32(textureDimensions(inscatter_volume).z);
+        let uvw = vec3<f32>(in.uv, saturate(distance / aerial.max_distance - 0.5 / slices));
+        // the first slice fades in from the camera
+        let fade = saturate(distance * slices / aerial.max_distance);
+
+        inscatter = textureSampleLevel(inscatter_volume, volume_sampler, uvw, 0.0).rgb * fade;
+        transmittance = mix(vec4<f32>(1f), textureSampleLevel(transmittance_volume, volume_sampler, uvw, 0.0), fade);
+    }
+
+#ifdef DUAL_SOURCE_BLENDING
+    return AerialPerspectiveOutput(vec4<f32>(inscatter, 0.0), vec4<f32>(transmittance.rgb, 1.0));
+#else
+    return AerialPerspectiveOutput(vec4<f32>(inscatter, transmittance.a));
+#endif
+}
@@ -0,0 +1,211 @@
+#import bevy_atmosphere::projection::{direction_texel, texel_direction}
+#import bevy_atmosphere::image::image
+#import bevy_atmosphere::slice::{in_slice, slice_texel}
+
+struct Clouds {
+    sun_direction: vec3<f32>,
+    sun_intensity: f32,
+    offset: vec2<f32>,
+    coverage: f32,
+    cumulus: f32,
+    bottom_height: f32,
+    top_height: f32,
+    density: f32,
+    size: f32,
+    planet_radius: f32,
+}
+
+const PI: f32 = 3.141592653589793;
+const STEPS: u32 = 32u;
+const LIGHT_STEPS: u32 = 4u;
+// Distance over which clouds fade into the sky near the horizon.
+const HAZE_DISTANCE: f32 = 60e3;
+// Rayleigh scattering coefficient and scale height of Earth, for reddening the sunlight.
+const RAYLEIGH: vec3<f32> = vec3<f32>(5.8e-6, 13.5e-6, 33.1e-6);
+const RAYLEIGH_SCALE_HEIGHT: f32 = 8e3;
+
+@group(0) @binding(0)
+var<uniform> clouds: Clouds;
+
+// The sky of the model, without clouds.
+@group(0) @binding(1)
+var sky: texture_2d_array<f32>;
+
+fn hash(v: u32) -> u32 {
+    let state = v * 747796405u + 2891336453u;
+    let word = ((state >> ((state >> 28u) + 4u)) ^ state) * 277803737u;
+    return (word >> 22u) ^ word;
+}
+
+fn hash_cell(cell: vec3<i32>) -> u32 {
+    return hash(bitcast<u32>(cell.x) ^ hash(bitcast<u32>(cell.y) ^ hash(bitcast<u32>(cell.z))));
+}
+
+// Maps a hash to [0, 1).
+fn unit(h: u32) -> f32 {
+    return f32(h >> 8u) / 16777216.0;
+}
+
+fn value_noise(p: vec3<f32>) -> f32 {
+    let cell = floor(p);
+    let f = p - cell;
+    let t = f * f * (3f - 2f * f);
+    let c = vec3<i32>(cell);
+
+    let x00 = mix(unit(hash_cell(c)), unit(hash_cell(c + vec3<i32>(1, 0, 0))), t.x);
+    let x10 = mix(unit(hash_cell(c + vec3<i32>(0, 1, 0))), unit(hash_cell(c + vec3<i32>(1, 1, 0))), t.x);
+    let x01 = mix(unit(hash_cell(c + vec3<i32>(0, 0, 1))), unit(hash_cell(c + vec3<i32>(1, 0, 1))), t.x);
+    let x11 = mix(unit(hash_cell(c + vec3<i32>(0, 1, 1))), unit(hash_cell(c + vec3<i32>(1, 1, 1))), t.x);
+
+    return mix(mix(x00, x10, t.y), mix(x01, x11, t.y), t.z);
+}
+
+fn fbm(p: vec3<f32>) -> f32 {
+    return 0.5 * value_noise(p) + 0.3 * value_noise(p * 2.03) + 0.2 * value_noise(p * 4.01);
+}
+
+// Distance from an observer at `height` looking along `mu` (cosine to the zenith) to the far side of the sphere at `shell`.
+// Written in terms of heights, as squaring planet-sized radii loses too much precision.
+fn shell_distance(height: f32, mu: f32, shell: f32) -> f32 {
+    let r = clouds.planet_radius + height;
+    let b = r * mu;
+    let c = (height - shell) * (2f * clouds.planet_radius + height + shell);
+    return -b + sqrt(max(b * b - c, 0f));
+}
+
+// Density of the clouds at `p`, relative to the center of the planet.
+fn cloud_density(p: vec3<f32>) -> f32 {
+    let thickness = clouds.top_height - clouds.bottom_height;
+    let h = (length(p) - clouds.planet_radius - clouds.bottom_height) / thickness;
+    if h <= 0f || h >= 1f {
+        return 0f;
+    }
+
+    // Coverage, from large scale noise on the horizontal plane.
+    let uv = (p.xz + clouds.offset) / clouds.size;
+    let weather = fbm(vec3<f32>(uv, 0f));
+    let threshold = mix(0.8, 0.2, clouds.coverage);
+    let shape = clamp((weather - threshold) / 0.25, 0f, 1f);
+    if shape <= 0f {
+        return 0f;
+    }
+
+    // Stratus form a thin sheet at the bottom of the layer, cumulus tower up where they are densest.
+    let stratus = smoothstep(0f, 0.1, h) * (1f - smoothstep(0.25, 0.4, h));
+    let cumulus = smoothstep(0f, 0.1, h) * (1f - smoothstep(0.4, 1f, h));
+    let profile = mix(stratus, cumulus, clouds.cumulus);
+    var d = clamp(shape + profile - 1f, 0f, 1f);
+    if d <= 0f {
+        return 0f;
+    }
+
+    // Detail noise erodes the edges, more so for billowing cumulus.
+    let detail = fbm(vec3<f32>(uv.x, h * thickness / clouds.size, uv.y) * 6f);
+    let erosion = 0.4 * mix(0.3, 1f, clouds.cumulus) * (1f - detail);
+    d = clamp((d - erosion) / (1f - erosion), 0f, 1f);
+
+    return d * clouds.density;
+}
+
+fn henyey_greenstein(mu: f32, g: f32) -> f32 {
+    let gg = g * g;
+    return (1f - gg) / (4f * PI * pow(1f + gg - 2f * g * mu, 1.5));
+}
+
+// Sunlight reaching the clouds, reddened by the air it passes through on the way.
+fn sunlight(sun: vec3<f32>) -> vec3<f32> {
+    let mu = max(sun.y, 0f);
+    let elevation = degrees(asin(mu));
+    // Kasten and Young's approximation of the relative air mass.
+    let air_mass = 1f / (mu + 0.50572 * pow(96.07995 - elevation, -1.6364));
+    let fade = smoothstep(-0.05, 0.02, sun.y);
+    return clouds.sun_intensity * exp(-RAYLEIGH * RAYLEIGH_SCALE_HEIGHT * air_mass) * fade;
+}
+
+// The color of the sky behind the clouds in `direction`.
+fn sky_color(direction: vec3<f32>, size: vec2<u32>, layers: u32) -> vec3<f32> {
+    let texel = direction_texel(direction, size, layers);
+    return textureLoad(sky, texel.xy, texel.z, 0).rgb;
+}
+
+// Light scattered towards the clouds by the rest of the sky.
+fn ambient_light(size: vec2<u32>, layers: u32) -> vec3<f32> {
+    let zenith = sky_color(vec3<f32>(0f, 1f, 0f), size, layers);
+    let horizon = (
+        sky_color(vec3<f32>(1f, 0f, 0f), size, layers) +
+        sky_color(vec3<f32>(-1f, 0f, 0f), size, layers) +
+        sky_color(vec3<f32>(0f, 0f, 1f), size, layers) +
+        sky_color(vec3<f32>(0f, 0f, -1f), size, layers)
+    ) / 4f;
+    return (zenith + horizon) / 2f;
+}
+
+@compute @workgroup_size(8, 8, 1)
+fn main(@builtin(global_invocation_id) invocation_id: vec3<u32>, @builtin(num_workgroups) num_workgroups: vec3<u32>) {
+    let size = textureDimensions(image);
+    if !in_slice(invocation_id, size) {
+        return;
+    }
+    let texel = slice_texel(invocation_id, size);
+
+    let layers = textureNumLayers(image);
+    let ray = texel_direction(texel, size, layers);
+
+    let r = normalize(ray);
+    let base = textureLoad(sky, texel.xy, texel.z, 0);
+
+    // The observer stands on the ground, so only rays above the horizon reach the clouds.
+    if r.y <= 0f || clouds.coverage <= 0f || clouds.density <= 0f {
+        textureStore(image, vec2<i32>(texel.xy), i32(texel.z), base);
+        return;
+    }
+
+    let origin = vec3<f32>(0f, clouds.planet_radius, 0f);
+    let start = shell_distance(0f, r.y, clouds.bottom_height);
+    let end = shell_distance(0f, r.y, clouds.top_height);
+    let step_length = (end - start) / f32(STEPS);
+
+    let sun = normalize(clouds.sun_direction);
+    let mu = dot(r, sun);
+    // Mostly forward scattering, with a little back scattering for the silver lining.
+    let phase = mix(henyey_greenstein(mu, -0.3), henyey_greenstein(mu, 0.6), 0.7);
+    let sun_light = sunlight(sun);
+    let ambient = ambient_light(size, layers);
+    let light_step = (clouds.top_height - clouds.bottom_height) * 0.15;
+
+    var transmittance = 1f;
+    var scattered = vec3<f32>(0f);
+
+    for (var i = 0u; i < STEPS; i++) {
+        let t = start + (f32(i) + 0.5) * step_length;
+        let p = origin + r * t;
+        let density = cloud_density(p);
+        if density <= 0f {
+            continue;
+        }
+
+        var optical_depth = 0f;
+        for (var j = 0u; j < LIGHT_STEPS; j++) {
+            optical_depth += cloud_density(p + sun * light_step * (f32(j) + 0.5)) * light_step;
+        }
+        // Beer's law, darkened at the edges facing the sun ("powder" effect).
+        let direct = exp(-optical_depth) * mix(1f, 1f - exp(-2f * optical_depth), 0.5);
+
+        let h = (length(p) - clouds.planet_radius - clouds.bottom_height) / (clouds.top_height - clouds.bottom_height);
+        let luminance = sun_light * direct * phase + ambient * mix(0.5, 1f, h);
+
+        // Integrates the scattering analytically over the step, which stays stable for dense clouds.
+        let step_transmittance = exp(-density * step_length);
+        scattered += transmittance * luminance * (1f - step_transmittance);
+        transmittance *= step_transmittance;
+
+        if transmittance < 0.01 {
+            break;
+        }
+    }
+
+    let fade = exp(-start / HAZE_DISTANCE);
+    let render = mix(base.rgb, base.rgb * transmittance + scattered, fade);
+
+    textureStore(image, vec2<i32>(texel.xy), i32(texel.z), vec4<f32>(render, 1.0));
+}
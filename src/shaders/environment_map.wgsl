@@ -0,0 +1,135 @@
+struct Filter {
+    roughness: f32,
+    sample_count: u32,
+}
+
+const PI: f32 = 3.141592653589793;
+
+@group(0) @binding(0)
+var sky_texture: texture_cube<f32>;
+@group(0) @binding(1)
+var sky_sampler: sampler;
+@group(0) @binding(2)
+var<uniform> settings: Filter;
+
+@group(1) @binding(0)
+var image: texture_storage_2d_array<rgba16float, write>;
+
+// Direction of the center of a texel, using the same face layout as the models.
+fn face_ray(invocation_id: vec3<u32>, size: u32) -> vec3<f32> {
+    let scale = f32(size) / 2f;
+
+    let dir = vec2<f32>(((f32(invocation_id.x) + 0.5) / scale) - 1f, ((f32(invocation_id.y) + 0.5) / scale) - 1f);
+
+    var ray: vec3<f32>;
+
+    switch invocation_id.z {
+        case 0u {
+            ray = vec3<f32>(1f, -dir.y, -dir.x); // +X
+        }
+        case 1u {
+            ray = vec3<f32>(-1f, -dir.y, dir.x);// -X
+        }
+        case 2u {
+            ray = vec3<f32>(dir.x, 1f, dir.y); // +Y
+        }
+        case 3u {
+            ray = vec3<f32>(dir.x, -1f, -dir.y);// -Y
+        }
+        case 4u {
+            ray = vec3<f32>(dir.x, -dir.y, 1f); // +Z
+        }
+        default: {
+            ray = vec3<f32>(-dir.x, -dir.y, -1f);// -Z
+        }
+    }
+
+    return normalize(ray);
+}
+
+fn hammersley(i: u32, n: u32) -> vec2<f32> {
+    return vec2<f32>(f32(i) / f32(n), f32(reverseBits(i)) * 2.3283064365386963e-10);
+}
+
+// Rotates a tangent space direction around the normal.
+fn to_world(h: vec3<f32>, n: vec3<f32>) -> vec3<f32> {
+    let up = select(vec3<f32>(1f, 0f, 0f), vec3<f32>(0f, 0f, 1f), abs(n.z) < 0.999);
+    let tangent = normalize(cross(up, n));
+    let bitangent = cross(n, tangent);
+    return normalize(tangent * h.x + bitangent * h.y + n * h.z);
+}
+
+fn importance_sample_ggx(xi: vec2<f32>, n: vec3<f32>, roughness: f32) -> vec3<f32> {
+    let a = roughness * roughness;
+    let phi = 2f * PI * xi.x;
+    let cos_theta = sqrt((1f - xi.y) / (1f + (a * a - 1f) * xi.y));
+    let sin_theta = sqrt(1f - cos_theta * cos_theta);
+    return to_world(vec3<f32>(cos(phi) * sin_theta, sin(phi) * sin_theta, cos_theta), n);
+}
+
+fn importance_sample_cosine(xi: vec2<f32>, n: vec3<f32>) -> vec3<f32> {
+    let phi = 2f * PI * xi.x;
+    let cos_theta = sqrt(1f - xi.y);
+    let sin_theta = sqrt(xi.y);
+    return to_world(vec3<f32>(cos(phi) * sin_theta, sin(phi) * sin_theta, cos_theta), n);
+}
+
+// Prefilters one mip level of the specular map with the GGX distribution (assumes N = V = R).
+@compute @workgroup_size(8, 8, 1)
+fn specular(@builtin(global_invocation_id) invocation_id: vec3<u32>) {
+    let size = textureDimensions(image).x;
+    if invocation_id.x >= size || invocation_id.y >= size {
+        return;
+    }
+
+    let n = face_ray(invocation_id, size);
+
+    var color = vec3<f32>(0f);
+    if settings.roughness == 0f {
+        color = textureSampleLevel(sky_texture, sky_sampler, n, 0f).rgb;
+    } else {
+        var weight = 0f;
+        for (var i = 0u; i < settings.sample_count; i++) {
+            let h = importance_sample_ggx(hammersley(i, settings.sample_count), n, settings.roughness);
+            let l = normalize(2f * dot(n, h) * h - n);
+            let n_dot_l = dot(n, l);
+            if n_dot_l > 0f {
+                color += textureSampleLevel(sky_texture, sky_sampler, l, 0f).rgb * n_dot_l;
+                weight += n_dot_l;
+            }
+        }
+        color /= max(weight, 1e-4);
+    }
+
+    textureStore(
+        image,
+        vec2<i32>(invocation_id.xy),
+        i32(invocation_id.z),
+        vec4<f32>(color, 1.0)
+    );
+}
+
+// Convolves the sky with a cosine lobe, giving irradiance divided by PI.
+@compute @workgroup_size(8, 8, 1)
+fn diffuse(@builtin(global_invocation_id) invocation_id: vec3<u32>) {
+    let size = textureDimensions(image).x;
+    if invocation_id.x >= size || invocation_id.y >= size {
+        return;
+    }
+
+    let n = face_ray(invocation_id, size);
+
+    var color = vec3<f32>(0f);
+    for (var i = 0u; i < settings.sample_count; i++) {
+        let l = importance_sample_cosine(hammersley(i, settings.sample_count), n);
+        color += textureSampleLevel(sky_texture, sky_sampler, l, 0f).rgb;
+    }
+    color /= f32(settings.sample_count);
+
+    textureStore(
+        image,
+        vec2<i32>(invocation_id.xy),
+        i32(invocation_id.z),
+        vec4<f32>(color, 1.0)
+    );
+}
@@ -0,0 +1,32 @@
+#import bevy_atmosphere::image::image
+#import bevy_atmosphere::slice::{in_slice, slice_texel}
+
+struct Fade {
+    factor: f32,
+}
+
+@group(0) @binding(0)
+var<uniform> fade: Fade;
+
+// The sky of the model faded out.
+@group(0) @binding(1)
+var from_sky: texture_2d_array<f32>;
+
+// The sky of the model faded in.
+@group(0) @binding(2)
+var to_sky: texture_2d_array<f32>;
+
+@compute @workgroup_size(8, 8, 1)
+fn main(@builtin(global_invocation_id) invocation_id: vec3<u32>) {
+    let size = textureDimensions(image);
+    if !in_slice(invocation_id, size) {
+        return;
+    }
+    let texel = slice_texel(invocation_id, size);
+
+    let faded_out = textureLoad(from_sky, texel.xy, texel.z, 0);
+    let faded_in = textureLoad(to_sky, texel.xy, texel.z, 0);
+    let render = mix(faded_out, faded_in, clamp(fade.factor, 0f, 1f));
+
+    textureStore(image, vec2<i32>(texel.xy), i32(texel.z), render);
+}
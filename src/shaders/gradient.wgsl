@@ -1,3 +1,6 @@
+#import bevy_atmosphere::projection::texel_direction
+#import bevy_atmosphere::image::image
+#import bevy_atmosphere::slice::{in_slice, slice_texel}
 
 struct Gradient {
     sky: vec4<f32>,
@@ -21,38 +24,15 @@ fn render_gradient(r: vec3<f32>, g: Gradient) -> vec3<f32> {
 @group(0) @binding(0)
 var<uniform> gradient: Gradient;
 
-@group(1) @binding(0)
-var image: texture_storage_2d_array<rgba16float, write>;
-
 @compute @workgroup_size(8, 8, 1)
 fn main(@builtin(global_invocation_id) invocation_id: vec3<u32>, @builtin(num_workgroups) num_workgroups: vec3<u32>) {
-    let size = textureDimensions(image).x;
-    let scale = f32(size)/2f;
-    
-    let dir = vec2<f32>((f32(invocation_id.x)/scale) - 1f, (f32(invocation_id.y)/scale) - 1f);
-
-    var ray: vec3<f32>;
-    
-    switch invocation_id.z {
-        case 0u {
-            ray = vec3<f32>(1f, -dir.y, -dir.x); // +X
-        }
-        case 1u {
-            ray = vec3<f32>(-1f, -dir.y, dir.x);// -X
-        }
-        case 2u {
-            ray = vec3<f32>(dir.x, 1f, dir.y); // +Y
-        }
-        case 3u {
-            ray = vec3<f32>(dir.x, -1f, -dir.y);// -Y
-        }
-        case 4u {
-            ray = vec3<f32>(dir.x, -dir.y, 1f); // +Z
-        }
-        default: {
-            ray = vec3<f32>(-dir.x, -dir.y, -1f);// -Z
-        }
+    let size = textureDimensions(image);
+    if !in_slice(invocation_id, size) {
+        return;
     }
+    let texel = slice_texel(invocation_id, size);
+
+    let ray = texel_direction(texel, size, textureNumLayers(image));
 
     let render = render_gradient(
         ray,
@@ -61,8 +41,8 @@ fn main(@builtin(global_invocation_id) invocation_id: vec3<u32>, @builtin(num_wo
 
     textureStore(
         image,
-        vec2<i32>(invocation_id.xy),
-        i32(invocation_id.z),
+        vec2<i32>(texel.xy),
+        i32(texel.z),
         vec4<f32>(render, 1.0)
     );
 }
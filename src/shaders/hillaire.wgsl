@@ -0,0 +1,328 @@
+#import bevy_atmosphere::projection::texel_direction
+#import bevy_atmosphere::image::image
+#import bevy_atmosphere::slice::{in_slice, slice_texel}
+
+struct Hillaire {
+    ray_origin: vec3<f32>,
+    sun_position: vec3<f32>,
+    sun_intensity: f32,
+    planet_radius: f32,
+    atmosphere_radius: f32,
+    rayleigh_scattering: vec3<f32>,
+    rayleigh_scale_height: f32,
+    mie_scattering: f32,
+    mie_absorption: f32,
+    mie_scale_height: f32,
+    mie_direction: f32,
+    ozone_absorption: vec3<f32>,
+    ozone_center_height: f32,
+    ozone_width: f32,
+    ground_albedo: vec3<f32>,
+}
+
+const PI: f32 = 3.141592653589793;
+const TRANSMITTANCE_STEPS: u32 = 40u;
+const MULTI_SCATTERING_STEPS: u32 = 20u;
+const MULTI_SCATTERING_SQRT_SAMPLES: u32 = 8u;
+const SKY_VIEW_STEPS: u32 = 32u;
+
+@group(0) @binding(0)
+var<uniform> hillaire: Hillaire;
+
+// Output of the current pass.
+@group(1) @binding(0)
+var lut: texture_storage_2d<rgba16float, write>;
+
+@group(2) @binding(0)
+var transmittance_lut: texture_2d<f32>;
+@group(2) @binding(1)
+var multi_scattering_lut: texture_2d<f32>;
+@group(2) @binding(2)
+var sky_view_lut: texture_2d<f32>;
+@group(2) @binding(3)
+var lut_sampler: sampler;
+
+// Distance along the ray to the sphere centered at the origin, or -1 when it misses.
+// From inside the sphere, the distance to the far side is returned.
+fn ray_sphere(ro: vec3<f32>, rd: vec3<f32>, radius: f32) -> f32 {
+    let b = dot(ro, rd);
+    let c = dot(ro, ro) - radius * radius;
+    if c > 0f && b > 0f { return -1f; }
+    let discriminant = b * b - c;
+    if discriminant < 0f { return -1f; }
+    if discriminant > b * b { return -b + sqrt(discriminant); }
+    return -b - sqrt(discriminant);
+}
+
+struct Medium {
+    rayleigh_scattering: vec3<f32>,
+    mie_scattering: f32,
+    extinction: vec3<f32>,
+}
+
+fn sample_medium(pos: vec3<f32>) -> Medium {
+    let height = length(pos) - hillaire.planet_radius;
+    let rayleigh_density = exp(-height / hillaire.rayleigh_scale_height);
+    let mie_density = exp(-height / hillaire.mie_scale_height);
+    // Ozone follows a tent profile around its center height.
+    let ozone_density = max(0f, 1f - abs(height - hillaire.ozone_center_height) / max(0.5 * hillaire.ozone_width, 1e-3));
+
+    var medium: Medium;
+    medium.rayleigh_scattering = hillaire.rayleigh_scattering * rayleigh_density;
+    medium.mie_scattering = hillaire.mie_scattering * mie_density;
+    medium.extinction = medium.rayleigh_scattering
+        + (hillaire.mie_scattering + hillaire.mie_absorption) * mie_density
+        + hillaire.ozone_absorption * ozone_density;
+    return medium;
+}
+
+fn rayleigh_phase(cos_theta: f32) -> f32 {
+    return 3f / (16f * PI) * (1f + cos_theta * cos_theta);
+}
+
+fn mie_phase(cos_theta: f32) -> f32 {
+    let g = hillaire.mie_direction;
+    let gg = g * g;
+    return 3f / (8f * PI) * ((1f - gg) * (1f + cos_theta * cos_theta)) / ((2f + gg) * pow(1f + gg - 2f * g * cos_theta, 1.5));
+}
+
+// Position and sun direction of a texel of the transmittance and multi-scattering tables,
+// which are indexed by the cosine of the sun's zenith angle and the height.
+fn lut_position(uv: vec2<f32>) -> vec3<f32> {
+    return vec3<f32>(0f, mix(hillaire.planet_radius, hillaire.atmosphere_radius, uv.y), 0f);
+}
+
+fn lut_sun_direction(uv: vec2<f32>) -> vec3<f32> {
+    let cos_zenith = 2f * uv.x - 1f;
+    return vec3<f32>(0f, cos_zenith, -sqrt(max(1f - cos_zenith * cos_zenith, 0f)));
+}
+
+fn lut_uv(pos: vec3<f32>, sun: vec3<f32>) -> vec2<f32> {
+    let height = length(pos);
+    let cos_zenith = dot(sun, pos / height);
+    return vec2<f32>(
+        clamp(0.5 + 0.5 * cos_zenith, 0f, 1f),
+        clamp((height - hillaire.planet_radius) / (hillaire.atmosphere_radius - hillaire.planet_radius), 0f, 1f),
+    );
+}
+
+fn sample_transmittance(pos: vec3<f32>, sun: vec3<f32>) -> vec3<f32> {
+    return textureSampleLevel(transmittance_lut, lut_sampler, lut_uv(pos, sun), 0f).rgb;
+}
+
+fn sample_multi_scattering(pos: vec3<f32>, sun: vec3<f32>) -> vec3<f32> {
+    return textureSampleLevel(multi_scattering_lut, lut_sampler, lut_uv(pos, sun), 0f).rgb;
+}
+
+fn texel_uv(invocation_id: vec3<u32>, size: vec2<u32>) -> vec2<f32> {
+    return (vec2<f32>(invocation_id.xy) + 0.5) / vec2<f32>(size);
+}
+
+// Transmittance from a point to the edge of the atmosphere.
+@compute @workgroup_size(8, 8, 1)
+fn transmittance(@builtin(global_invocation_id) invocation_id: vec3<u32>) {
+    let size = textureDimensions(lut);
+    if invocation_id.x >= size.x || invocation_id.y >= size.y {
+        return;
+    }
+
+    let uv = texel_uv(invocation_id, size);
+    let pos = lut_position(uv);
+    let sun = lut_sun_direction(uv);
+
+    var result = vec3<f32>(0f);
+    if ray_sphere(pos, sun, hillaire.planet_radius) < 0f {
+        let ray_length = ray_sphere(pos, sun, hillaire.atmosphere_radius);
+        var optical_depth = vec3<f32>(0f);
+        var t = 0f;
+        for (var i = 0u; i < TRANSMITTANCE_STEPS; i++) {
+            let new_t = ((f32(i) + 0.3) / f32(TRANSMITTANCE_STEPS)) * ray_length;
+            let dt = new_t - t;
+            t = new_t;
+            optical_depth += sample_medium(pos + t * sun).extinction * dt;
+        }
+        result = exp(-optical_depth);
+    }
+
+    textureStore(lut, vec2<i32>(invocation_id.xy), vec4<f32>(result, 1f));
+}
+
+// Light scattered any number of times, assuming it is isotropic (section 5.5 of the paper).
+@compute @workgroup_size(8, 8, 1)
+fn multi_scattering(@builtin(global_invocation_id) invocation_id: vec3<u32>) {
+    let size = textureDimensions(lut);
+    if invocation_id.x >= size.x || invocation_id.y >= size.y {
+        return;
+    }
+
+    let uv = texel_uv(invocation_id, size);
+    let pos = lut_position(uv);
+    let sun = lut_sun_direction(uv);
+
+    let isotropic_phase = 1f / (4f * PI);
+    let sample_weight = 1f / f32(MULTI_SCATTERING_SQRT_SAMPLES * MULTI_SCATTERING_SQRT_SAMPLES);
+
+    var second_order = vec3<f32>(0f);
+    var transfer = vec3<f32>(0f);
+
+    for (var i = 0u; i < MULTI_SCATTERING_SQRT_SAMPLES; i++) {
+        for (var j = 0u; j < MULTI_SCATTERING_SQRT_SAMPLES; j++) {
+            // Uniform directions on the sphere.
+            let theta = 2f * PI * (f32(i) + 0.5) / f32(MULTI_SCATTERING_SQRT_SAMPLES);
+            let phi = acos(clamp(1f - 2f * (f32(j) + 0.5) / f32(MULTI_SCATTERING_SQRT_SAMPLES), -1f, 1f));
+            let ray = vec3<f32>(sin(phi) * sin(theta), cos(phi), sin(phi) * cos(theta));
+
+            let atmosphere_distance = ray_sphere(pos, ray, hillaire.atmosphere_radius);
+            let ground_distance = ray_sphere(pos, ray, hillaire.planet_radius);
+            let ray_length = select(atmosphere_distance, ground_distance, ground_distance > 0f);
+
+            var luminance = vec3<f32>(0f);
+            var luminance_factor = vec3<f32>(0f);
+            var throughput = vec3<f32>(1f);
+            var t = 0f;
+            for (var k = 0u; k < MULTI_SCATTERING_STEPS; k++) {
+                let new_t = ((f32(k) + 0.3) / f32(MULTI_SCATTERING_STEPS)) * ray_length;
+                let dt = new_t - t;
+                t = new_t;
+                let sample_pos = pos + t * ray;
+
+                let medium = sample_medium(sample_pos);
+                let step_transmittance = exp(-dt * medium.extinction);
+                let scattering = medium.rayleigh_scattering + medium.mie_scattering;
+
+                // Integrated analytically over the step.
+                let scattering_integral = (scattering - scattering * step_transmittance) / medium.extinction;
+                luminance_factor += throughput * scattering_integral;
+
+                let in_scattering = scattering * isotropic_phase * sample_transmittance(sample_pos, sun);
+                luminance += throughput * (in_scattering - in_scattering * step_transmittance) / medium.extinction;
+
+                throughput *= step_transmittance;
+            }
+
+            // Sunlight reflected by the ground.
+            if ground_distance > 0f {
+                let ground = normalize(pos + ground_distance * ray) * hillaire.planet_radius;
+                let cos_sun = clamp(dot(normalize(ground), sun), 0f, 1f);
+                luminance += throughput * hillaire.ground_albedo / PI * cos_sun * sample_transmittance(ground, sun);
+            }
+
+            second_order += luminance * sample_weight;
+            transfer += luminance_factor * sample_weight;
+        }
+    }
+
+    // The geometric series of every order of scattering (equation 10 of the paper).
+    let psi = second_order / (1f - transfer);
+
+    textureStore(lut, vec2<i32>(invocation_id.xy), vec4<f32>(psi, 1f));
+}
+
+// The sky as seen from `ray_origin`, indexed by the azimuth relative to the sun and the altitude above the horizon.
+@compute @workgroup_size(8, 8, 1)
+fn sky_view(@builtin(global_invocation_id) invocation_id: vec3<u32>) {
+    let size = textureDimensions(lut);
+    if invocation_id.x >= size.x || invocation_id.y >= size.y {
+        return;
+    }
+
+    let uv = texel_uv(invocation_id, size);
+
+    let height = length(hillaire.ray_origin);
+    let up = hillaire.ray_origin / height;
+    let pos = vec3<f32>(0f, height, 0f);
+
+    // Concentrate texels around the horizon (section 5.3 of the paper).
+    let azimuth = (uv.x - 0.5) * 2f * PI;
+    var v: f32;
+    if uv.y < 0.5 {
+        let coord = 1f - 2f * uv.y;
+        v = -coord * coord;
+    } else {
+        let coord = 2f * uv.y - 1f;
+        v = coord * coord;
+    }
+    let horizon = acos(clamp(sqrt(max(height * height - hillaire.planet_radius * hillaire.planet_radius, 0f)) / height, -1f, 1f));
+    let altitude = v * 0.5 * PI + (0.5 * PI - horizon);
+    let ray = vec3<f32>(cos(altitude) * sin(azimuth), sin(altitude), -cos(altitude) * cos(azimuth));
+
+    let sun_altitude = 0.5 * PI - acos(clamp(dot(normalize(hillaire.sun_position), up), -1f, 1f));
+    let sun = vec3<f32>(0f, sin(sun_altitude), -cos(sun_altitude));
+
+    let atmosphere_distance = ray_sphere(pos, ray, hillaire.atmosphere_radius);
+    let ground_distance = ray_sphere(pos, ray, hillaire.planet_radius);
+    let ray_length = select(atmosphere_distance, ground_distance, ground_distance > 0f);
+
+    let cos_theta = dot(ray, sun);
+    let rayleigh = rayleigh_phase(cos_theta);
+    let mie = mie_phase(cos_theta);
+
+    var luminance = vec3<f32>(0f);
+    var throughput = vec3<f32>(1f);
+    var t = 0f;
+    for (var i = 0u; i < SKY_VIEW_STEPS; i++) {
+        let new_t = ((f32(i) + 0.3) / f32(SKY_VIEW_STEPS)) * ray_length;
+        let dt = new_t - t;
+        t = new_t;
+        let sample_pos = pos + t * ray;
+
+        let medium = sample_medium(sample_pos);
+        let step_transmittance = exp(-dt * medium.extinction);
+
+        let sun_transmittance = sample_transmittance(sample_pos, sun);
+        let psi = sample_multi_scattering(sample_pos, sun);
+
+        let in_scattering = medium.rayleigh_scattering * (rayleigh * sun_transmittance + psi)
+            + medium.mie_scattering * (mie * sun_transmittance + psi);
+
+        // Integrated analytically over the step.
+        luminance += throughput * (in_scattering - in_scattering * step_transmittance) / medium.extinction;
+        throughput *= step_transmittance;
+    }
+
+    textureStore(lut, vec2<i32>(invocation_id.xy), vec4<f32>(luminance, 1f));
+}
+
+fn render_hillaire(r: vec3<f32>) -> vec3<f32> {
+    let height = length(hillaire.ray_origin);
+    let up = hillaire.ray_origin / height;
+    let sun = normalize(hillaire.sun_position);
+
+    let horizon = acos(clamp(sqrt(max(height * height - hillaire.planet_radius * hillaire.planet_radius, 0f)) / height, -1f, 1f));
+    let altitude = horizon - acos(clamp(dot(r, up), -1f, 1f));
+
+    var azimuth = 0f;
+    if abs(altitude) < 0.5 * PI - 1e-4 {
+        let right = cross(sun, up);
+        let forward = cross(up, right);
+        let projected = r - up * dot(r, up);
+        azimuth = atan2(dot(projected, right), dot(projected, forward)) + PI;
+    }
+
+    let uv = vec2<f32>(
+        azimuth / (2f * PI),
+        0.5 + 0.5 * sign(altitude) * sqrt(abs(altitude) * 2f / PI),
+    );
+
+    return hillaire.sun_intensity * textureSampleLevel(sky_view_lut, lut_sampler, uv, 0f).rgb;
+}
+
+@compute @workgroup_size(8, 8, 1)
+fn main(@builtin(global_invocation_id) invocation_id: vec3<u32>) {
+    let size = textureDimensions(image);
+    if !in_slice(invocation_id, size) {
+        return;
+    }
+    let texel = slice_texel(invocation_id, size);
+
+    let ray = texel_direction(texel, size, textureNumLayers(image));
+
+    let render = render_hillaire(normalize(ray));
+
+    textureStore(
+        image,
+        vec2<i32>(texel.xy),
+        i32(texel.z),
+        vec4<f32>(render, 1.0)
+    );
+}
@@ -0,0 +1,15 @@
+#define_import_path bevy_atmosphere::image
+
+// The texture the last stage of a sky writes to, an `AtmosphereImage` or its back buffer.
+//
+// Its texel format follows the `AtmosphereFormat` of the images, told to the shader with the `ATMOSPHERE_FORMAT_*` shader defs.
+#ifdef ATMOSPHERE_FORMAT_RGBA32FLOAT
+@group(1) @binding(0)
+var image: texture_storage_2d_array<rgba32float, write>;
+#else ifdef ATMOSPHERE_FORMAT_RGBA8UNORM
+@group(1) @binding(0)
+var image: texture_storage_2d_array<rgba8unorm, write>;
+#else
+@group(1) @binding(0)
+var image: texture_storage_2d_array<rgba16float, write>;
+#endif
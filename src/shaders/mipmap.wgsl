@@ -0,0 +1,26 @@
+#import bevy_core_pipeline::fullscreen_vertex_shader::FullscreenVertexOutput
+
+// A single layer and mip of the texture copied from.
+@group(0) @binding(0)
+var source: texture_2d<f32>;
+
+// Copies the texel of the same size `source`, converting it to the format of the target.
+@fragment
+fn copy(in: FullscreenVertexOutput) -> @location(0) vec4<f32> {
+    return textureLoad(source, vec2<u32>(in.position.xy), 0);
+}
+
+// Averages the 2x2 texels of the previous mip of `source`.
+//
+// The texels are loaded rather than sampled, so formats that can't be filtered are downsampled as well.
+@fragment
+fn downsample(in: FullscreenVertexOutput) -> @location(0) vec4<f32> {
+    let last = textureDimensions(source) - 1u;
+    let texel = vec2<u32>(in.position.xy) * 2u;
+
+    let color = textureLoad(source, min(texel, last), 0)
+        + textureLoad(source, min(texel + vec2<u32>(1u, 0u), last), 0)
+        + textureLoad(source, min(texel + vec2<u32>(0u, 1u), last), 0)
+        + textureLoad(source, min(texel + vec2<u32>(1u, 1u), last), 0);
+    return color * 0.25;
+}
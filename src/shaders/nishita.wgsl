@@ -1,3 +1,6 @@
+#import bevy_atmosphere::projection::texel_direction
+#import bevy_atmosphere::image::image
+#import bevy_atmosphere::slice::{in_slice, slice_texel}
 
 struct Nishita {
     ray_origin: vec3<f32>,
@@ -10,12 +13,34 @@ struct Nishita {
     mie_coefficient: f32,
     mie_scale_height: f32,
     mie_direction: f32,
+    ozone_coefficient: vec3<f32>,
+    ozone_center_height: f32,
+    ozone_width: f32,
+    sun_angular_diameter: f32,
+    sun_disc_intensity: f32,
+    sun_limb_darkening: vec3<f32>,
+    moon_position: vec3<f32>,
+    moon_phase: f32,
+    moon_intensity: f32,
+    moon_disc_intensity: f32,
+    moon_angular_diameter: f32,
+    star_intensity: f32,
+    star_density: f32,
+    milky_way_intensity: f32,
 }
 
 const PI: f32 = 3.141592653589793;
 const ISTEPS: u32 = 16u;
 const JSTEPS: u32 = 8u;
 
+// Number of star cells along each axis of the unit cube.
+const STAR_CELLS: f32 = 100.0;
+// Angular radius of a star, in cells.
+const STAR_RADIUS: f32 = 0.25;
+// Normal of the plane of the Milky Way.
+const GALACTIC_POLE: vec3<f32> = vec3<f32>(-0.48, 0.6, 0.64);
+const MILKY_WAY_WIDTH: f32 = 0.12;
+
 fn rsi(rd: vec3<f32>, r0: vec3<f32>, sr: f32) -> vec2<f32> {
     // ray-sphere intersection that assumes
     // the sphere is centered at the origin.
@@ -35,7 +60,13 @@ fn rsi(rd: vec3<f32>, r0: vec3<f32>, sr: f32) -> vec2<f32> {
     }
 }
 
-fn render_nishita(r_full: vec3<f32>, r0: vec3<f32>, p_sun_full: vec3<f32>, i_sun: f32, r_planet: f32, r_atmos: f32, k_rlh: vec3<f32>, k_mie: f32, sh_rlh: f32, sh_mie: f32, g: f32) -> vec3<f32> {
+// Density of ozone, a tent profile around its center height.
+// A layer without width is kept from dividing by zero.
+fn ozone_density(height: f32, center: f32, width: f32) -> f32 {
+    return max(0f, 1f - abs(height - center) / max(0.5 * width, 1e-3));
+}
+
+fn render_nishita(r_full: vec3<f32>, r0: vec3<f32>, p_sun_full: vec3<f32>, i_sun: f32, r_planet: f32, r_atmos: f32, k_rlh: vec3<f32>, k_mie: f32, sh_rlh: f32, sh_mie: f32, g: f32, k_ozone: vec3<f32>, c_ozone: f32, w_ozone: f32) -> vec3<f32> {
     // Normalize the ray direction and sun position.
     let r = normalize(r_full);
     let p_sun = normalize(p_sun_full);
@@ -56,6 +87,7 @@ fn render_nishita(r_full: vec3<f32>, r0: vec3<f32>, p_sun_full: vec3<f32>, i_sun
     // Initialize optical depth accumulators for the primary ray.
     var i_od_rlh = 0f;
     var i_od_mie = 0f;
+    var i_od_ozone = 0f;
 
     // Calculate the Rayleigh and Mie phases.
     let mu = dot(r, p_sun);
@@ -75,10 +107,12 @@ fn render_nishita(r_full: vec3<f32>, r0: vec3<f32>, p_sun_full: vec3<f32>, i_sun
         // Calculate the optical depth of the Rayleigh and Mie scattering for this step.
         let od_step_rlh = exp(-i_height / sh_rlh) * i_step_size;
         let od_step_mie = exp(-i_height / sh_mie) * i_step_size;
+        let od_step_ozone = ozone_density(i_height, c_ozone, w_ozone) * i_step_size;
 
         // Accumulate optical depth.
         i_od_rlh += od_step_rlh;
         i_od_mie += od_step_mie;
+        i_od_ozone += od_step_ozone;
 
         // Calculate the step size of the secondary ray.
         let j_step_size = rsi(p_sun, i_pos, r_atmos).y / f32(JSTEPS);
@@ -89,6 +123,7 @@ fn render_nishita(r_full: vec3<f32>, r0: vec3<f32>, p_sun_full: vec3<f32>, i_sun
         // Initialize optical depth accumulators for the secondary ray.
         var j_od_rlh = 0f;
         var j_od_mie = 0f;
+        var j_od_ozone = 0f;
 
         // Sample the secondary ray.
         for (var j = 0u; j < JSTEPS; j++) {
@@ -102,13 +137,14 @@ fn render_nishita(r_full: vec3<f32>, r0: vec3<f32>, p_sun_full: vec3<f32>, i_sun
             // Accumulate the optical depth.
             j_od_rlh += exp(-j_height / sh_rlh) * j_step_size;
             j_od_mie += exp(-j_height / sh_mie) * j_step_size;
+            j_od_ozone += ozone_density(j_height, c_ozone, w_ozone) * j_step_size;
 
             // Increment the secondary ray depth.
             j_depth += j_step_size;
         }
 
         // Calculate attenuation.
-        let attn = exp(-(k_mie * (i_od_mie + j_od_mie) + k_rlh * (i_od_rlh + j_od_rlh)));
+        let attn = exp(-(k_mie * (i_od_mie + j_od_mie) + k_rlh * (i_od_rlh + j_od_rlh) + k_ozone * (i_od_ozone + j_od_ozone)));
 
         // Accumulate scattering.
         total_rlh += od_step_rlh * attn;
@@ -125,39 +161,213 @@ fn render_nishita(r_full: vec3<f32>, r0: vec3<f32>, p_sun_full: vec3<f32>, i_sun
 @group(0) @binding(0)
 var<uniform> nishita: Nishita;
 
-@group(1) @binding(0)
-var image: texture_storage_2d_array<rgba16float, write>;
+fn transmittance(r: vec3<f32>, r0: vec3<f32>, r_planet: f32, r_atmos: f32, k_rlh: vec3<f32>, k_mie: f32, sh_rlh: f32, sh_mie: f32, k_ozone: vec3<f32>, c_ozone: f32, w_ozone: f32) -> vec3<f32> {
+    // Nothing reaches the ray origin through the planet.
+    let planet = rsi(r, r0, r_planet);
+    if planet.x <= planet.y && planet.x > 0.0 { return vec3<f32>(0f); }
 
-@compute @workgroup_size(8, 8, 1)
-fn main(@builtin(global_invocation_id) invocation_id: vec3<u32>, @builtin(num_workgroups) num_workgroups: vec3<u32>) {
-    let size = textureDimensions(image).x;
-    let scale = f32(size) / 2f;
+    let step_size = max(rsi(r, r0, r_atmos).y, 0f) / f32(ISTEPS);
 
-    let dir = vec2<f32>((f32(invocation_id.x) / scale) - 1f, (f32(invocation_id.y) / scale) - 1f);
+    var od_rlh = 0f;
+    var od_mie = 0f;
+    var od_ozone = 0f;
 
-    var ray: vec3<f32>;
+    for (var i = 0u; i < ISTEPS; i++) {
+        let pos = r0 + r * (step_size * (f32(i) + 0.5));
+        let height = length(pos) - r_planet;
 
-    switch invocation_id.z {
-        case 0u {
-            ray = vec3<f32>(1f, -dir.y, -dir.x); // +X
-        }
-        case 1u {
-            ray = vec3<f32>(-1f, -dir.y, dir.x);// -X
-        }
-        case 2u {
-            ray = vec3<f32>(dir.x, 1f, dir.y); // +Y
-        }
-        case 3u {
-            ray = vec3<f32>(dir.x, -1f, -dir.y);// -Y
-        }
-        case 4u {
-            ray = vec3<f32>(dir.x, -dir.y, 1f); // +Z
-        }
-        default: {
-            ray = vec3<f32>(-dir.x, -dir.y, -1f);// -Z
+        od_rlh += exp(-height / sh_rlh) * step_size;
+        od_mie += exp(-height / sh_mie) * step_size;
+        od_ozone += ozone_density(height, c_ozone, w_ozone) * step_size;
+    }
+
+    return exp(-(k_mie * od_mie + k_rlh * od_rlh + k_ozone * od_ozone));
+}
+
+// PCG hash.
+fn hash(v: u32) -> u32 {
+    let state = v * 747796405u + 2891336453u;
+    let word = ((state >> ((state >> 28u) + 4u)) ^ state) * 277803737u;
+    return (word >> 22u) ^ word;
+}
+
+fn hash_cell(cell: vec3<i32>) -> u32 {
+    return hash(bitcast<u32>(cell.x) ^ hash(bitcast<u32>(cell.y) ^ hash(bitcast<u32>(cell.z))));
+}
+
+// Maps a hash to [0, 1).
+fn unit(h: u32) -> f32 {
+    return f32(h >> 8u) / 16777216.0;
+}
+
+fn value_noise(p: vec3<f32>) -> f32 {
+    let cell = floor(p);
+    let f = p - cell;
+    let t = f * f * (3f - 2f * f);
+    let c = vec3<i32>(cell);
+
+    let x00 = mix(unit(hash_cell(c)), unit(hash_cell(c + vec3<i32>(1, 0, 0))), t.x);
+    let x10 = mix(unit(hash_cell(c + vec3<i32>(0, 1, 0))), unit(hash_cell(c + vec3<i32>(1, 1, 0))), t.x);
+    let x01 = mix(unit(hash_cell(c + vec3<i32>(0, 0, 1))), unit(hash_cell(c + vec3<i32>(1, 0, 1))), t.x);
+    let x11 = mix(unit(hash_cell(c + vec3<i32>(0, 1, 1))), unit(hash_cell(c + vec3<i32>(1, 1, 1))), t.x);
+
+    return mix(mix(x00, x10, t.y), mix(x01, x11, t.y), t.z);
+}
+
+fn fbm(p: vec3<f32>) -> f32 {
+    return 0.5 * value_noise(p) + 0.3 * value_noise(p * 2.03) + 0.2 * value_noise(p * 4.01);
+}
+
+// Brightness of the Milky Way, a noisy band split by a dust lane.
+fn render_milky_way(r: vec3<f32>) -> f32 {
+    let latitude = dot(r, GALACTIC_POLE);
+    let band = exp(-latitude * latitude / (2f * MILKY_WAY_WIDTH * MILKY_WAY_WIDTH));
+    let dust = exp(-latitude * latitude / (2f * 0.02 * 0.02)) * fbm(r * 12f);
+    return band * (0.4 + 0.6 * fbm(r * 6f)) * (1f - 0.7 * dust);
+}
+
+// Procedural stars, with one star at most in each cell of a grid around the unit sphere.
+fn render_stars(r: vec3<f32>, density: f32) -> vec3<f32> {
+    let p = r * STAR_CELLS;
+    let base = vec3<i32>(floor(p));
+
+    var color = vec3<f32>(0f);
+    for (var z = -1; z <= 1; z++) {
+        for (var y = -1; y <= 1; y++) {
+            for (var x = -1; x <= 1; x++) {
+                let cell = base + vec3<i32>(x, y, z);
+                let h0 = hash_cell(cell);
+                if unit(h0) >= density { continue; }
+
+                let h1 = hash(h0);
+                let h2 = hash(h1);
+                let h3 = hash(h2);
+                let h4 = hash(h3);
+                let h5 = hash(h4);
+
+                // Place the star on the sphere, keeping it only if it stays in its cell.
+                let center = normalize(vec3<f32>(cell) + vec3<f32>(unit(h1), unit(h2), unit(h3))) * STAR_CELLS;
+                if any(vec3<i32>(floor(center)) != cell) { continue; }
+
+                let d = length(p - center) / STAR_RADIUS;
+                let brightness = pow(unit(h4), 12f) * exp(-0.5 * d * d);
+                let tint = mix(vec3<f32>(1.0, 0.75, 0.55), vec3<f32>(0.7, 0.8, 1.0), unit(h5));
+                color += tint * brightness;
+            }
         }
     }
 
+    return color;
+}
+
+// Shading of the moon disc, from 0 outside the disc to 1 where it is fully lit.
+fn render_moon(r: vec3<f32>, moon: vec3<f32>, phase: f32, angular_diameter: f32) -> f32 {
+    let radius = angular_diameter * 0.5;
+    let cos_angle = dot(r, moon);
+    if cos_angle < cos(radius) { return 0f; }
+
+    // Position on the disc, with z facing the viewer.
+    let up = select(vec3<f32>(0f, 1f, 0f), vec3<f32>(1f, 0f, 0f), abs(moon.y) > 0.999);
+    let tangent = normalize(cross(up, moon));
+    let bitangent = cross(moon, tangent);
+    let offset = (r - moon * cos_angle) / sin(radius);
+    let x = dot(offset, tangent);
+    let y = dot(offset, bitangent);
+    let normal = vec3<f32>(x, y, sqrt(max(1f - x * x - y * y, 0f)));
+
+    // The sun is behind the moon when new and behind the viewer when full.
+    let phase_angle = 2f * PI * phase;
+    let light = vec3<f32>(sin(phase_angle), 0f, -cos(phase_angle));
+
+    // Earthshine keeps the dark side faintly visible.
+    return max(dot(normal, light), 0f) + 0.02;
+}
+
+// Disc of the sun, darkened towards its limb.
+fn render_sun_disc(r: vec3<f32>, p_sun_full: vec3<f32>, angular_diameter: f32, intensity: f32, limb_darkening: vec3<f32>) -> vec3<f32> {
+    let radius = angular_diameter * 0.5;
+    let angle = acos(clamp(dot(r, normalize(p_sun_full)), -1f, 1f));
+    if angle >= radius { return vec3<f32>(0f); }
+
+    // Cosine between the surface of the sun and the view direction.
+    let x = angle / radius;
+    let mu = sqrt(1f - x * x);
+
+    return intensity * pow(vec3<f32>(mu), limb_darkening);
+}
+
+// Moon, stars and Milky Way, faded in as the sun sets.
+fn render_night(r: vec3<f32>, r0: vec3<f32>, p_sun_full: vec3<f32>, attn: vec3<f32>) -> vec3<f32> {
+    let night = 1f - smoothstep(-0.15, 0.05, dot(normalize(p_sun_full), normalize(r0)));
+
+    let p_moon = normalize(nishita.moon_position);
+    let moon = nishita.moon_disc_intensity * render_moon(r, p_moon, nishita.moon_phase, nishita.moon_angular_diameter);
+
+    var color = vec3<f32>(moon) * attn;
+
+    if night > 0f {
+        let milky_way = render_milky_way(r);
+        let stars = render_stars(r, nishita.star_density * (1f + 2f * milky_way));
+        color += night * attn * (nishita.star_intensity * stars + nishita.milky_way_intensity * milky_way * vec3<f32>(0.85, 0.88, 1.0));
+
+        // Moonlight scatters like a much weaker sun, depending on how much of the moon is lit.
+        let lit = 0.5 - 0.5 * cos(2f * PI * nishita.moon_phase);
+        color += night * render_nishita(
+            r,
+            r0,
+            p_moon,
+            nishita.moon_intensity * lit,
+            nishita.planet_radius,
+            nishita.atmosphere_radius,
+            nishita.rayleigh_coefficient,
+            nishita.mie_coefficient,
+            nishita.rayleigh_scale_height,
+            nishita.mie_scale_height,
+            nishita.mie_direction,
+            nishita.ozone_coefficient,
+            nishita.ozone_center_height,
+            nishita.ozone_width,
+        );
+    }
+
+    return color;
+}
+
+@compute @workgroup_size(8, 8, 1)
+fn main(@builtin(global_invocation_id) invocation_id: vec3<u32>, @builtin(num_workgroups) num_workgroups: vec3<u32>) {
+    let size = textureDimensions(image);
+    if !in_slice(invocation_id, size) {
+        return;
+    }
+    let texel = slice_texel(invocation_id, size);
+
+    let ray = texel_direction(texel, size, textureNumLayers(image));
+
+    let r = normalize(ray);
+
+    // Light from outside the atmosphere is attenuated by the same optical depth as the sky.
+    let attn = transmittance(
+        r,
+        nishita.ray_origin,
+        nishita.planet_radius,
+        nishita.atmosphere_radius,
+        nishita.rayleigh_coefficient,
+        nishita.mie_coefficient,
+        nishita.rayleigh_scale_height,
+        nishita.mie_scale_height,
+        nishita.ozone_coefficient,
+        nishita.ozone_center_height,
+        nishita.ozone_width,
+    );
+
+    let sun_disc = render_sun_disc(
+        r,
+        nishita.sun_position,
+        nishita.sun_angular_diameter,
+        nishita.sun_disc_intensity,
+        nishita.sun_limb_darkening,
+    );
+
     let render = render_nishita(
         ray,
         nishita.ray_origin,
@@ -170,12 +380,15 @@ fn main(@builtin(global_invocation_id) invocation_id: vec3<u32>, @builtin(num_wo
         nishita.rayleigh_scale_height,
         nishita.mie_scale_height,
         nishita.mie_direction,
-    );
+        nishita.ozone_coefficient,
+        nishita.ozone_center_height,
+        nishita.ozone_width,
+    ) + sun_disc * attn + render_night(r, nishita.ray_origin, nishita.sun_position, attn);
 
     textureStore(
         image,
-        vec2<i32>(invocation_id.xy),
-        i32(invocation_id.z),
+        vec2<i32>(texel.xy),
+        i32(texel.z),
         vec4<f32>(render, 1.0)
     );
 }
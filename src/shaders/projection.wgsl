@@ -0,0 +1,161 @@
+#define_import_path bevy_atmosphere::projection
+
+// Maps texels of an `AtmosphereImage` to directions of the sky, and back.
+//
+// The `AtmosphereProjection` is told apart by the layout of the texture,
+// so the same pipeline renders every projection:
+// 6 layers are a cubemap, a single layer twice as wide as it is high is equirectangular, and any other single layer is octahedral.
+
+const PI: f32 = 3.141592653589793;
+
+// The direction of the texel at `id` of a cubemap with `size` texels per side, `id.z` being the face.
+//
+// It isn't normalized.
+fn cube_direction(id: vec3<u32>, size: u32) -> vec3<f32> {
+    let scale = f32(size) / 2f;
+
+    let dir = vec2<f32>((f32(id.x) / scale) - 1f, (f32(id.y) / scale) - 1f);
+
+    switch id.z {
+        case 0u {
+            return vec3<f32>(1f, -dir.y, -dir.x); // +X
+        }
+        case 1u {
+            return vec3<f32>(-1f, -dir.y, dir.x);// -X
+        }
+        case 2u {
+            return vec3<f32>(dir.x, 1f, dir.y); // +Y
+        }
+        case 3u {
+            return vec3<f32>(dir.x, -1f, -dir.y);// -Y
+        }
+        case 4u {
+            return vec3<f32>(dir.x, -dir.y, 1f); // +Z
+        }
+        default: {
+            return vec3<f32>(-dir.x, -dir.y, -1f);// -Z
+        }
+    }
+}
+
+// The texel of a cubemap with `size` texels per side that `direction` points at, the inverse of `cube_direction`.
+fn cube_texel(direction: vec3<f32>, size: u32) -> vec3<u32> {
+    let a = abs(direction);
+
+    var face: u32;
+    var dir: vec2<f32>;
+    if a.x >= a.y && a.x >= a.z {
+        let q = direction / a.x;
+        if direction.x > 0f {
+            face = 0u;
+            dir = vec2<f32>(-q.z, -q.y);
+        } else {
+            face = 1u;
+            dir = vec2<f32>(q.z, -q.y);
+        }
+    } else if a.y >= a.z {
+        let q = direction / a.y;
+        if direction.y > 0f {
+            face = 2u;
+            dir = vec2<f32>(q.x, q.z);
+        } else {
+            face = 3u;
+            dir = vec2<f32>(q.x, -q.z);
+        }
+    } else {
+        let q = direction / a.z;
+        if direction.z > 0f {
+            face = 4u;
+            dir = vec2<f32>(q.x, -q.y);
+        } else {
+            face = 5u;
+            dir = vec2<f32>(-q.x, -q.y);
+        }
+    }
+
+    let texel = min(vec2<u32>((dir + 1f) * f32(size) / 2f), vec2<u32>(size - 1u));
+    return vec3<u32>(texel, face);
+}
+
+// The direction at `uv` of an equirectangular image.
+//
+// The center of the image looks along -Z, the left and right edges along +Z, and the top row straight up.
+fn equirectangular_direction(uv: vec2<f32>) -> vec3<f32> {
+    let longitude = (uv.x * 2f - 1f) * PI;
+    let latitude = (0.5 - uv.y) * PI;
+
+    return vec3<f32>(
+        cos(latitude) * sin(longitude),
+        sin(latitude),
+        -cos(latitude) * cos(longitude),
+    );
+}
+
+// The `uv` of an equirectangular image that `direction` points at, the inverse of `equirectangular_direction`.
+fn equirectangular_uv(direction: vec3<f32>) -> vec2<f32> {
+    let r = normalize(direction);
+    let longitude = atan2(r.x, -r.z);
+    let latitude = asin(clamp(r.y, -1f, 1f));
+
+    return vec2<f32>(longitude / (2f * PI) + 0.5, 0.5 - latitude / PI);
+}
+
+fn sign_not_zero(v: vec2<f32>) -> vec2<f32> {
+    return select(vec2<f32>(-1f), vec2<f32>(1f), v >= vec2<f32>(0f));
+}
+
+// The direction at `uv` of an octahedral image.
+//
+// The upper hemisphere is the diamond in the middle of the image, with the zenith at its center,
+// and the lower hemisphere is folded over the corners.
+fn octahedral_direction(uv: vec2<f32>) -> vec3<f32> {
+    let p = uv * 2f - 1f;
+    var r = vec3<f32>(p.x, 1f - abs(p.x) - abs(p.y), p.y);
+    if r.y < 0f {
+        let folded = (1f - abs(r.zx)) * sign_not_zero(r.xz);
+        r = vec3<f32>(folded.x, r.y, folded.y);
+    }
+
+    return normalize(r);
+}
+
+// The `uv` of an octahedral image that `direction` points at, the inverse of `octahedral_direction`.
+fn octahedral_uv(direction: vec3<f32>) -> vec2<f32> {
+    let r = direction / (abs(direction.x) + abs(direction.y) + abs(direction.z));
+    var p = r.xz;
+    if r.y < 0f {
+        p = (1f - abs(r.zx)) * sign_not_zero(r.xz);
+    }
+
+    return p * 0.5 + 0.5;
+}
+
+// The direction of the texel at `id` of an image of `size` texels with `layers` layers.
+//
+// It isn't normalized.
+fn texel_direction(id: vec3<u32>, size: vec2<u32>, layers: u32) -> vec3<f32> {
+    if layers == 6u {
+        return cube_direction(id, size.x);
+    }
+
+    let uv = (vec2<f32>(id.xy) + 0.5) / vec2<f32>(size);
+    if size.x == 2u * size.y {
+        return equirectangular_direction(uv);
+    }
+    return octahedral_direction(uv);
+}
+
+// The texel of an image of `size` texels with `layers` layers that `direction` points at, `z` being the layer.
+fn direction_texel(direction: vec3<f32>, size: vec2<u32>, layers: u32) -> vec3<u32> {
+    if layers == 6u {
+        return cube_texel(direction, size.x);
+    }
+
+    var uv: vec2<f32>;
+    if size.x == 2u * size.y {
+        uv = equirectangular_uv(direction);
+    } else {
+        uv = octahedral_uv(direction);
+    }
+    return vec3<u32>(min(vec2<u32>(uv * vec2<f32>(size)), size - 1u), 0u);
+}
@@ -11,17 +11,30 @@ fn dither(frag_coord: vec2<f32>) -> vec3<f32> {
 #endif
 
 #import bevy_pbr::forward_io::VertexOutput
+#import bevy_atmosphere::projection::{equirectangular_uv, octahedral_uv}
 
 @group(2) @binding(0)
 var sky_texture: texture_cube<f32>;
 @group(2) @binding(1)
 var sky_sampler: sampler;
+// The sky of the `Equirectangular` and `Octahedral` projections.
+@group(2) @binding(2)
+var sky_panorama: texture_2d<f32>;
 
 @fragment
 fn fragment(
     in: VertexOutput
 ) -> @location(0) vec4<f32> {
+#ifdef PROJECTION_EQUIRECTANGULAR
+    let uv = equirectangular_uv(in.world_normal);
+    // the seam of the longitude would pick the smallest mip
+    let color = textureSampleLevel(sky_panorama, sky_sampler, uv, 0f).xyz;
+#else ifdef PROJECTION_OCTAHEDRAL
+    let uv = octahedral_uv(in.world_normal);
+    let color = textureSampleLevel(sky_panorama, sky_sampler, uv, 0f).xyz;
+#else
     let color = textureSample(sky_texture, sky_sampler, in.world_normal).xyz;
+#endif
 #ifdef DITHER
     return vec4<f32>(color + dither(in.position.xy), 1f);
 #else
@@ -0,0 +1,24 @@
+#define_import_path bevy_atmosphere::slice
+
+// The rows of an `AtmosphereImage` rendered by a dispatch, counting the rows of every layer in order.
+struct Slice {
+    start: u32,
+    end: u32,
+}
+
+@group(1) @binding(1)
+var<uniform> atmosphere_slice: Slice;
+
+// Whether the invocation at `id` of a dispatch renders a texel of an image of `size` texels.
+//
+// A dispatch only covers the rows of its slice, `id.y` counting from its first row,
+// so the invocations past the end of the slice or the width of the image are skipped.
+fn in_slice(id: vec3<u32>, size: vec2<u32>) -> bool {
+    return id.x < size.x && atmosphere_slice.start + id.y < atmosphere_slice.end;
+}
+
+// The texel of an image of `size` texels rendered by the invocation at `id`, with its layer in `z`.
+fn slice_texel(id: vec3<u32>, size: vec2<u32>) -> vec3<u32> {
+    let row = atmosphere_slice.start + id.y;
+    return vec3<u32>(id.x, row % size.y, row / size.y);
+}
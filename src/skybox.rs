@@ -2,20 +2,32 @@
 
 use bevy::{
     asset::weak_handle,
+    ecs::system::{lifetimeless::SRes, SystemParamItem},
     pbr::{MaterialPipeline, MaterialPipelineKey},
     prelude::*,
     reflect::TypePath,
     render::{
         mesh::{Indices, Mesh, MeshVertexBufferLayoutRef, PrimitiveTopology},
-        render_resource::{AsBindGroup, RenderPipelineDescriptor, ShaderRef},
+        render_asset::RenderAssets,
+        render_resource::{
+            binding_types::{sampler, texture_2d, texture_cube},
+            AsBindGroup, AsBindGroupError, BindGroupLayout, BindGroupLayoutEntries,
+            BindGroupLayoutEntry, BindingResources, OwnedBindingResource, RenderPipelineDescriptor,
+            SamplerBindingType, ShaderDefVal, ShaderRef, ShaderStages, TextureSampleType,
+            TextureViewDimension, UnpreparedBindGroup,
+        },
+        renderer::RenderDevice,
+        texture::{FallbackImage, GpuImage},
     },
 };
 
-#[cfg(feature = "dithering")]
-use bevy::render::render_resource::ShaderDefVal;
+use crate::settings::AtmosphereProjection;
 
 /// The `Handle` for the created [`SkyBoxMaterial`].
-#[derive(Resource)]
+///
+/// As a `Resource`, it renders the global sky.
+/// As a `Component`, it renders the sky of the [`AtmosphereModel`](crate::model::AtmosphereModel) on the same entity.
+#[derive(Resource, Component, Debug, Clone)]
 pub struct AtmosphereSkyBoxMaterial(pub Handle<SkyBoxMaterial>);
 
 /// The `Handle` for the shader for the [`SkyBoxMaterial`].
@@ -23,13 +35,14 @@ pub const ATMOSPHERE_SKYBOX_SHADER_HANDLE: Handle<Shader> =
     weak_handle!("01968d2d-0491-76b2-af3d-eeda07e46ec2");
 
 /// The `Material` that renders skyboxes.
-#[derive(AsBindGroup, TypePath, Debug, Clone, Asset)]
-#[bind_group_data(SkyBoxMaterialKey)]
+#[derive(TypePath, Debug, Clone, Asset)]
 pub struct SkyBoxMaterial {
     /// [Handle] to the [AtmosphereImage](crate::pipeline::AtmosphereImage)
-    #[texture(0, dimension = "cube")]
-    #[sampler(1)]
     pub sky_texture: Handle<Image>,
+    /// The layout of the sky in `sky_texture`.
+    ///
+    /// See [`AtmosphereProjection`]
+    pub projection: AtmosphereProjection,
     #[cfg(feature = "dithering")]
     pub dithering: bool,
 }
@@ -37,10 +50,84 @@ pub struct SkyBoxMaterial {
 /// Bind group data for [`SkyBoxMaterial`]
 #[derive(Copy, Clone, Hash, Eq, PartialEq)]
 pub struct SkyBoxMaterialKey {
+    projection: AtmosphereProjection,
     #[cfg(feature = "dithering")]
     dithering: bool,
 }
 
+// Implemented by hand, since the sky is bound as a cube or as a 2D texture depending on its projection.
+// The binding the projection doesn't sample is given a fallback image.
+impl AsBindGroup for SkyBoxMaterial {
+    type Data = SkyBoxMaterialKey;
+    type Param = (SRes<RenderAssets<GpuImage>>, SRes<FallbackImage>);
+
+    fn label() -> Option<&'static str> {
+        Some("sky_box_material")
+    }
+
+    fn unprepared_bind_group(
+        &self,
+        _layout: &BindGroupLayout,
+        _render_device: &RenderDevice,
+        (gpu_images, fallback_image): &mut SystemParamItem<'_, '_, Self::Param>,
+        _force_no_bindless: bool,
+    ) -> Result<UnpreparedBindGroup<Self::Data>, AsBindGroupError> {
+        let image = gpu_images
+            .get(&self.sky_texture)
+            .ok_or(AsBindGroupError::RetryNextUpdate)?;
+
+        let (cube, panorama) = match self.projection.view_dimension() {
+            TextureViewDimension::Cube => (image, &fallback_image.d2),
+            _ => (&fallback_image.cube, image),
+        };
+
+        Ok(UnpreparedBindGroup {
+            bindings: BindingResources(vec![
+                (
+                    0,
+                    OwnedBindingResource::TextureView(
+                        TextureViewDimension::Cube,
+                        cube.texture_view.clone(),
+                    ),
+                ),
+                (
+                    1,
+                    OwnedBindingResource::Sampler(
+                        SamplerBindingType::Filtering,
+                        image.sampler.clone(),
+                    ),
+                ),
+                (
+                    2,
+                    OwnedBindingResource::TextureView(
+                        TextureViewDimension::D2,
+                        panorama.texture_view.clone(),
+                    ),
+                ),
+            ]),
+            data: self.into(),
+        })
+    }
+
+    fn bind_group_layout_entries(
+        _render_device: &RenderDevice,
+        _force_no_bindless: bool,
+    ) -> Vec<BindGroupLayoutEntry> {
+        BindGroupLayoutEntries::with_indices(
+            ShaderStages::FRAGMENT,
+            (
+                (
+                    0,
+                    texture_cube(TextureSampleType::Float { filterable: true }),
+                ),
+                (1, sampler(SamplerBindingType::Filtering)),
+                (2, texture_2d(TextureSampleType::Float { filterable: true })),
+            ),
+        )
+        .to_vec()
+    }
+}
+
 impl Material for SkyBoxMaterial {
     fn fragment_shader() -> ShaderRef {
         ATMOSPHERE_SKYBOX_SHADER_HANDLE.into()
@@ -48,13 +135,21 @@ impl Material for SkyBoxMaterial {
 
     fn specialize(
         _pipeline: &MaterialPipeline<Self>,
-        #[cfg_attr(not(feature = "dithering"), allow(unused_variables))]
         descriptor: &mut RenderPipelineDescriptor,
         _layout: &MeshVertexBufferLayoutRef,
-        #[cfg_attr(not(feature = "dithering"), allow(unused_variables))] key: MaterialPipelineKey<
-            Self,
-        >,
+        key: MaterialPipelineKey<Self>,
     ) -> Result<(), bevy::render::render_resource::SpecializedMeshPipelineError> {
+        let projection = match key.bind_group_data.projection {
+            AtmosphereProjection::Cubemap => None,
+            AtmosphereProjection::Equirectangular => Some("PROJECTION_EQUIRECTANGULAR"),
+            AtmosphereProjection::Octahedral => Some("PROJECTION_OCTAHEDRAL"),
+        };
+        if let (Some(projection), Some(fragment)) = (projection, &mut descriptor.fragment) {
+            fragment
+                .shader_defs
+                .push(ShaderDefVal::Bool(String::from(projection), true));
+        }
+
         #[cfg(feature = "dithering")]
         if key.bind_group_data.dithering {
             if let Some(fragment) = &mut descriptor.fragment {
@@ -69,10 +164,9 @@ impl Material for SkyBoxMaterial {
 }
 
 impl From<&SkyBoxMaterial> for SkyBoxMaterialKey {
-    fn from(
-        #[cfg_attr(not(feature = "dithering"), allow(unused_variables))] material: &SkyBoxMaterial,
-    ) -> SkyBoxMaterialKey {
+    fn from(material: &SkyBoxMaterial) -> SkyBoxMaterialKey {
         SkyBoxMaterialKey {
+            projection: material.projection,
             #[cfg(feature = "dithering")]
             dithering: material.dithering,
         }
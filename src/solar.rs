@@ -0,0 +1,520 @@
+//! Provides [`SolarClock`], a `Resource` that moves the sun of the [`Nishita`] model like the real one.
+//!
+//! The sun's position is calculated from a date, time and geographic location,
+//! using the [NOAA solar calculator](https://gml.noaa.gov/grad/solcalc/calcdetails.html) equations.
+//!
+//! ```no_run
+//! # use bevy::prelude::*;
+//! # use bevy_atmosphere::prelude::*;
+//! App::new()
+//!     .add_plugins((DefaultPlugins, AtmospherePlugin))
+//!     .insert_resource(
+//!         SolarClock {
+//!             // Tokyo, on the summer solstice
+//!             latitude: 35.6762,
+//!             longitude: 139.6503,
+//!             utc_offset: 9.0,
+//!             year: 2024,
+//!             month: 6,
+//!             day: 21,
+//!             time: 5.0,
+//!             ..default()
+//!         }
+//!         // a full day lasts 10 minutes
+//!         .with_day_length(600.0),
+//!     )
+//!     .run();
+//! ```
+
+use bevy::prelude::*;
+
+use crate::{collection::nishita::Nishita, system_param::OptionalAtmosphereMut};
+
+/// Hours in a day.
+const DAY: f64 = 24.0;
+
+/// Zenith angle of the sun at sunrise and sunset, in degrees.
+///
+/// Accounts for atmospheric refraction and the radius of the sun's disc.
+const SUNRISE_ZENITH: f64 = 90.833;
+
+/// A `Resource` that keeps the sun of the [`Nishita`] model at its real position in the sky.
+///
+/// While it exists, the clock advances with `Time` and writes the sun's direction to `sun_position`.
+/// The world is oriented with north towards `-Z`, east towards `+X` and up towards `+Y`.
+///
+/// Does nothing while the model isn't [`Nishita`].
+#[derive(Resource, Debug, Clone, Copy, PartialEq)]
+pub struct SolarClock {
+    /// Latitude in degrees, positive towards the north (Default: `51.4779`).
+    pub latitude: f64,
+    /// Longitude in degrees, positive towards the east (Default: `-0.0015`).
+    pub longitude: f64,
+    /// Offset of the local time zone from UTC in hours (Default: `0.0`).
+    pub utc_offset: f64,
+    /// Year of the date (Default: `2000`).
+    pub year: i32,
+    /// Month of the date, from `1` to `12` (Default: `3`).
+    pub month: u32,
+    /// Day of the month, starting at `1` (Default: `20`).
+    pub day: u32,
+    /// Local time of day in hours, from `0.0` to `24.0` (Default: `12.0`).
+    pub time: f64,
+    /// How many seconds pass on the clock for every real second (Default: `1.0`).
+    pub time_scale: f64,
+    /// Stops the clock from advancing, while still allowing it to be scrubbed (Default: `false`).
+    pub paused: bool,
+}
+
+impl Default for SolarClock {
+    /// Greenwich Observatory at noon on the March equinox of 2000.
+    fn default() -> Self {
+        Self {
+            latitude: 51.4779,
+            longitude: -0.0015,
+            utc_offset: 0.0,
+            year: 2000,
+            month: 3,
+            day: 20,
+            time: 12.0,
+            time_scale: 1.0,
+            paused: false,
+        }
+    }
+}
+
+/// Position of the sun in the sky.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SolarPosition {
+    /// Angle in degrees, clockwise from north.
+    pub azimuth: f64,
+    /// Angle in degrees above the horizon, negative when the sun has set.
+    pub elevation: f64,
+}
+
+impl SolarPosition {
+    /// The direction towards the sun, with north towards `-Z`, east towards `+X` and up towards `+Y`.
+    pub fn direction(&self) -> Vec3 {
+        let (azimuth, elevation) = (self.azimuth.to_radians(), self.elevation.to_radians());
+        Vec3::new(
+            (azimuth.sin() * elevation.cos()) as f32,
+            elevation.sin() as f32,
+            (-azimuth.cos() * elevation.cos()) as f32,
+        )
+    }
+}
+
+impl SolarClock {
+    /// Sets `time_scale` so a full day lasts `seconds` real seconds.
+    pub fn with_day_length(mut self, seconds: f64) -> Self {
+        self.set_day_length(seconds);
+        self
+    }
+
+    /// Sets `time_scale` so a full day lasts `seconds` real seconds.
+    pub fn set_day_length(&mut self, seconds: f64) {
+        self.time_scale = DAY * 3600.0 / seconds;
+    }
+
+    /// The number of real seconds a full day lasts.
+    pub fn day_length(&self) -> f64 {
+        DAY * 3600.0 / self.time_scale
+    }
+
+    /// Moves the clock by `hours`, rolling the date over when passing midnight.
+    ///
+    /// Negative values move the clock backwards.
+    pub fn advance(&mut self, hours: f64) {
+        let time = self.time + hours;
+        let days = (time / DAY).floor();
+        self.time = time - days * DAY;
+
+        if days != 0.0 {
+            let (year, month, day) =
+                civil_from_days(days_from_civil(self.year, self.month, self.day) + days as i64);
+            (self.year, self.month, self.day) = (year, month, day);
+        }
+    }
+
+    /// Calculates the position of the sun at the current date, time and location.
+    pub fn position(&self) -> SolarPosition {
+        let sun = Sun::new(self.julian_day(self.time));
+        let latitude = self.latitude.to_radians();
+
+        let true_solar_time = (self.time * 60.0 + sun.equation_of_time + 4.0 * self.longitude
+            - 60.0 * self.utc_offset)
+            .rem_euclid(1440.0);
+        let hour_angle = (true_solar_time / 4.0 - 180.0).to_radians();
+
+        let zenith = (latitude.sin() * sun.declination.sin()
+            + latitude.cos() * sun.declination.cos() * hour_angle.cos())
+        .clamp(-1.0, 1.0)
+        .acos();
+
+        let azimuth = {
+            let denominator = latitude.cos() * zenith.sin();
+            let angle = if denominator.abs() < 1e-9 {
+                0.0
+            } else {
+                ((latitude.sin() * zenith.cos() - sun.declination.sin()) / denominator)
+                    .clamp(-1.0, 1.0)
+                    .acos()
+                    .to_degrees()
+            };
+            if hour_angle > 0.0 {
+                (angle + 180.0).rem_euclid(360.0)
+            } else {
+                (540.0 - angle).rem_euclid(360.0)
+            }
+        };
+
+        SolarPosition {
+            azimuth,
+            elevation: 90.0 - zenith.to_degrees(),
+        }
+    }
+
+    /// Local time of sunrise in hours, or `None` during polar day or night.
+    pub fn sunrise(&self) -> Option<f64> {
+        self.sunrise_hour_angle()
+            .map(|hour_angle| self.solar_noon() - hour_angle / 15.0)
+    }
+
+    /// Local time of sunset in hours, or `None` during polar day or night.
+    pub fn sunset(&self) -> Option<f64> {
+        self.sunrise_hour_angle()
+            .map(|hour_angle| self.solar_noon() + hour_angle / 15.0)
+    }
+
+    /// Local time of solar noon in hours.
+    pub fn solar_noon(&self) -> f64 {
+        let sun = Sun::new(self.julian_day(12.0));
+        (720.0 - 4.0 * self.longitude - sun.equation_of_time + self.utc_offset * 60.0) / 60.0
+    }
+
+    /// Hour angle of sunrise in degrees.
+    fn sunrise_hour_angle(&self) -> Option<f64> {
+        let sun = Sun::new(self.julian_day(12.0));
+        let latitude = self.latitude.to_radians();
+        let cos_hour_angle = SUNRISE_ZENITH.to_radians().cos()
+            / (latitude.cos() * sun.declination.cos())
+            - latitude.tan() * sun.declination.tan();
+
+        (-1.0..=1.0)
+            .contains(&cos_hour_angle)
+            .then(|| cos_hour_angle.acos().to_degrees())
+    }
+
+    /// Julian day at a local time of the current date.
+    fn julian_day(&self, time: f64) -> f64 {
+        days_from_civil(self.year, self.month, self.day) as f64
+            + 2440587.5
+            + (time - self.utc_offset) / DAY
+    }
+}
+
+/// The parts of the sun's orbit needed to place it in the sky.
+struct Sun {
+    /// In radians.
+    declination: f64,
+    /// In minutes.
+    equation_of_time: f64,
+}
+
+impl Sun {
+    fn new(julian_day: f64) -> Self {
+        let jc = (julian_day - 2451545.0) / 36525.0;
+
+        let mean_longitude = (280.46646 + jc * (36000.76983 + jc * 0.0003032)).rem_euclid(360.0);
+        let mean_anomaly = 357.52911 + jc * (35999.05029 - 0.0001537 * jc);
+        let eccentricity = 0.016708634 - jc * (0.000042037 + 0.0000001267 * jc);
+
+        let equation_of_center = mean_anomaly.to_radians().sin()
+            * (1.914602 - jc * (0.004817 + 0.000014 * jc))
+            + (2.0 * mean_anomaly).to_radians().sin() * (0.019993 - 0.000101 * jc)
+            + (3.0 * mean_anomaly).to_radians().sin() * 0.000289;
+        let omega = (125.04 - 1934.136 * jc).to_radians();
+        let apparent_longitude =
+            mean_longitude + equation_of_center - 0.00569 - 0.00478 * omega.sin();
+
+        let mean_obliquity =
+            23.0 + (26.0 + (21.448 - jc * (46.815 + jc * (0.00059 - jc * 0.001813))) / 60.0) / 60.0;
+        let obliquity = (mean_obliquity + 0.00256 * omega.cos()).to_radians();
+
+        let declination = (obliquity.sin() * apparent_longitude.to_radians().sin()).asin();
+
+        let y = (obliquity / 2.0).tan().powi(2);
+        let (l, m) = (mean_longitude.to_radians(), mean_anomaly.to_radians());
+        let equation_of_time = 4.0
+            * (y * (2.0 * l).sin() - 2.0 * eccentricity * m.sin()
+                + 4.0 * eccentricity * y * m.sin() * (2.0 * l).cos()
+                - 0.5 * y * y * (4.0 * l).sin()
+                - 1.25 * eccentricity * eccentricity * (2.0 * m).sin())
+            .to_degrees();
+
+        Self {
+            declination,
+            equation_of_time,
+        }
+    }
+}
+
+/// Days since 1970-01-01 of a date in the proleptic Gregorian calendar.
+fn days_from_civil(year: i32, month: u32, day: u32) -> i64 {
+    let year = year as i64 - (month <= 2) as i64;
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month = month as i64;
+    let day_of_year = (153 * (month + if month > 2 { -3 } else { 9 }) + 2) / 5 + day as i64 - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146097 + day_of_era - 719468
+}
+
+/// Date in the proleptic Gregorian calendar of a number of days since 1970-01-01.
+fn civil_from_days(days: i64) -> (i32, u32, u32) {
+    let days = days + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days - era * 146097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + (month <= 2) as i64;
+    (year as i32, month as u32, day as u32)
+}
+
+/// Advances the [`SolarClock`] and moves the sun of the [`Nishita`] model.
+pub(crate) fn solar_clock(
+    time: Res<Time>,
+    clock: Option<ResMut<SolarClock>>,
+    mut atmosphere: OptionalAtmosphereMut<Nishita>,
+) {
+    let Some(mut clock) = clock else {
+        return;
+    };
+
+    if !clock.paused && clock.time_scale != 0.0 {
+        let hours = time.delta_secs_f64() * clock.time_scale / 3600.0;
+        clock.advance(hours);
+    }
+
+    let Some(atmosphere) = atmosphere.as_mut() else {
+        return;
+    };
+    // the model may have just become a `Nishita`
+    if clock.is_changed() || atmosphere.is_changed() {
+        atmosphere.set_field_if_neq("sun_position", clock.position().direction());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Sunrise and sunset in local time, as published for each city.
+    const TABLES: [(&str, f64, f64, f64, (i32, u32, u32), (f64, f64)); 4] = [
+        (
+            "London",
+            51.5074,
+            -0.1278,
+            1.0,
+            (2024, 6, 21),
+            (4.0 + 43.0 / 60.0, 21.0 + 21.0 / 60.0),
+        ),
+        (
+            "London",
+            51.5074,
+            -0.1278,
+            0.0,
+            (2024, 12, 21),
+            (8.0 + 3.0 / 60.0, 15.0 + 53.0 / 60.0),
+        ),
+        (
+            "Sydney",
+            -33.8688,
+            151.2093,
+            11.0,
+            (2024, 12, 21),
+            (5.0 + 41.0 / 60.0, 20.0 + 5.0 / 60.0),
+        ),
+        (
+            "Tokyo",
+            35.6762,
+            139.6503,
+            9.0,
+            (2024, 6, 21),
+            (4.0 + 25.0 / 60.0, 19.0 + 0.0 / 60.0),
+        ),
+    ];
+
+    /// Published times are rounded to the minute.
+    const TOLERANCE: f64 = 2.0 / 60.0;
+
+    fn clock(city: &(&str, f64, f64, f64, (i32, u32, u32), (f64, f64))) -> SolarClock {
+        let (_, latitude, longitude, utc_offset, (year, month, day), _) = *city;
+        SolarClock {
+            latitude,
+            longitude,
+            utc_offset,
+            year,
+            month,
+            day,
+            ..default()
+        }
+    }
+
+    #[test]
+    fn sunrise_and_sunset_match_tables() {
+        for city in &TABLES {
+            let clock = clock(city);
+            let (sunrise, sunset) = city.5;
+            let (actual_sunrise, actual_sunset) =
+                (clock.sunrise().unwrap(), clock.sunset().unwrap());
+            assert!(
+                (actual_sunrise - sunrise).abs() < TOLERANCE,
+                "{} sunrise: expected {sunrise}, got {actual_sunrise}",
+                city.0
+            );
+            assert!(
+                (actual_sunset - sunset).abs() < TOLERANCE,
+                "{} sunset: expected {sunset}, got {actual_sunset}",
+                city.0
+            );
+        }
+    }
+
+    #[test]
+    fn sun_is_on_the_horizon_at_sunrise_and_sunset() {
+        for city in &TABLES {
+            let mut clock = clock(city);
+            let (sunrise, sunset) = city.5;
+
+            clock.time = sunrise;
+            let morning = clock.position();
+            assert!((morning.elevation + 0.833).abs() < 0.5, "{}", city.0);
+            // rises in the east
+            assert!(
+                morning.azimuth > 0.0 && morning.azimuth < 180.0,
+                "{}",
+                city.0
+            );
+
+            clock.time = sunset;
+            let evening = clock.position();
+            assert!((evening.elevation + 0.833).abs() < 0.5, "{}", city.0);
+            // sets in the west
+            assert!(
+                evening.azimuth > 180.0 && evening.azimuth < 360.0,
+                "{}",
+                city.0
+            );
+        }
+    }
+
+    #[test]
+    fn sun_is_due_south_at_solar_noon_in_the_north() {
+        let mut clock = clock(&TABLES[0]);
+        clock.time = clock.solar_noon();
+        let noon = clock.position();
+        assert!((noon.azimuth - 180.0).abs() < 0.5);
+        // 90 - latitude + declination at the solstice
+        assert!((noon.elevation - (90.0 - 51.5074 + 23.44)).abs() < 0.1);
+    }
+
+    #[test]
+    fn polar_night_has_no_sunrise() {
+        let clock = SolarClock {
+            latitude: 78.2232,
+            longitude: 15.6267,
+            month: 12,
+            day: 21,
+            ..default()
+        };
+        assert_eq!(clock.sunrise(), None);
+        assert!(clock.position().elevation < 0.0);
+    }
+
+    #[test]
+    fn advance_rolls_the_date() {
+        let mut clock = SolarClock {
+            year: 2023,
+            month: 12,
+            day: 31,
+            time: 23.0,
+            ..default()
+        };
+        clock.advance(2.5);
+        assert_eq!((clock.year, clock.month, clock.day), (2024, 1, 1));
+        assert!((clock.time - 1.5).abs() < 1e-9);
+
+        clock.advance(-DAY * 365.0);
+        assert_eq!((clock.year, clock.month, clock.day), (2023, 1, 1));
+
+        let mut leap = SolarClock {
+            year: 2024,
+            month: 2,
+            day: 28,
+            time: 12.0,
+            ..default()
+        };
+        leap.advance(DAY);
+        assert_eq!((leap.year, leap.month, leap.day), (2024, 2, 29));
+    }
+
+    #[test]
+    fn day_length_sets_time_scale() {
+        let clock = SolarClock::default().with_day_length(600.0);
+        assert_eq!(clock.time_scale, 144.0);
+        assert_eq!(clock.day_length(), 600.0);
+    }
+
+    #[test]
+    fn direction_follows_compass() {
+        let east = SolarPosition {
+            azimuth: 90.0,
+            elevation: 0.0,
+        };
+        assert!(east.direction().abs_diff_eq(Vec3::X, 1e-6));
+        let north = SolarPosition {
+            azimuth: 0.0,
+            elevation: 0.0,
+        };
+        assert!(north.direction().abs_diff_eq(Vec3::NEG_Z, 1e-6));
+        let zenith = SolarPosition {
+            azimuth: 0.0,
+            elevation: 90.0,
+        };
+        assert!(zenith.direction().abs_diff_eq(Vec3::Y, 1e-6));
+    }
+
+    #[cfg(feature = "gradient")]
+    #[test]
+    fn other_models_are_left_unchanged() {
+        use crate::{collection::gradient::Gradient, model::AtmosphereModel};
+
+        let mut world = World::new();
+        world.init_resource::<Time>();
+        world.insert_resource(SolarClock::default());
+        world.insert_resource(AtmosphereModel::new(Gradient::default()));
+        world.run_system_cached(solar_clock).unwrap();
+        world.clear_trackers();
+
+        world.resource_mut::<SolarClock>().time = 6.0;
+        world.run_system_cached(solar_clock).unwrap();
+        assert!(!world.is_resource_changed::<AtmosphereModel>());
+
+        world.insert_resource(AtmosphereModel::new(Nishita::default()));
+        world.run_system_cached(solar_clock).unwrap();
+        let sun_position = world
+            .resource::<AtmosphereModel>()
+            .to_ref::<Nishita>()
+            .unwrap()
+            .sun_position;
+        assert_eq!(
+            sun_position,
+            world.resource::<SolarClock>().position().direction()
+        );
+    }
+}
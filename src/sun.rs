@@ -0,0 +1,108 @@
+//! Provides [`AtmosphereSun`], a `Component` for lighting a scene with the sun of the [`Nishita`] model.
+
+use bevy::{pbr::light_consts::lux, prelude::*};
+
+use crate::{collection::nishita::Nishita, model::AtmosphereModel};
+
+/// A `Component` that keeps a `DirectionalLight` in sync with the sun of the [`Nishita`] model.
+///
+/// The light is rotated to shine from `sun_position`,
+/// while its color and illuminance are attenuated by the atmosphere the same way the sky is.
+///
+/// Follows the global [`AtmosphereModel`] and does nothing while it isn't [`Nishita`].
+#[derive(Component, Debug, Clone, Copy)]
+#[require(DirectionalLight)]
+pub struct AtmosphereSun {
+    /// Illuminance of the sun before it enters the atmosphere (Default: `lux::AMBIENT_DAYLIGHT`).
+    pub illuminance: f32,
+}
+
+impl Default for AtmosphereSun {
+    fn default() -> Self {
+        Self {
+            illuminance: lux::AMBIENT_DAYLIGHT,
+        }
+    }
+}
+
+impl AtmosphereSun {
+    /// Calculates the color and illuminance of the sun as seen from `ray_origin`.
+    ///
+    /// The color is the transmittance towards the sun, scaled so its brightest channel is `1.0`.
+    pub fn light(&self, nishita: &Nishita) -> (Color, f32) {
+        let transmittance = nishita.transmittance(nishita.sun_position);
+        let max = transmittance.max_element();
+        if max <= 0.0 {
+            return (Color::BLACK, 0.0);
+        }
+
+        let color = transmittance / max;
+        (
+            Color::linear_rgb(color.x, color.y, color.z),
+            self.illuminance * max,
+        )
+    }
+}
+
+/// Updates the lights of [`AtmosphereSun`] entities when the model or the component changes.
+pub(crate) fn atmosphere_sun(
+    atmosphere: Option<Res<AtmosphereModel>>,
+    mut suns: Query<(Ref<AtmosphereSun>, &mut DirectionalLight, &mut Transform)>,
+) {
+    let Some(atmosphere) = atmosphere else {
+        return;
+    };
+    let Some(nishita) = atmosphere.to_ref::<Nishita>() else {
+        return;
+    };
+
+    for (sun, mut light, mut transform) in &mut suns {
+        if !atmosphere.is_changed() && !sun.is_changed() {
+            continue;
+        }
+
+        let (color, illuminance) = sun.light(nishita);
+        light.color = color;
+        light.illuminance = illuminance;
+        transform.look_to(-nishita.sun_position, Vec3::Y);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn noon_is_bright_and_white() {
+        let nishita = Nishita {
+            sun_position: Vec3::Y,
+            ..default()
+        };
+        let (color, illuminance) = AtmosphereSun::default().light(&nishita);
+        let color = color.to_linear();
+        assert_eq!(color.red, 1.0);
+        assert!(color.blue > 0.85);
+        assert!(illuminance > lux::AMBIENT_DAYLIGHT * 0.95);
+    }
+
+    #[test]
+    fn sunset_is_dim_and_red() {
+        let nishita = Nishita {
+            sun_position: Vec3::new(1.0, 0.02, 0.0),
+            ..default()
+        };
+        let (color, illuminance) = AtmosphereSun::default().light(&nishita);
+        let color = color.to_linear();
+        assert!(color.red > color.green && color.green > color.blue);
+        assert!(illuminance < lux::AMBIENT_DAYLIGHT * 0.5);
+    }
+
+    #[test]
+    fn night_is_dark() {
+        let nishita = Nishita {
+            sun_position: Vec3::NEG_Y,
+            ..default()
+        };
+        assert_eq!(AtmosphereSun::default().light(&nishita).1, 0.0);
+    }
+}
@@ -1,21 +1,73 @@
 //! Provides system params for easy reading/modifying of [`Atmospheric`] models.
+//!
+//! A system with an [`Atmosphere<T>`] or [`AtmosphereMut<T>`] param is skipped while the [`AtmosphereModel`] is another type of model.
+//! To run it anyway, use [`OptionalAtmosphere<T>`] or [`OptionalAtmosphereMut<T>`], which are `None` in that case
+//! (`Option<Atmosphere<T>>` can't be a system param of its own, `SystemParam` and `Option` both being foreign to this crate).
+//!
+//! Borrowing an [`AtmosphereMut<T>`] doesn't mark the model as changed, only writing to it does.
+//! Since a changed model is rendered again, prefer [`AtmosphereMut::set_if_neq`] or [`AtmosphereMut::set_field_if_neq`]
+//! for values that are written every frame but rarely differ.
 
 use std::ops::{Deref, DerefMut};
 
 use bevy::{
     ecs::{
         component::{ComponentId, Tick},
-        system::{ReadOnlySystemParam, SystemMeta, SystemParam},
+        system::{ReadOnlySystemParam, SystemMeta, SystemParam, SystemParamValidationError},
         world::unsafe_world_cell::UnsafeWorldCell,
     },
     prelude::*,
+    reflect::{ReflectMut, ReflectRef},
 };
 
 use crate::{model::Atmospheric, prelude::AtmosphereModel};
 
+/// Checks that the [`AtmosphereModel`] exists and is a `T`, skipping the system otherwise.
+///
+/// # Safety
+///
+/// The `AtmosphereModel` must be readable by the system, as with `Res::validate_param`.
+unsafe fn validate_model<P, T: Atmospheric>(
+    state: &ComponentId,
+    system_meta: &SystemMeta,
+    world: UnsafeWorldCell,
+) -> Result<(), SystemParamValidationError> {
+    // SAFETY: upheld by the caller
+    unsafe { <Res<AtmosphereModel> as SystemParam>::validate_param(state, system_meta, world) }?;
+
+    // SAFETY: only reads the resource, which the params register access to
+    let model = unsafe { world.get_resource::<AtmosphereModel>() };
+    if model.is_some_and(|model| model.to_ref::<T>().is_some()) {
+        Ok(())
+    } else {
+        Err(SystemParamValidationError::skipped::<P>(
+            "the `AtmosphereModel` is another type of model",
+        ))
+    }
+}
+
 /// Accessor for reading from an [`Atmospheric`] model.
 pub struct Atmosphere<'w, T: Atmospheric> {
-    value: &'w T,
+    value: Ref<'w, T>,
+}
+
+impl<T: Atmospheric> Atmosphere<'_, T> {
+    /// Returns `true` if the [`AtmosphereModel`] was added or changed since the system last ran.
+    ///
+    /// This includes it becoming a `T` after being another type of model.
+    pub fn is_changed(&self) -> bool {
+        self.value.is_changed()
+    }
+
+    /// Returns `true` if the [`AtmosphereModel`] was added since the system last ran.
+    pub fn is_added(&self) -> bool {
+        self.value.is_added()
+    }
+
+    /// The tick the [`AtmosphereModel`] was last changed at.
+    pub fn last_changed(&self) -> Tick {
+        self.value.last_changed()
+    }
 }
 
 // SAFETY: Res only reads a single World resource
@@ -25,7 +77,7 @@ impl<T: Atmospheric> Deref for Atmosphere<'_, T> {
     type Target = T;
 
     fn deref(&self) -> &Self::Target {
-        self.value
+        &self.value
     }
 }
 
@@ -37,6 +89,50 @@ unsafe impl<T: Atmospheric> SystemParam for Atmosphere<'_, T> {
         Res::<AtmosphereModel>::init_state(world, system_meta)
     }
 
+    #[inline]
+    unsafe fn validate_param(
+        state: &Self::State,
+        system_meta: &SystemMeta,
+        world: UnsafeWorldCell,
+    ) -> Result<(), SystemParamValidationError> {
+        validate_model::<Self, T>(state, system_meta, world)
+    }
+
+    #[inline]
+    unsafe fn get_param<'w, 's>(
+        state: &'s mut Self::State,
+        system_meta: &SystemMeta,
+        world: UnsafeWorldCell<'w>,
+        change_tick: Tick,
+    ) -> Self::Item<'w, 's> {
+        OptionalAtmosphere::<T>::get_param(state, system_meta, world, change_tick)
+            .0
+            .expect("Wrong type of `Atmospheric` model found")
+    }
+}
+
+/// Accessor for reading from an [`Atmospheric`] model, which is `None` while the [`AtmosphereModel`] is another type of model.
+pub struct OptionalAtmosphere<'w, T: Atmospheric>(pub Option<Atmosphere<'w, T>>);
+
+// SAFETY: Res only reads a single World resource
+unsafe impl<T: Atmospheric> ReadOnlySystemParam for OptionalAtmosphere<'_, T> {}
+
+impl<'w, T: Atmospheric> Deref for OptionalAtmosphere<'w, T> {
+    type Target = Option<Atmosphere<'w, T>>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+unsafe impl<T: Atmospheric> SystemParam for OptionalAtmosphere<'_, T> {
+    type State = ComponentId;
+    type Item<'w, 's> = OptionalAtmosphere<'w, T>;
+
+    fn init_state(world: &mut World, system_meta: &mut SystemMeta) -> Self::State {
+        Res::<AtmosphereModel>::init_state(world, system_meta)
+    }
+
     #[inline]
     unsafe fn get_param<'w, 's>(
         state: &'s mut Self::State,
@@ -44,36 +140,109 @@ unsafe impl<T: Atmospheric> SystemParam for Atmosphere<'_, T> {
         world: UnsafeWorldCell<'w>,
         change_tick: Tick,
     ) -> Self::Item<'w, 's> {
-        let atmosphere_model = <Res<AtmosphereModel> as SystemParam>::get_param(
+        let atmosphere_model = <Option<Res<AtmosphereModel>> as SystemParam>::get_param(
             state,
             system_meta,
             world,
             change_tick,
+        );
+        OptionalAtmosphere(
+            atmosphere_model
+                .map(Ref::from)
+                .filter(|atmosphere_model| atmosphere_model.to_ref::<T>().is_some())
+                .map(|atmosphere_model| Atmosphere {
+                    value: atmosphere_model.map(|model| model.to_ref::<T>().unwrap()),
+                }),
         )
-        .into_inner();
-        let value = atmosphere_model
-            .to_ref::<T>()
-            .expect("Wrong type of `Atmospheric` model found");
-        Self::Item { value }
     }
 }
 
 /// Accessor for writing to an [`Atmospheric`] model.
+///
+/// Like `ResMut`, mutably dereferencing it marks the [`AtmosphereModel`] as changed, which renders the sky again.
 pub struct AtmosphereMut<'w, T: Atmospheric> {
-    value: &'w mut T,
+    value: Mut<'w, T>,
+}
+
+impl<T: Atmospheric> AtmosphereMut<'_, T> {
+    /// Returns `true` if the [`AtmosphereModel`] was added or changed since the system last ran.
+    pub fn is_changed(&self) -> bool {
+        self.value.is_changed()
+    }
+
+    /// Returns `true` if the [`AtmosphereModel`] was added since the system last ran.
+    pub fn is_added(&self) -> bool {
+        self.value.is_added()
+    }
+
+    /// The tick the [`AtmosphereModel`] was last changed at.
+    pub fn last_changed(&self) -> Tick {
+        self.value.last_changed()
+    }
+
+    /// Marks the [`AtmosphereModel`] as changed.
+    pub fn set_changed(&mut self) {
+        self.value.set_changed();
+    }
+
+    /// Mutably borrows the model without marking it as changed, so the sky isn't rendered again.
+    pub fn bypass_change_detection(&mut self) -> &mut T {
+        self.value.bypass_change_detection()
+    }
+
+    /// Overwrites the model with `value` if they differ, comparing them field by field with reflection.
+    ///
+    /// Returns `true` if the model was changed.
+    pub fn set_if_neq(&mut self, value: T) -> bool {
+        let current: &T = &self.value;
+        if current.reflect_partial_eq(&value) == Some(true) {
+            return false;
+        }
+        *self.value = value;
+        true
+    }
+
+    /// Overwrites the field called `name` with `value` if they differ, comparing them with reflection.
+    ///
+    /// Returns `true` if the model was changed.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the model has no such field, or if `value` can't be applied to it.
+    pub fn set_field_if_neq(&mut self, name: &str, value: impl PartialReflect) -> bool {
+        let field = match self.value.reflect_ref() {
+            ReflectRef::Struct(current) => current.field(name),
+            _ => None,
+        };
+        let Some(field) = field else {
+            panic!(
+                "`{}` has no field `{name}`",
+                self.value.reflect_short_type_path()
+            );
+        };
+        if field.reflect_partial_eq(&value) == Some(true) {
+            return false;
+        }
+
+        let ReflectMut::Struct(model) = self.value.reflect_mut() else {
+            unreachable!()
+        };
+        model.field_mut(name).unwrap().apply(&value);
+        true
+    }
 }
 
 impl<T: Atmospheric> Deref for AtmosphereMut<'_, T> {
     type Target = T;
 
     fn deref(&self) -> &Self::Target {
-        self.value
+        &self.value
     }
 }
 
 impl<T: Atmospheric> DerefMut for AtmosphereMut<'_, T> {
     fn deref_mut(&mut self) -> &mut Self::Target {
-        self.value
+        &mut self.value
     }
 }
 
@@ -85,6 +254,53 @@ unsafe impl<T: Atmospheric> SystemParam for AtmosphereMut<'_, T> {
         ResMut::<AtmosphereModel>::init_state(world, system_meta)
     }
 
+    #[inline]
+    unsafe fn validate_param(
+        state: &Self::State,
+        system_meta: &SystemMeta,
+        world: UnsafeWorldCell,
+    ) -> Result<(), SystemParamValidationError> {
+        validate_model::<Self, T>(state, system_meta, world)
+    }
+
+    #[inline]
+    unsafe fn get_param<'w, 's>(
+        state: &'s mut Self::State,
+        system_meta: &SystemMeta,
+        world: UnsafeWorldCell<'w>,
+        change_tick: Tick,
+    ) -> Self::Item<'w, 's> {
+        OptionalAtmosphereMut::<T>::get_param(state, system_meta, world, change_tick)
+            .0
+            .expect("Wrong type of `Atmospheric` model found")
+    }
+}
+
+/// Accessor for writing to an [`Atmospheric`] model, which is `None` while the [`AtmosphereModel`] is another type of model.
+pub struct OptionalAtmosphereMut<'w, T: Atmospheric>(pub Option<AtmosphereMut<'w, T>>);
+
+impl<'w, T: Atmospheric> Deref for OptionalAtmosphereMut<'w, T> {
+    type Target = Option<AtmosphereMut<'w, T>>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<T: Atmospheric> DerefMut for OptionalAtmosphereMut<'_, T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+unsafe impl<T: Atmospheric> SystemParam for OptionalAtmosphereMut<'_, T> {
+    type State = ComponentId;
+    type Item<'w, 's> = OptionalAtmosphereMut<'w, T>;
+
+    fn init_state(world: &mut World, system_meta: &mut SystemMeta) -> Self::State {
+        ResMut::<AtmosphereModel>::init_state(world, system_meta)
+    }
+
     #[inline]
     unsafe fn get_param<'w, 's>(
         state: &'s mut Self::State,
@@ -92,16 +308,110 @@ unsafe impl<T: Atmospheric> SystemParam for AtmosphereMut<'_, T> {
         world: UnsafeWorldCell<'w>,
         change_tick: Tick,
     ) -> Self::Item<'w, 's> {
-        let atmosphere_model = <ResMut<AtmosphereModel> as SystemParam>::get_param(
+        let atmosphere_model = <Option<ResMut<AtmosphereModel>> as SystemParam>::get_param(
             state,
             system_meta,
             world,
             change_tick,
         )
-        .into_inner();
-        let value = atmosphere_model
-            .to_mut::<T>()
-            .expect("Wrong type of `Atmospheric` model found");
-        Self::Item { value }
+        // a model of another type is left alone
+        .filter(|atmosphere_model| atmosphere_model.to_ref::<T>().is_some());
+        // only writing to the model marks it as changed
+        OptionalAtmosphereMut(atmosphere_model.map(|atmosphere_model| AtmosphereMut {
+            value: atmosphere_model.map_unchanged(|model| model.to_mut::<T>().unwrap()),
+        }))
+    }
+}
+
+#[cfg(all(test, feature = "gradient", feature = "nishita"))]
+mod tests {
+    use super::*;
+    use crate::collection::{gradient::Gradient, nishita::Nishita};
+    use bevy::ecs::system::RunSystemOnce;
+
+    #[derive(Resource, Default)]
+    struct Reads(u32);
+
+    #[test]
+    fn mismatched_systems_are_skipped() {
+        let mut world = World::new();
+        world.insert_resource(AtmosphereModel::new(Gradient::default()));
+        world.init_resource::<Reads>();
+
+        world
+            .run_system_cached(|_: Atmosphere<Nishita>, mut reads: ResMut<Reads>| reads.0 += 1)
+            .unwrap_err();
+        world
+            .run_system_cached(|_: AtmosphereMut<Nishita>, mut reads: ResMut<Reads>| reads.0 += 1)
+            .unwrap_err();
+        world
+            .run_system_cached(|_: Atmosphere<Gradient>, mut reads: ResMut<Reads>| reads.0 += 1)
+            .unwrap();
+        assert_eq!(world.resource::<Reads>().0, 1);
+
+        let nishita = world
+            .run_system_cached(|atmosphere: OptionalAtmosphere<Nishita>| atmosphere.is_some())
+            .unwrap();
+        assert!(!nishita);
+        world
+            .run_system_cached(|mut atmosphere: OptionalAtmosphereMut<Gradient>| {
+                atmosphere.as_mut().unwrap().horizon = LinearRgba::RED;
+            })
+            .unwrap();
+        assert_eq!(
+            world
+                .resource::<AtmosphereModel>()
+                .to_ref::<Gradient>()
+                .unwrap()
+                .horizon,
+            LinearRgba::RED
+        );
+    }
+
+    #[test]
+    fn equal_writes_are_not_changes() {
+        let mut world = World::new();
+        world.insert_resource(AtmosphereModel::new(Gradient::default()));
+        fn changed<M>(world: &mut World, write: impl IntoSystem<(), (), M>) -> bool {
+            world.clear_trackers();
+            world.run_system_once(write).unwrap();
+            world.is_resource_changed::<AtmosphereModel>()
+        }
+
+        assert!(!changed(&mut world, |_: AtmosphereMut<Gradient>| {}));
+        assert!(!changed(
+            &mut world,
+            |mut atmosphere: AtmosphereMut<Gradient>| {
+                assert!(!atmosphere.set_field_if_neq("horizon", Gradient::default().horizon));
+                assert!(!atmosphere.set_if_neq(Gradient::default()));
+                atmosphere.bypass_change_detection().sky = LinearRgba::BLUE;
+            }
+        ));
+        assert!(changed(
+            &mut world,
+            |mut atmosphere: AtmosphereMut<Gradient>| {
+                assert!(atmosphere.set_field_if_neq("horizon", LinearRgba::RED));
+            }
+        ));
+        assert!(changed(
+            &mut world,
+            |mut atmosphere: AtmosphereMut<Gradient>| {
+                assert!(atmosphere.set_if_neq(Gradient::default()));
+            }
+        ));
+
+        let read = |world: &mut World| {
+            world
+                .run_system_cached(|atmosphere: Atmosphere<Gradient>| atmosphere.is_changed())
+                .unwrap()
+        };
+        assert!(read(&mut world), "the first run sees the model as changed");
+        assert!(!read(&mut world));
+        world
+            .run_system_cached(|mut atmosphere: AtmosphereMut<Gradient>| {
+                atmosphere.horizon = LinearRgba::RED
+            })
+            .unwrap();
+        assert!(read(&mut world));
     }
 }
@@ -0,0 +1,558 @@
+//! Provides [`AtmosphereTransition`], for smoothly animating an [`AtmosphereModel`] towards another state or another model.
+//!
+//! ```no_run
+//! # use std::time::Duration;
+//! # use bevy::prelude::*;
+//! # use bevy_atmosphere::prelude::*;
+//! fn storm(mut commands: Commands) {
+//!     let stormy = Nishita {
+//!         sun_intensity: 8.0,
+//!         mie_coefficient: 1e-4,
+//!         ..default()
+//!     };
+//!     commands.insert_resource(
+//!         AtmosphereTransition::new(stormy, Duration::from_secs(10))
+//!             .with_easing(EaseFunction::CubicInOut),
+//!     );
+//! }
+//! ```
+//!
+//! The fields are interpolated with [`AtmosphereModel::lerp`], which works for any model that derives `Reflect`.
+//!
+//! Models of different types can't be interpolated, so the sky cross-fades between them instead:
+//! the [`AtmosphereModel`] switches to the target right away, while the render world keeps rendering the previous model
+//! to a texture of its own and blends the two skies until the transition is finished.
+//! Both models are only rendered again when they change, and the textures of the previous model are released at the end.
+
+use std::time::Duration;
+
+use bevy::{
+    asset::weak_handle,
+    platform::collections::HashMap,
+    prelude::*,
+    render::{
+        render_resource::{
+            binding_types::{texture_2d_array, uniform_buffer},
+            BindGroup, BindGroupEntries, BindGroupLayout, BindGroupLayoutEntries, BufferId,
+            CachedComputePipelineId, ComputePass, ComputePipelineDescriptor, Extent3d,
+            PipelineCache, ShaderStages, ShaderType, TextureSampleType, TextureView, TextureViewId,
+            UniformBuffer,
+        },
+        renderer::{RenderDevice, RenderQueue},
+        Extract,
+    },
+};
+
+use crate::{
+    model::{AtmosphereModel, Atmospheric},
+    pipeline::{
+        atmosphere_stage_texture_descriptor, AtmosphereCached, AtmosphereDispatch, AtmosphereImage,
+        AtmosphereImageBindGroupLayout, AtmosphereImageBinding, AtmosphereImageFormat,
+        AtmosphereModelResources, AtmosphereSkies, AtmosphereSkyId, AtmosphereSkyModel,
+        AtmosphereUpdateEvent, ATMOSPHERE_ARRAY_TEXTURE_VIEW_DESCRIPTOR,
+    },
+    settings::AtmosphereSettings,
+};
+
+/// The `Handle` for the shader that blends the skies of a cross-fade.
+pub const ATMOSPHERE_FADE_SHADER_HANDLE: Handle<Shader> =
+    weak_handle!("019a1b52-8e3d-7f04-a6c1-3b7e9d2f5a48");
+
+/// Animates an [`AtmosphereModel`] from its current state to `target`.
+///
+/// As a `Resource`, it animates the global [`AtmosphereModel`].
+/// As a `Component`, it animates the [`AtmosphereModel`] on the same entity.
+///
+/// It is removed once the transition is finished.
+#[derive(Resource, Component, Clone)]
+pub struct AtmosphereTransition {
+    /// The model to animate towards.
+    pub target: AtmosphereModel,
+    /// How long the transition takes.
+    pub duration: Duration,
+    /// The easing applied to the progress of the transition (Default: `EaseFunction::SineInOut`).
+    pub easing: EaseFunction,
+    /// The model at the start of the transition, captured on its first update.
+    start: Option<AtmosphereModel>,
+    elapsed: Duration,
+}
+
+impl AtmosphereTransition {
+    /// Creates a transition towards `target` that takes `duration`.
+    pub fn new(target: impl Atmospheric + 'static, duration: Duration) -> Self {
+        Self::to_model(AtmosphereModel::new(target), duration)
+    }
+
+    /// Creates a transition towards the model of `target` that takes `duration`.
+    pub fn to_model(target: AtmosphereModel, duration: Duration) -> Self {
+        Self {
+            target,
+            duration,
+            easing: EaseFunction::SineInOut,
+            start: None,
+            elapsed: Duration::ZERO,
+        }
+    }
+
+    /// Sets the easing applied to the progress of the transition.
+    pub fn with_easing(mut self, easing: EaseFunction) -> Self {
+        self.easing = easing;
+        self
+    }
+
+    /// The progress of the transition, from `0.0` to `1.0`, before easing.
+    pub fn progress(&self) -> f32 {
+        if self.duration.is_zero() {
+            1.0
+        } else {
+            (self.elapsed.as_secs_f32() / self.duration.as_secs_f32()).min(1.0)
+        }
+    }
+
+    /// Returns `true` once the transition has reached its target.
+    pub fn is_finished(&self) -> bool {
+        self.elapsed >= self.duration
+    }
+
+    /// The model faded out and how far the target has faded in, from `0.0` to `1.0`,
+    /// while the transition cross-fades between models of different types.
+    pub fn cross_fade(&self) -> Option<(&AtmosphereModel, f32)> {
+        let start = self.start.as_ref()?;
+        let cross_fade = start.model().type_id() != self.target.model().type_id();
+        (cross_fade && !self.is_finished())
+            .then(|| (start, self.easing.sample_clamped(self.progress())))
+    }
+
+    /// Advances the transition by `delta` and returns the model at the new point in time,
+    /// or `None` if the model stays as it is.
+    fn tick(&mut self, model: &AtmosphereModel, delta: Duration) -> Option<AtmosphereModel> {
+        let started = self.start.is_some();
+        let start = self.start.get_or_insert_with(|| model.clone());
+        let cross_fade = start.model().type_id() != self.target.model().type_id();
+        self.elapsed += delta;
+
+        if cross_fade {
+            // the target renders from the start, and the render world fades it in
+            return (!started).then(|| self.target.clone());
+        }
+        if self.is_finished() {
+            return Some(self.target.clone());
+        }
+
+        let t = self.easing.sample_clamped(self.progress());
+        self.start
+            .as_ref()
+            .and_then(|start| start.lerp(&self.target, t))
+    }
+}
+
+/// Advances every [`AtmosphereTransition`] and removes those that finished.
+pub(crate) fn atmosphere_transition(
+    mut commands: Commands,
+    time: Res<Time>,
+    global_transition: Option<ResMut<AtmosphereTransition>>,
+    global_model: Option<ResMut<AtmosphereModel>>,
+    mut entity_transitions: Query<(Entity, &mut AtmosphereTransition, &mut AtmosphereModel)>,
+) {
+    if let (Some(mut transition), Some(mut model)) = (global_transition, global_model) {
+        if let Some(next) = transition.tick(&model, time.delta()) {
+            *model = next;
+        }
+        if transition.is_finished() {
+            commands.remove_resource::<AtmosphereTransition>();
+        }
+    }
+
+    for (entity, mut transition, mut model) in &mut entity_transitions {
+        if let Some(next) = transition.tick(&model, time.delta()) {
+            *model = next;
+        }
+        if transition.is_finished() {
+            commands.entity(entity).remove::<AtmosphereTransition>();
+        }
+    }
+}
+
+/// The `Fade` struct in `fade.wgsl`.
+#[derive(ShaderType, Debug, Clone, Copy, Default, PartialEq)]
+struct FadeUniform {
+    factor: f32,
+}
+
+/// The render world state of a cross-fade between the models of a single sky.
+pub(crate) struct AtmosphereSkyFade {
+    /// The model faded out, which renders to the first texture.
+    pub(crate) from: AtmosphereSkyModel,
+    /// How far the model of the sky has faded in.
+    factor: f32,
+    uniform: UniformBuffer<FadeUniform>,
+    /// The size and `TextureViewDimension::D2Array` views of the textures the faded out and faded in models render to.
+    textures: Option<(Extent3d, TextureView, TextureView)>,
+    /// The uniform and both textures.
+    bind_group: AtmosphereCached<(BufferId, TextureViewId, TextureViewId), BindGroup>,
+    /// The texture to blend into.
+    image_bind_group: AtmosphereCached<AtmosphereImageBinding, BindGroup>,
+    /// Set while the faded out model is waiting to be rendered.
+    from_dirty: bool,
+    /// Set when the faded out model has been rendered to the first texture.
+    from_rendered: bool,
+    /// Set when the model of the sky has been rendered to the second texture.
+    to_rendered: bool,
+    /// Set while the blend is waiting to be rendered.
+    dirty: bool,
+    /// Set when the faded out model is rendered during the current frame.
+    pub(crate) render_from: bool,
+    /// Set when the skies are blended during the current frame.
+    pub(crate) render: bool,
+}
+
+impl AtmosphereSkyFade {
+    fn new(from: AtmosphereModel, factor: f32) -> Self {
+        Self {
+            from: AtmosphereSkyModel::new(from),
+            factor,
+            uniform: UniformBuffer::default(),
+            textures: None,
+            bind_group: AtmosphereCached::default(),
+            image_bind_group: AtmosphereCached::default(),
+            from_dirty: true,
+            from_rendered: false,
+            to_rendered: false,
+            dirty: true,
+            render_from: false,
+            render: false,
+        }
+    }
+
+    /// The view of the texture the model of the sky renders to, once it is created.
+    pub(crate) fn target(&self) -> Option<&TextureView> {
+        self.textures.as_ref().map(|(_, _, to)| to)
+    }
+
+    /// Drops the textures and the bind groups of both models, which are created again when the fade is prepared next.
+    pub(crate) fn drop_resources(&mut self) {
+        self.from.drop_resources();
+        self.textures = None;
+        self.bind_group.clear();
+        self.image_bind_group.clear();
+    }
+
+    /// Decides whether the faded out model is rendered and the skies are blended this frame,
+    /// given whether the model of the sky was `rendered`.
+    ///
+    /// Returns `true` if the skies are blended, as the texture after the fade is rendered to.
+    pub(crate) fn queue(
+        &mut self,
+        rendered: bool,
+        pipeline_ready: bool,
+        pipeline_cache: &PipelineCache,
+    ) -> bool {
+        self.render_from = self.from_dirty && self.from.is_ready(pipeline_cache);
+        self.from_dirty &= !self.render_from;
+        self.from_rendered |= self.render_from;
+        self.to_rendered |= rendered;
+
+        self.render = pipeline_ready
+            && self.bind_group.get().is_some()
+            && self.image_bind_group.get().is_some()
+            && self.from_rendered
+            && self.to_rendered
+            && (self.dirty || self.render_from || rendered);
+        self.dirty &= !self.render;
+        self.render
+    }
+
+    /// Dispatches the blend of both skies.
+    pub(crate) fn render(
+        &self,
+        pass: &mut ComputePass,
+        pipeline_cache: &PipelineCache,
+        pipeline: &AtmosphereFadePipeline,
+        dispatch: &AtmosphereDispatch,
+    ) {
+        let (Some(fade), Some(image), Some(pipeline)) = (
+            self.bind_group.get(),
+            self.image_bind_group.get(),
+            pipeline_cache.get_compute_pipeline(pipeline.pipeline),
+        ) else {
+            return;
+        };
+        pass.set_bind_group(0, fade, &[]);
+        dispatch.set_image_bind_group(pass, image);
+        pass.set_pipeline(pipeline);
+        dispatch.dispatch(pass);
+    }
+}
+
+/// Extracts the cross-fades of [`AtmosphereTransition`]s from the main world into their skies.
+///
+/// A fade that starts or ends changes the texture the model of the sky renders to, so the sky is rendered again.
+#[allow(clippy::type_complexity)]
+pub(crate) fn extract_atmosphere_fades(
+    mut skies: ResMut<AtmosphereSkies>,
+    main_transition: Extract<Option<Res<AtmosphereTransition>>>,
+    main_entities: Extract<Query<(Entity, &AtmosphereTransition), With<AtmosphereImage>>>,
+) {
+    let mut extracted = HashMap::new();
+    if let Some(cross_fade) = main_transition
+        .as_deref()
+        .and_then(AtmosphereTransition::cross_fade)
+    {
+        extracted.insert(AtmosphereSkyId::Global, cross_fade);
+    }
+    for (entity, transition) in &main_entities {
+        if let Some(cross_fade) = transition.cross_fade() {
+            extracted.insert(AtmosphereSkyId::Entity(entity), cross_fade);
+        }
+    }
+
+    for (id, sky) in skies.0.iter_mut() {
+        match (extracted.get(id), &mut sky.fade) {
+            (Some(&(from, factor)), Some(fade))
+                if fade.from.model().model().type_id() == from.model().type_id() =>
+            {
+                fade.factor = factor;
+            }
+            (Some(&(from, factor)), _) => {
+                trace!("Starting cross-fade of sky {:?}", id);
+                sky.fade = Some(AtmosphereSkyFade::new(from.clone(), factor));
+                sky.cancel_update();
+                sky.changed = true;
+            }
+            (None, Some(_)) => {
+                // drops the textures of the faded out model
+                trace!("Finished cross-fade of sky {:?}", id);
+                sky.fade = None;
+                sky.cancel_update();
+                sky.changed = true;
+            }
+            (None, None) => {}
+        }
+    }
+}
+
+/// Creates the textures of skies that are fading, and writes the uniforms of the fades that progressed.
+pub(crate) fn prepare_atmosphere_fades(
+    mut skies: ResMut<AtmosphereSkies>,
+    mut update_events: ResMut<Events<AtmosphereUpdateEvent>>,
+    settings: Res<AtmosphereSettings>,
+    format: Res<AtmosphereImageFormat>,
+    resources: AtmosphereModelResources,
+    render_queue: Res<RenderQueue>,
+) {
+    let render_device = &resources.render_device;
+
+    for (id, sky) in skies.0.iter_mut() {
+        let updating = sky.updating();
+        let Some(fade) = &mut sky.fade else {
+            continue;
+        };
+
+        let size = settings.projection.size(settings.resolution);
+        if fade
+            .textures
+            .as_ref()
+            .is_none_or(|(textures_size, _, _)| *textures_size != size)
+        {
+            let texture = |label| {
+                render_device
+                    .create_texture(&atmosphere_stage_texture_descriptor(label, size, **format))
+                    .create_view(&ATMOSPHERE_ARRAY_TEXTURE_VIEW_DESCRIPTOR)
+            };
+            fade.textures = Some((
+                size,
+                texture("atmosphere_fade_from_texture"),
+                texture("atmosphere_fade_to_texture"),
+            ));
+            fade.from_dirty = true;
+            fade.from_rendered = false;
+            fade.to_rendered = false;
+            update_events.send(AtmosphereUpdateEvent(*id));
+        }
+
+        // every slice of an update blends with the same factor
+        if updating {
+            continue;
+        }
+
+        if fade.from.images_changed(&resources.gpu_images) {
+            fade.from_dirty = true;
+        }
+
+        let uniform = FadeUniform {
+            factor: fade.factor,
+        };
+        if fade.uniform.buffer().is_none() || *fade.uniform.get() != uniform {
+            fade.uniform.set(uniform);
+            render_device.write_uniform_buffer(&mut fade.uniform, &render_queue);
+            fade.dirty = true;
+        }
+    }
+}
+
+/// Prepares the faded out models, and creates the bind groups of the fades of skies whenever the buffers or textures they bind change.
+pub(crate) fn prepare_atmosphere_fade_bind_group(
+    mut skies: ResMut<AtmosphereSkies>,
+    resources: AtmosphereModelResources,
+    pipeline: Res<AtmosphereFadePipeline>,
+) {
+    for (id, sky) in skies.0.iter_mut() {
+        if sky.updating() {
+            continue;
+        }
+        let Some(output) = sky.clouds_base().cloned() else {
+            continue;
+        };
+        let Some(fade) = &mut sky.fade else {
+            continue;
+        };
+        let Some((_, from, to)) = &fade.textures else {
+            continue;
+        };
+
+        if fade.from_dirty {
+            fade.from.prepare(*id, from, &resources);
+        }
+
+        let (Some(uniform), Some(slice), Some(slice_buffer)) = (
+            fade.uniform.buffer(),
+            resources.slices.binding(),
+            resources.slices.buffer_id(),
+        ) else {
+            continue;
+        };
+
+        fade.bind_group
+            .get_or_create((uniform.id(), from.id(), to.id()), || {
+                resources.render_device.create_bind_group(
+                    "bevy_atmosphere_fade_bind_group",
+                    &pipeline.layout,
+                    &BindGroupEntries::sequential((uniform.as_entire_binding(), from, to)),
+                )
+            });
+        fade.image_bind_group
+            .get_or_create((output.id(), slice_buffer), || {
+                resources.render_device.create_bind_group(
+                    "bevy_atmosphere_fade_image_bind_group",
+                    &resources.image_bind_group_layout.0,
+                    &BindGroupEntries::sequential((&output, slice)),
+                )
+            });
+    }
+}
+
+/// The compute pipeline that blends the skies of a cross-fade.
+#[derive(Resource)]
+pub(crate) struct AtmosphereFadePipeline {
+    layout: BindGroupLayout,
+    pub(crate) pipeline: CachedComputePipelineId,
+}
+
+impl FromWorld for AtmosphereFadePipeline {
+    fn from_world(world: &mut World) -> Self {
+        let render_device = world.resource::<RenderDevice>();
+        let AtmosphereImageBindGroupLayout(image_bind_group_layout) =
+            world.resource::<AtmosphereImageBindGroupLayout>().clone();
+        let format = world.resource::<AtmosphereImageFormat>();
+
+        let layout = render_device.create_bind_group_layout(
+            "bevy_atmosphere_fade_bind_group_layout",
+            &BindGroupLayoutEntries::sequential(
+                ShaderStages::COMPUTE,
+                (
+                    uniform_buffer::<FadeUniform>(false),
+                    texture_2d_array(TextureSampleType::Float { filterable: false }),
+                    texture_2d_array(TextureSampleType::Float { filterable: false }),
+                ),
+            ),
+        );
+
+        let pipeline =
+            world
+                .resource::<PipelineCache>()
+                .queue_compute_pipeline(ComputePipelineDescriptor {
+                    label: Some("bevy_atmosphere_fade_pipeline".into()),
+                    layout: vec![layout.clone(), image_bind_group_layout],
+                    push_constant_ranges: vec![],
+                    shader: ATMOSPHERE_FADE_SHADER_HANDLE,
+                    shader_defs: format.shader_defs(),
+                    entry_point: "main".into(),
+                    zero_initialize_workgroup_memory: true,
+                });
+
+        Self { layout, pipeline }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(feature = "gradient")]
+    fn transition_reaches_its_target() {
+        use crate::collection::gradient::Gradient;
+        use bevy::time::TimeUpdateStrategy;
+
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_millis(
+                250,
+            )))
+            .insert_resource(AtmosphereModel::new(Gradient {
+                sky: LinearRgba::BLACK,
+                ..default()
+            }))
+            .insert_resource(
+                AtmosphereTransition::new(
+                    Gradient {
+                        sky: LinearRgba::WHITE,
+                        ..default()
+                    },
+                    Duration::from_secs(1),
+                )
+                .with_easing(EaseFunction::Linear),
+            )
+            .add_systems(Update, atmosphere_transition);
+
+        let mut previous = 0.0;
+        for _ in 0..10 {
+            app.update();
+            let sky = app
+                .world()
+                .resource::<AtmosphereModel>()
+                .to_ref::<Gradient>()
+                .unwrap()
+                .sky;
+            assert!(sky.red >= previous && sky.red <= 1.0);
+            assert_eq!(sky.red, sky.blue);
+            previous = sky.red;
+        }
+
+        assert_eq!(previous, 1.0);
+        assert!(app.world().get_resource::<AtmosphereTransition>().is_none());
+    }
+
+    #[test]
+    #[cfg(all(feature = "gradient", feature = "nishita"))]
+    fn transition_to_another_model_cross_fades() {
+        use crate::collection::{gradient::Gradient, nishita::Nishita};
+
+        let start = AtmosphereModel::new(Gradient::default());
+        let mut transition = AtmosphereTransition::new(Nishita::default(), Duration::from_secs(2))
+            .with_easing(EaseFunction::Linear);
+        assert!(transition.cross_fade().is_none());
+
+        // the target is rendered right away, and faded in by the render world
+        let model = transition.tick(&start, Duration::from_secs(1)).unwrap();
+        assert!(model.to_ref::<Nishita>().is_some());
+        let (from, factor) = transition.cross_fade().unwrap();
+        assert!(from.to_ref::<Gradient>().is_some());
+        assert_eq!(factor, 0.5);
+
+        // the model isn't touched again, so it is only rendered again if it changes
+        assert!(transition.tick(&model, Duration::from_secs(1)).is_none());
+        assert!(transition.is_finished());
+        assert!(transition.cross_fade().is_none());
+    }
+}
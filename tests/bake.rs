@@ -0,0 +1,81 @@
+//! Golden image tests for the skies baked on the CPU.
+//!
+//! Run with `BEVY_ATMOSPHERE_BLESS=1` to write the current output to `tests/golden` after an intended change.
+
+use std::path::{Path, PathBuf};
+
+use bevy::prelude::*;
+use bevy_atmosphere::{
+    bake::{bake_cubemap, bake_equirectangular, FACE_NAMES},
+    prelude::*,
+};
+use image::ImageFormat;
+
+fn golden_path(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/golden")
+        .join(name)
+}
+
+/// Compares `bytes` to the golden file `name`, channel by channel, with `close`.
+fn assert_golden(name: &str, bytes: &[u8], format: ImageFormat, close: impl Fn(f32, f32) -> bool) {
+    let path = golden_path(name);
+    if std::env::var_os("BEVY_ATMOSPHERE_BLESS").is_some() {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, bytes).unwrap();
+        return;
+    }
+
+    let golden = std::fs::read(&path)
+        .unwrap_or_else(|error| panic!("could not read {}: {error}", path.display()));
+    let decode = |bytes: &[u8]| {
+        image::load_from_memory_with_format(bytes, format)
+            .unwrap()
+            .into_rgba32f()
+    };
+    let (golden, actual) = (decode(&golden), decode(bytes));
+
+    assert_eq!(golden.dimensions(), actual.dimensions(), "{name}");
+    for (i, (g, a)) in golden.iter().zip(actual.iter()).enumerate() {
+        assert!(close(*g, *a), "{name}: value {i} is {a}, expected {g}");
+    }
+}
+
+fn assert_cubemap_golden(model_name: &str, model: AtmosphereModel) {
+    let sky = bake_cubemap(&model, 16).unwrap();
+    for (face, face_name) in FACE_NAMES.iter().enumerate() {
+        let mut bytes = Vec::new();
+        sky.write_png_face(face, &mut bytes).unwrap();
+        assert_golden(
+            &format!("{model_name}_{face_name}.png"),
+            &bytes,
+            ImageFormat::Png,
+            // one step of 8-bit precision
+            |golden, actual| (golden - actual).abs() <= 1.5 / 255.0,
+        );
+    }
+}
+
+#[test]
+fn gradient_cubemap() {
+    assert_cubemap_golden("gradient", AtmosphereModel::new(Gradient::default()));
+}
+
+#[test]
+fn nishita_cubemap() {
+    assert_cubemap_golden("nishita", AtmosphereModel::new(Nishita::default()));
+}
+
+#[test]
+fn nishita_equirectangular() {
+    let sky = bake_equirectangular(&AtmosphereModel::new(Nishita::default()), 64).unwrap();
+    let mut bytes = Vec::new();
+    sky.write_hdr(&mut bytes).unwrap();
+    assert_golden(
+        "nishita_equirectangular.hdr",
+        &bytes,
+        ImageFormat::Hdr,
+        // the shared exponent of RGBE keeps about two significant digits
+        |golden, actual| (golden - actual).abs() <= golden.abs() * 0.02 + 1e-4,
+    );
+}
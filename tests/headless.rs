@@ -0,0 +1,70 @@
+//! Tests for running `AtmospherePlugin` without a `RenderApp`, as on a dedicated server.
+//!
+//! They don't need a GPU.
+
+use std::time::Duration;
+
+use bevy::{prelude::*, time::TimeUpdateStrategy};
+use bevy_atmosphere::{model::ReflectAtmosphereModel, prelude::*};
+
+fn headless_app() -> App {
+    let mut app = App::new();
+    app.add_plugins((MinimalPlugins, AtmospherePlugin))
+        .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs(1)))
+        .insert_resource(AtmosphereModel::new(Nishita::default()));
+    app.finish();
+    app.cleanup();
+    app
+}
+
+#[test]
+fn models_are_registered() {
+    let app = headless_app();
+    let type_registry = app.world().resource::<AppTypeRegistry>().read();
+    let reflect_model = type_registry
+        .get_type_data::<ReflectAtmosphereModel>(std::any::TypeId::of::<Nishita>())
+        .expect("`Nishita` isn't registered");
+
+    let model = reflect_model.from_reflect(&Nishita::default()).unwrap();
+    assert!(model.to_ref::<Nishita>().is_some());
+}
+
+#[test]
+fn systems_write_the_model() {
+    let mut app = headless_app();
+    app.add_systems(Update, |mut atmosphere: AtmosphereMut<Nishita>| {
+        atmosphere.set_field_if_neq("sun_position", Vec3::Y);
+    });
+    app.update();
+
+    let model = app.world().resource::<AtmosphereModel>();
+    assert_eq!(model.to_ref::<Nishita>().unwrap().sun_position, Vec3::Y);
+}
+
+#[test]
+fn solar_clock_moves_the_sun() {
+    let mut app = headless_app();
+    app.insert_resource(SolarClock {
+        time: 6.0,
+        time_scale: 3600.0,
+        ..default()
+    });
+    app.update();
+    let sunrise = app
+        .world()
+        .resource::<AtmosphereModel>()
+        .to_ref::<Nishita>()
+        .unwrap()
+        .sun_position;
+
+    for _ in 0..6 {
+        app.update();
+    }
+    let noon = app
+        .world()
+        .resource::<AtmosphereModel>()
+        .to_ref::<Nishita>()
+        .unwrap()
+        .sun_position;
+    assert!(noon.y > sunrise.y);
+}